@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
+
+use crate::{
+    gadgets::checked_add_diff,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    sync::{RawEvent, ShroudEvent},
+    utils::fr_hex,
+    wasm::{diff_hash, history_step, parse_diffs, Account},
+    ASSET_SIZE,
+};
+
+const AUDIT_VERSION: u8 = 1;
+
+/// One note transition, as disclosed to an auditor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditTransition {
+    pub digest: String,
+    /// Index of `after_leaf` in the tree.
+    pub leaf_index: u64,
+    pub before: [u64; ASSET_SIZE],
+    pub diff: [i128; ASSET_SIZE],
+    pub after: [u64; ASSET_SIZE],
+    #[serde(with = "fr_hex")]
+    pub before_leaf: Fr,
+    #[serde(with = "fr_hex")]
+    pub after_leaf: Fr,
+    /// Nullifier of the before leaf, the genesis nullifier of the account for
+    /// the first note.
+    #[serde(with = "fr_hex")]
+    pub nullifier: Fr,
+    #[serde(with = "fr_hex")]
+    pub diff_hash: Fr,
+    /// Counter of the after note, i.e. this transition's position plus one.
+    pub counter: u64,
+    #[serde(with = "fr_hex")]
+    pub before_history: Fr,
+    #[serde(with = "fr_hex")]
+    pub after_history: Fr,
+}
+
+/// Full history of an account, in order. Discloses the viewing key only, so
+/// the auditor can check every note but not spend them.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditBundle {
+    version: u8,
+    #[serde(with = "fr_hex")]
+    address: Fr,
+    #[serde(with = "fr_hex")]
+    viewing_key: Fr,
+    note_type: u64,
+    transitions: Vec<AuditTransition>,
+}
+
+/// On-chain leaves and nullifiers the bundle is checked against, with the
+/// digest of the transaction that emitted each of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainSnapshot {
+    pub leafs: BTreeMap<u64, Fr>,
+    /// Transaction that inserted each leaf.
+    pub digests: BTreeMap<u64, String>,
+    /// Transaction that used each nullifier.
+    pub nullifiers: BTreeMap<Fr, String>,
+}
+
+impl ChainSnapshot {
+    /// Decodes raw `core` events of `package_id`, skipping the others.
+    pub fn decode(package_id: &str, events: &[RawEvent]) -> anyhow::Result<Self> {
+        let mut snapshot = Self::default();
+        for event in events {
+            let digest = event.id.tx_digest.clone();
+            match ShroudEvent::decode(package_id, event)? {
+                Some(ShroudEvent::LeafInserted(leaf)) => {
+                    snapshot.leafs.insert(leaf.index, leaf.value);
+                    snapshot.digests.insert(leaf.index, digest);
+                }
+                Some(ShroudEvent::NullifierUsed(used)) => {
+                    snapshot.nullifiers.insert(used.nullifier, digest);
+                }
+                _ => {}
+            }
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Result of a successful verification.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditSummary {
+    pub transitions: usize,
+    /// Balances of the latest note.
+    pub balance: [u64; ASSET_SIZE],
+    pub leaf_index: Option<u64>,
+    /// History root committed in the latest note.
+    #[serde(with = "fr_hex")]
+    pub history_root: Fr,
+}
+
+impl AuditBundle {
+    pub fn new(account: &Account) -> Self {
+        Self {
+            version: AUDIT_VERSION,
+            address: account.address_fr(),
+            viewing_key: account.viewing_key(),
+            note_type: account.note_type(),
+            transitions: vec![],
+        }
+    }
+
+    pub fn transitions(&self) -> &[AuditTransition] {
+        &self.transitions
+    }
+
+    /// Records a transition that was applied to `before` and landed at
+    /// `leaf_index` in transaction `digest`.
+    pub fn record(
+        &mut self,
+        before: &Account,
+        diff: [i128; ASSET_SIZE],
+        leaf_index: u64,
+        digest: &str,
+        hasher: &PoseidonHash,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            before.address_fr() == self.address
+                && before.viewing_key() == self.viewing_key
+                && before.note_type() == self.note_type,
+            "Account does not belong to this bundle"
+        );
+
+        let before_balance = before.balance();
+        let after = apply_diff(&before_balance, &diff)?;
+        let before_leaf = before.leaf(hasher);
+        let diff_hash = diff_hash(&diff.map(Fr::from), hasher);
+        let counter = before.counter() + 1;
+        let after_history = before.next_history_root(&diff_hash, hasher);
+
+        self.transitions.push(AuditTransition {
+            digest: digest.to_string(),
+            leaf_index,
+            before: before_balance,
+            diff,
+            after,
+            before_leaf,
+            after_leaf: before.leaf_of(&after, counter, &after_history, hasher),
+            nullifier: before.nullifier(hasher),
+            diff_hash,
+            counter,
+            before_history: before.history_root(),
+            after_history,
+        });
+
+        Ok(())
+    }
+
+    /// Recomputes every leaf, diff hash, history root and nullifier, checks
+    /// that the transitions chain into each other and that each one was carried
+    /// out by the transaction it cites. The first transition must spend the
+    /// genesis nullifier and the latest note must be unspent, so that with
+    /// every nullifier single use on chain no transition can be omitted.
+    pub fn verify(
+        &self,
+        snapshot: &ChainSnapshot,
+        hasher: &PoseidonHash,
+    ) -> anyhow::Result<AuditSummary> {
+        ensure!(
+            self.version == AUDIT_VERSION,
+            "Unsupported audit bundle version {}",
+            self.version
+        );
+        let account = Account::watch_only(self.address, self.viewing_key, self.note_type);
+        let mut prev: Option<&AuditTransition> = None;
+
+        for (i, t) in self.transitions.iter().enumerate() {
+            let fail = |reason: &str| anyhow!("Transition {} ({}): {}", i, t.digest, reason);
+
+            let (expected_before, expected_history) = match prev {
+                Some(p) => {
+                    if t.leaf_index <= p.leaf_index {
+                        return Err(fail("leaf index does not increase"));
+                    }
+                    (p.after, p.after_history)
+                }
+                None => ([0; ASSET_SIZE], Fr::ZERO),
+            };
+            if t.before != expected_before {
+                return Err(fail("before balances do not match the previous note"));
+            }
+            if t.counter != i as u64 + 1 || t.before_history != expected_history {
+                return Err(fail("history does not continue the previous note"));
+            }
+            if apply_diff(&t.before, &t.diff).ok() != Some(t.after) {
+                return Err(fail("after balances are not before + diff"));
+            }
+            if t.diff_hash != diff_hash(&t.diff.map(Fr::from), hasher) {
+                return Err(fail("invalid diff hash"));
+            }
+            if t.after_history != history_step(&t.before_history, &t.diff_hash, t.counter, hasher) {
+                return Err(fail("invalid history root"));
+            }
+            if t.before_leaf != account.leaf_of(&t.before, t.counter - 1, &t.before_history, hasher)
+            {
+                return Err(fail("invalid before leaf"));
+            }
+            if t.after_leaf != account.leaf_of(&t.after, t.counter, &t.after_history, hasher) {
+                return Err(fail("invalid after leaf"));
+            }
+            if t.nullifier != account.nullifier_of(&t.before_leaf, hasher) {
+                return Err(fail("invalid nullifier"));
+            }
+            if snapshot.leafs.get(&t.leaf_index) != Some(&t.after_leaf) {
+                return Err(fail("after leaf is not in the tree"));
+            }
+            if snapshot.digests.get(&t.leaf_index) != Some(&t.digest) {
+                return Err(fail("after leaf was not inserted by this transaction"));
+            }
+            if snapshot.nullifiers.get(&t.nullifier) != Some(&t.digest) {
+                return Err(fail("nullifier was not used by this transaction"));
+            }
+
+            prev = Some(t);
+        }
+
+        // the latest note, or the empty leaf without any, must be unspent
+        let latest_leaf = prev
+            .map(|t| t.after_leaf)
+            .unwrap_or_else(|| account.leaf_of(&[0; ASSET_SIZE], 0, &Fr::ZERO, hasher));
+        if let Some(digest) = snapshot
+            .nullifiers
+            .get(&account.nullifier_of(&latest_leaf, hasher))
+        {
+            bail!(
+                "Latest note was spent by transaction {} missing from the bundle",
+                digest
+            );
+        }
+
+        Ok(AuditSummary {
+            transitions: self.transitions.len(),
+            balance: prev.map(|t| t.after).unwrap_or([0; ASSET_SIZE]),
+            leaf_index: prev.map(|t| t.leaf_index),
+            history_root: prev.map(|t| t.after_history).unwrap_or(Fr::ZERO),
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl AuditBundle {
+    #[wasm_bindgen(constructor)]
+    pub fn wasm_new(account: &Account) -> Self {
+        Self::new(account)
+    }
+
+    #[wasm_bindgen(js_name = record)]
+    pub fn wasm_record(
+        &mut self,
+        before: &Account,
+        diffs: Vec<String>,
+        leaf_index: u64,
+        digest: String,
+    ) -> Result<(), JsError> {
+        parse_diffs(&diffs)
+            .and_then(|diff| {
+                self.record(
+                    before,
+                    diff,
+                    leaf_index,
+                    &digest,
+                    &PoseidonHash::new(poseidon_bn254()),
+                )
+            })
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn wasm_to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize bundle")
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn wasm_from_json(json: &str) -> Result<AuditBundle, JsError> {
+        serde_json::from_str(json).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Verifies the bundle against raw `core` events of `package_id`.
+    #[wasm_bindgen(js_name = verify)]
+    pub fn wasm_verify(&self, package_id: String, events: JsValue) -> Result<JsValue, JsError> {
+        let events: Vec<RawEvent> = from_value(events)?;
+        let summary = ChainSnapshot::decode(&package_id, &events)
+            .and_then(|snapshot| self.verify(&snapshot, &PoseidonHash::new(poseidon_bn254())))
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        Ok(to_value(&json!(summary))?)
+    }
+}
+
+fn apply_diff(
+    before: &[u64; ASSET_SIZE],
+    diff: &[i128; ASSET_SIZE],
+) -> anyhow::Result<[u64; ASSET_SIZE]> {
+    let mut after = [0; ASSET_SIZE];
+    for i in 0..ASSET_SIZE {
+        after[i] = checked_add_diff(before[i], diff[i])
+            .ok_or_else(|| anyhow!("Balance {} out of range", i))?;
+    }
+    Ok(after)
+}
@@ -0,0 +1,34 @@
+use std::fs;
+
+use anyhow::Context;
+use circuits_rust::{
+    audit::{AuditBundle, ChainSnapshot},
+    poseidon::{poseidon_bn254, PoseidonHash},
+    sync::RawEvent,
+};
+
+/// Usage: audit <package_id> <bundle.json> <events.json>
+///
+/// `events.json` is an array of raw `shroud::core` events, e.g. the
+/// concatenated `data` of every `queryEvents` page.
+pub fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, package_id, bundle_path, events_path] = args.as_slice() else {
+        anyhow::bail!("Usage: audit <package_id> <bundle.json> <events.json>");
+    };
+
+    let bundle: AuditBundle =
+        serde_json::from_str(&fs::read_to_string(bundle_path).context("Failed to read bundle")?)?;
+    let events: Vec<RawEvent> =
+        serde_json::from_str(&fs::read_to_string(events_path).context("Failed to read events")?)?;
+
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let summary = bundle.verify(&ChainSnapshot::decode(package_id, &events)?, &hasher)?;
+
+    println!("Transitions: {}", summary.transitions);
+    println!("Latest leaf index: {:?}", summary.leaf_index);
+    println!("Balances: {:?}", summary.balance);
+    println!("History is complete and consistent");
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::fs;
+
+use circuits_rust::conformance;
+
+/// Usage: conformance [output.json [conformance_test.move]]
+///
+/// Writes the conformance suite, to stdout without a path, and the Move test
+/// asserting it. The checked-in files are regenerated with
+/// `conformance conformance.json ../contracts/tests/conformance_test.move`.
+pub fn main() -> anyhow::Result<()> {
+    let suite = conformance::generate();
+    let json = serde_json::to_string_pretty(&suite)?;
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        Some(path) => fs::write(path, json + "\n")?,
+        None => println!("{json}"),
+    }
+    if let Some(path) = args.next() {
+        fs::write(path, conformance::move_test(&suite))?;
+    }
+
+    Ok(())
+}
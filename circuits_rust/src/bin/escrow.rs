@@ -0,0 +1,114 @@
+use std::{fs, io::Write};
+
+use anyhow::Context;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use circuits_rust::{
+    curve::Curve,
+    escrow::{AuditorKey, EscrowCiphertext},
+    poseidon::PoseidonHash,
+    sync::{RawEvent, ShroudEvent},
+};
+use rand::thread_rng;
+
+const KEY_PATH: &str = "auditor.key";
+
+/// Usage:
+///   escrow keygen [bn254|bls12381]
+///   escrow decrypt <auditor.key> <package_id> <events.json>
+///
+/// `keygen` writes a fresh auditor key on the embedded curve of the given
+/// pairing curve to `auditor.key`, readable by the owner only, and prints the
+/// public key to pass to `keygen`. The key file starts with the name of its
+/// curve, and keys for BLS12-381 are saved with a `bls12381.` prefix as
+/// `keygen` does. `decrypt` opens the ciphertext of every `Escrowed` event
+/// with the key file, on the curve it names.
+pub fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [_, "keygen"] => keygen::<Bn254>("")?,
+        [_, "keygen", Bn254::NAME] => keygen::<Bn254>("")?,
+        [_, "keygen", Bls12_381::NAME] => keygen::<Bls12_381>("bls12381.")?,
+        [_, "decrypt", key_path, package_id, events_path] => {
+            let key_file = fs::read_to_string(key_path).context("Failed to read auditor key")?;
+            let (curve, key_hex) = key_file
+                .trim()
+                .split_once(':')
+                .context("Auditor key without a curve")?;
+            let key_bytes = hex::decode(key_hex).context("Invalid key hex")?;
+            let events: Vec<RawEvent> = serde_json::from_str(
+                &fs::read_to_string(events_path).context("Failed to read events")?,
+            )?;
+
+            match curve {
+                Bn254::NAME => decrypt::<Bn254>(&key_bytes, package_id, &events)?,
+                Bls12_381::NAME => decrypt::<Bls12_381>(&key_bytes, package_id, &events)?,
+                _ => anyhow::bail!("Unknown curve {curve}, expected bn254 or bls12381"),
+            }
+        }
+        _ => anyhow::bail!(
+            "Usage: escrow keygen [bn254|bls12381] | escrow decrypt <auditor.key> <package_id> <events.json>"
+        ),
+    }
+
+    Ok(())
+}
+
+fn keygen<E: Curve>(prefix: &str) -> anyhow::Result<()> {
+    let key = AuditorKey::<E>::generate(&mut thread_rng());
+
+    let mut key_bytes = vec![];
+    key.serialize_compressed(&mut key_bytes)?;
+    let mut pk_bytes = vec![];
+    key.public_key().serialize_compressed(&mut pk_bytes)?;
+
+    // never overwrite an existing key, the escrows to it could not be opened
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let key_path = format!("{prefix}{KEY_PATH}");
+    options
+        .open(&key_path)
+        .with_context(|| format!("Failed to create {key_path}"))?
+        .write_all(format!("{}:{}", E::NAME, hex::encode(key_bytes)).as_bytes())?;
+
+    println!("Auditor key written to {key_path}");
+    println!("Auditor public key: {}", hex::encode(pk_bytes));
+
+    Ok(())
+}
+
+fn decrypt<E: Curve>(
+    key_bytes: &[u8],
+    package_id: &str,
+    events: &[RawEvent],
+) -> anyhow::Result<()> {
+    let key = AuditorKey::<E>::deserialize_compressed(key_bytes)?;
+    let hasher = PoseidonHash::new(E::poseidon());
+
+    for event in events {
+        let Some(ShroudEvent::Escrowed(escrowed)) = ShroudEvent::decode(package_id, event)? else {
+            continue;
+        };
+        let plaintext = EscrowCiphertext::<E>::from_bytes(&escrowed.ciphertext)
+            .and_then(|ciphertext| key.open(&ciphertext, &hasher))
+            .with_context(|| format!("Failed to open escrow of leaf {}", escrowed.index))?;
+        println!(
+            "Leaf {} ({}): address {}, diff {:?}",
+            escrowed.index,
+            event.id.tx_digest,
+            hex::encode(plaintext.address.into_bigint().to_bytes_be()),
+            plaintext.diff
+        );
+    }
+
+    Ok(())
+}
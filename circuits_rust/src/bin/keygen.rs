@@ -1,40 +1,79 @@
+use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
-use ark_crypto_primitives::snark::CircuitSpecificSetupSNARK;
-use ark_groth16::Groth16;
-use ark_serialize::CanonicalSerialize;
-use circuits_rust::poseidon::PoseidonHash;
-use circuits_rust::{poseidon::poseidon_bn254, Circuit};
-use hex;
+use ark_serialize::CanonicalDeserialize;
+use circuits_rust::{
+    curve::{Curve, EmbeddedAffine},
+    poseidon::PoseidonHash,
+    prover, Circuit, Multisig,
+};
 use rand::thread_rng;
 use std::fs::File;
 use std::io::Write;
 
+/// Usage: keygen [--curve bn254|bls12381] [--multisig] [auditor_public_key_hex]
+///
+/// With `--multisig`, the keys are for multisig notes and saved with a
+/// `multisig.` prefix. With an auditor key, every proof also escrows its diff
+/// to the auditor. Keys for BLS12-381 are saved with a `bls12381.` prefix and
+/// take a Jubjub auditor key. They are for native proving and verification
+/// only, the contracts verify BN254 proofs alone.
 pub fn main() -> anyhow::Result<()> {
-    let poseidon = PoseidonHash::new(poseidon_bn254());
-    let circuit = Circuit::empty(poseidon);
-    let (pk, vk) = Groth16::<Bn254>::setup(circuit, &mut thread_rng())?;
+    let mut args = std::env::args().skip(1).peekable();
+    let curve = match args.next_if(|a| a == "--curve") {
+        Some(_) => args
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing curve"))?,
+        None => Bn254::NAME.to_string(),
+    };
+    let multisig = args.next_if(|a| a == "--multisig").is_some();
+    let auditor = args.next();
 
-    // pk uncompressed
-    let mut pk_bytes = vec![];
-    pk.serialize_uncompressed(&mut pk_bytes)?;
+    match curve.as_str() {
+        Bn254::NAME => keygen::<Bn254>(multisig, auditor, ""),
+        Bls12_381::NAME => keygen::<Bls12_381>(multisig, auditor, "bls12381."),
+        _ => anyhow::bail!("Unknown curve {curve}, expected bn254 or bls12381"),
+    }
+}
+
+fn keygen<E: Curve>(multisig: bool, auditor: Option<String>, prefix: &str) -> anyhow::Result<()> {
+    let auditor = auditor
+        .map(|auditor| {
+            EmbeddedAffine::<E>::deserialize_compressed(
+                &hex::decode(auditor.trim_start_matches("0x"))?[..],
+            )
+            .map_err(anyhow::Error::from)
+        })
+        .transpose()?;
+
+    let poseidon = PoseidonHash::new(E::poseidon());
+    let mut circuit = Circuit::<E>::empty(poseidon.clone());
+    if let Some(auditor) = auditor {
+        circuit = circuit.with_escrow(auditor);
+    }
 
-    // vk compressed
-    let mut vk_bytes = vec![];
-    vk.serialize_compressed(&mut vk_bytes)?;
+    let (pk_bytes, vk_bytes, prefix) = if multisig {
+        let mut multisig = Multisig::<E>::empty(poseidon);
+        multisig.main = circuit;
+        let (pk, vk) = prover::setup::<E, _>(multisig, &mut thread_rng())?;
+        (pk, vk, format!("{prefix}multisig."))
+    } else {
+        let (pk, vk) = prover::setup::<E, _>(circuit, &mut thread_rng())?;
+        (pk, vk, prefix.to_string())
+    };
 
     println!("PK size: {} bytes", pk_bytes.len());
     println!("VK size: {} bytes", vk_bytes.len());
 
     // Save PK to file
-    let mut pk_file = File::create("pk.full.bin")?;
+    let mut pk_file = File::create(format!("{prefix}pk.full.bin"))?;
     pk_file.write_all(&pk_bytes)?;
 
     // Save VK as hex string
     let vk_hex = hex::encode(&vk_bytes);
-    let mut vk_hex_file = File::create("vk.hex.bin")?;
+    let mut vk_hex_file = File::create(format!("{prefix}vk.hex.bin"))?;
     vk_hex_file.write_all(vk_hex.as_bytes())?;
 
-    println!("Keys saved to pk.full.bin, vk.hex.bin");
+    println!("Keys saved to {prefix}pk.full.bin, {prefix}vk.hex.bin");
 
     Ok(())
 }
@@ -1,5 +1,6 @@
-use ark_bn254::Fr;
-use ark_ff::AdditiveGroup;
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ff::{AdditiveGroup, Field};
 use ark_r1cs_std::{
     fields::fp::FpVar,
     prelude::{AllocVar, Boolean, EqGadget, FieldVar},
@@ -8,124 +9,290 @@ use ark_relations::{
     ns,
     r1cs::{self, ConstraintSynthesizer, ConstraintSystemRef},
 };
+use ark_serialize::CanonicalSerialize;
 
 use crate::{
+    curve::{Curve, EmbeddedAffine},
+    escrow::{Escrow, EscrowVar},
+    gadgets::{hash_vector, UInt64Var},
+    hash::{Domain, TwoToOneHash, TwoToOneHashVar},
     merkle_tree::{Path, PathVar},
-    poseidon::{PoseidonHash, PoseidonHashVar},
+    poseidon::{PoseidonHash, DIFF_HASH_DOMAIN},
 };
 
+pub mod multisig;
+
+/// Note types, bound into every leaf so that a note can only be spent by the
+/// circuit of its type. Also the slot of that circuit's verifying key in
+/// `Shroud.keys`, which `core` selects by the note type it is passed.
+pub const SINGLE_NOTE: u64 = 0;
+pub const MULTISIG_NOTE: u64 = 1;
+
 #[derive(Debug, Clone)]
-pub struct MainCircuit<const L: usize, const N: usize> {
-    pub nonce: Fr,
-    pub before: [Fr; N],
-    pub diff: [Fr; N],
-    pub after: [Fr; N],
-    pub merkle_root: Fr, // public
-    pub merkle_path: Path<L>,
-    pub diff_hash: Fr,        // public
-    pub nullifier: Fr,        // public
-    pub after_leaf: Fr,       // public
-    pub hasher: PoseidonHash, // constant
-    pub address: Fr,
-    pub public_address: Fr, // public
-    pub aux: Fr,            // public
+pub struct MainCircuit<
+    const L: usize,
+    const N: usize,
+    E: Curve = Bn254,
+    H: TwoToOneHash<E::ScalarField> = PoseidonHash<<E as Pairing>::ScalarField>,
+> {
+    pub spending_key: E::ScalarField,
+    pub before: [E::ScalarField; N],
+    pub diff: [E::ScalarField; N],
+    pub after: [E::ScalarField; N],
+    pub history: E::ScalarField,     // history root of the before leaf
+    pub counter: E::ScalarField,     // transition counter of the before leaf
+    pub merkle_root: E::ScalarField, // public
+    pub merkle_path: Path<L, E::ScalarField>,
+    pub diff_hash: E::ScalarField,      // public
+    pub nullifier: E::ScalarField,      // public
+    pub after_leaf: E::ScalarField,     // public
+    pub hasher: H,                      // constant
+    pub address: E::ScalarField,        // utils::address_to_fr of the owner
+    pub public_address: E::ScalarField, // public, same encoding
+    pub aux: E::ScalarField,            // public
+    pub escrow: Option<Escrow<E>>,      // ciphertext public, auditor key constant
 }
 
-impl<const L: usize, const N: usize> MainCircuit<L, N> {
-    pub fn empty(hasher: PoseidonHash) -> Self {
+impl<const L: usize, const N: usize, E: Curve, H: TwoToOneHash<E::ScalarField>>
+    MainCircuit<L, N, E, H>
+{
+    pub fn empty(hasher: H) -> Self {
         Self {
-            nonce: Fr::ZERO,
-            before: [Fr::ZERO; N],
-            diff: [Fr::ZERO; N],
-            after: [Fr::ZERO; N],
-            merkle_root: Fr::ZERO,
+            spending_key: E::ScalarField::ZERO,
+            before: [E::ScalarField::ZERO; N],
+            diff: [E::ScalarField::ZERO; N],
+            after: [E::ScalarField::ZERO; N],
+            history: E::ScalarField::ZERO,
+            counter: E::ScalarField::ZERO,
+            merkle_root: E::ScalarField::ZERO,
             merkle_path: Path::empty(),
-            diff_hash: Fr::ZERO,
-            nullifier: Fr::ZERO,
-            after_leaf: Fr::ZERO,
-            aux: Fr::ZERO,
+            diff_hash: E::ScalarField::ZERO,
+            nullifier: E::ScalarField::ZERO,
+            after_leaf: E::ScalarField::ZERO,
+            aux: E::ScalarField::ZERO,
             hasher,
-            address: Fr::ZERO,
-            public_address: Fr::ZERO,
+            address: E::ScalarField::ZERO,
+            public_address: E::ScalarField::ZERO,
+            escrow: None,
         }
     }
+
+    /// Enables escrow of `address || diff` to `auditor`, for setup.
+    pub fn with_escrow(mut self, auditor: EmbeddedAffine<E>) -> Self {
+        self.escrow = Some(Escrow::empty(auditor, N + 1));
+        self
+    }
+
+    /// Public inputs in allocation order, the order `core::verify_proof`
+    /// appends them in.
+    pub fn public_inputs(&self) -> Vec<E::ScalarField> {
+        [
+            self.merkle_root,
+            self.diff_hash,
+            self.nullifier,
+            self.after_leaf,
+            self.public_address,
+            self.aux,
+        ]
+        .into_iter()
+        .chain(
+            self.escrow
+                .iter()
+                .flat_map(|e| e.ciphertext.public_inputs()),
+        )
+        .collect()
+    }
+
+    /// Public inputs as 32-byte little-endian words, the bytes
+    /// `groth16::public_proof_inputs_from_bytes` takes.
+    pub fn public_input_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for input in self.public_inputs() {
+            input
+                .serialize_compressed(&mut bytes)
+                .expect("Failed to serialize public input");
+        }
+        bytes
+    }
+}
+
+/// Variables of a [`MainCircuit`] that other circuits build on.
+pub struct MainVars<
+    E: Curve = Bn254,
+    H: TwoToOneHash<E::ScalarField> = PoseidonHash<<E as Pairing>::ScalarField>,
+> {
+    pub address: FpVar<E::ScalarField>,
+    pub public_address: FpVar<E::ScalarField>,
+    pub nullifier: FpVar<E::ScalarField>,
+    pub diff_hash: FpVar<E::ScalarField>,
+    pub aux: FpVar<E::ScalarField>,
+    pub hasher: H::Var,
+}
+
+impl<const L: usize, const N: usize, E: Curve, H: TwoToOneHash<E::ScalarField>>
+    ConstraintSynthesizer<E::ScalarField> for MainCircuit<L, N, E, H>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<E::ScalarField>) -> r1cs::Result<()> {
+        self.synthesize(cs, SINGLE_NOTE).map(|_| ())
+    }
 }
 
-impl<const L: usize, const N: usize> ConstraintSynthesizer<Fr> for MainCircuit<L, N> {
-    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> r1cs::Result<()> {
+impl<const L: usize, const N: usize, E: Curve, H: TwoToOneHash<E::ScalarField>>
+    MainCircuit<L, N, E, H>
+{
+    /// Generates the note update constraints for notes of `note_type`. The
+    /// public address is only bound to the owner address for single owner
+    /// notes, circuits of other types authorize it differently.
+    pub fn synthesize(
+        self,
+        cs: ConstraintSystemRef<E::ScalarField>,
+        note_type: u64,
+    ) -> r1cs::Result<MainVars<E, H>> {
         let merkle_root_var = FpVar::new_input(ns!(cs, "merkle_root"), || Ok(self.merkle_root))?;
         let diff_hash_var = FpVar::new_input(ns!(cs, "diff_hash"), || Ok(self.diff_hash))?;
         let nullifier_var = FpVar::new_input(ns!(cs, "nullifier"), || Ok(self.nullifier))?;
         let after_leaf_var = FpVar::new_input(ns!(cs, "after_leaf"), || Ok(self.after_leaf))?;
         let public_address_var =
             FpVar::new_input(ns!(cs, "public_address"), || Ok(self.public_address))?;
-        let _aux_var = FpVar::new_input(ns!(cs, "aux"), || Ok(self.aux))?;
+        let aux_var = FpVar::new_input(ns!(cs, "aux"), || Ok(self.aux))?;
+        let escrow_var = self
+            .escrow
+            .as_ref()
+            .map(|escrow| EscrowVar::new(cs.clone(), escrow))
+            .transpose()?;
 
-        let zero_var = FpVar::<Fr>::constant(Fr::ZERO);
-        let poseidon_hash_var =
-            PoseidonHashVar::new_constant(ns!(cs, "poseidon_parameter"), self.hasher)?;
+        let zero_var = FpVar::<E::ScalarField>::constant(E::ScalarField::ZERO);
+        let hasher_var = H::Var::new_constant(ns!(cs, "hasher"), self.hasher)?;
 
         let merkle_path_var =
             PathVar::new_witness(ns!(cs, "merkle_path"), || Ok(self.merkle_path))?;
-        let nonce_var = FpVar::new_witness(ns!(cs, "nonce"), || Ok(self.nonce))?;
-        let before_var = Vec::<FpVar<Fr>>::new_witness(ns!(cs, "before"), || Ok(self.before))?;
-        let diff_var = Vec::<FpVar<Fr>>::new_witness(ns!(cs, "diff"), || Ok(self.diff))?;
-        let after_var = Vec::<FpVar<Fr>>::new_witness(ns!(cs, "after"), || Ok(self.after))?;
+        let spending_key_var =
+            FpVar::new_witness(ns!(cs, "spending_key"), || Ok(self.spending_key))?;
+        let before_var =
+            Vec::<FpVar<E::ScalarField>>::new_witness(ns!(cs, "before"), || Ok(self.before))?;
+        let diff_var =
+            Vec::<FpVar<E::ScalarField>>::new_witness(ns!(cs, "diff"), || Ok(self.diff))?;
+        let after_var =
+            Vec::<FpVar<E::ScalarField>>::new_witness(ns!(cs, "after"), || Ok(self.after))?;
         let address_var = FpVar::new_witness(ns!(cs, "address"), || Ok(self.address))?;
+        let history_var = FpVar::new_witness(ns!(cs, "history"), || Ok(self.history))?;
+        let counter_var = FpVar::new_witness(ns!(cs, "counter"), || Ok(self.counter))?;
 
         // check for balance updates
-        for i in 0..N {
-            // check if after = before + diff
-            after_var[i].enforce_equal(&(before_var[i].clone() + diff_var[i].clone()))?;
-            // check if after is smaller than mod - 1 / 2 (>= 0)
-            after_var[i].enforce_smaller_or_equal_than_mod_minus_one_div_two()?;
+        {
+            let _ns = ns!(cs, "balance");
+            for i in 0..N {
+                // check if after = before + diff
+                after_var[i].enforce_equal(&(before_var[i].clone() + diff_var[i].clone()))?;
+                // check if after is an amount (>= 0). before is one as well, the
+                // after of an earlier update or zero, so any diff in
+                // -(2^64 - 1)..2^64 between two amounts is accepted
+                UInt64Var::from_fp(after_var[i].clone())?;
+            }
         }
 
-        // P = H(address, nonce)
-        let prehash = poseidon_hash_var.hash(&address_var, &nonce_var)?;
+        // viewing_key = H_view(spending_key, 0), derived here so that a note can
+        // only be spent by the holder of the spending key it was created for
+        let viewing_key =
+            hasher_var.hash_in(Domain::ViewingKey, &spending_key_var, &FpVar::zero())?;
 
-        // empty_leaf = H(H(P, 0), 0), ....
-        let empty_leaf = (0..N).try_fold(prehash.clone(), |acc, _| {
-            poseidon_hash_var.hash(&acc, &zero_var)
-        })?;
+        // P = H_leaf(H_leaf(address, viewing_key), note_type)
+        let prehash = hasher_var.hash_in(
+            Domain::Leaf,
+            &hasher_var.hash_in(Domain::Leaf, &address_var, &viewing_key)?,
+            &FpVar::constant(E::ScalarField::from(note_type)),
+        )?;
 
-        // before_leaf = H(H(P, before[0]), before[1]), ....
-        let before_leaf = before_var
-            .iter()
-            .try_fold(prehash.clone(), |acc, v| poseidon_hash_var.hash(&acc, v))?;
+        // leaf = H_leaf(H_leaf(H_leaf(H_leaf(P, balance[0]), balance[1]), ..., counter), history)
+        let leaf = |balance: &[FpVar<E::ScalarField>],
+                    counter: &FpVar<E::ScalarField>,
+                    history: &FpVar<E::ScalarField>| {
+            let balance_hash = hash_vector(&hasher_var, Domain::Leaf, &prehash, balance)?;
+            hasher_var.hash_in(
+                Domain::Leaf,
+                &hasher_var.hash_in(Domain::Leaf, &balance_hash, counter)?,
+                history,
+            )
+        };
 
-        // after_leaf = H(H(P, after[0]), after[1]), ....
-        let after_leaf = after_var
-            .iter()
-            .try_fold(prehash.clone(), |acc, v| poseidon_hash_var.hash(&acc, v))?;
+        // empty_leaf has zero balances, counter and history
+        let empty_leaf = leaf(&vec![zero_var.clone(); N], &zero_var, &zero_var)?;
 
-        // check if after_leaf is eq to after_leaf_var
-        after_leaf.enforce_equal(&after_leaf_var)?;
+        let before_leaf = leaf(&before_var, &counter_var, &history_var)?;
 
-        // diff_hash = H(H(0, diff[0]), diff[1]), ....
-        let diff_hash = diff_var
-            .iter()
-            .try_fold(zero_var.clone(), |acc, v| poseidon_hash_var.hash(&acc, v))?;
+        // diff_hash = H(H("diff", diff[0]), diff[1]), ....
+        let diff_hash = hash_vector(
+            &hasher_var,
+            Domain::Node,
+            &FpVar::constant(E::ScalarField::from(DIFF_HASH_DOMAIN)),
+            &diff_var,
+        )?;
 
         // check if diff_hash is eq to diff_hash_var
-        diff_hash.enforce_equal(&diff_hash_var)?;
+        {
+            let _ns = ns!(cs, "diff_hash");
+            diff_hash.enforce_equal(&diff_hash_var)?;
+        }
+
+        // after_history = H_history(H_history(history, diff_hash), counter + 1)
+        let after_counter = &counter_var + E::ScalarField::ONE;
+        let after_history = hasher_var.hash_in(
+            Domain::History,
+            &hasher_var.hash_in(Domain::History, &history_var, &diff_hash)?,
+            &after_counter,
+        )?;
+
+        let after_leaf = leaf(&after_var, &after_counter, &after_history)?;
+
+        // check if after_leaf is eq to after_leaf_var
+        {
+            let _ns = ns!(cs, "after_leaf");
+            after_leaf.enforce_equal(&after_leaf_var)?;
+        }
 
         // check if merkle_path is valid
         let is_before_membership_valid =
-            merkle_path_var.check_membership(&merkle_root_var, &before_leaf, &poseidon_hash_var)?;
+            merkle_path_var.check_membership(&merkle_root_var, &before_leaf, &hasher_var)?;
+
+        // public address need to be equal to address (public ops) or zero (private ops),
+        // both are encoded with `address_to_fr` so distinct Sui addresses never match
+        if note_type == SINGLE_NOTE {
+            let _ns = ns!(cs, "public_address");
+            (public_address_var.is_eq(&address_var)? | public_address_var.is_zero()?)
+                .enforce_equal(&Boolean::TRUE)?;
+        }
 
-        // public address need to be equal to address (public ops) or zero (private ops)
-        (public_address_var.is_eq(&address_var)? | public_address_var.is_zero()?)
-            .enforce_equal(&Boolean::TRUE)?;
+        // nullifier = H_nullifier(before_leaf, viewing_key), the empty leaf
+        // included, so the first note spends the account's genesis nullifier
+        // and an account has a single chain of notes a viewing key can follow
+        {
+            let _ns = ns!(cs, "nullifier");
+            hasher_var
+                .hash_in(Domain::Nullifier, &before_leaf, &viewing_key)?
+                .enforce_equal(&nullifier_var)?;
+        }
 
-        let nullifier = poseidon_hash_var.hash(&before_leaf, &nonce_var)?;
+        // before_leaf is either the empty leaf or in the tree
+        {
+            let _ns = ns!(cs, "spend");
+            (before_leaf.is_eq(&empty_leaf)? | is_before_membership_valid)
+                .enforce_equal(&Boolean::TRUE)?;
+        }
 
-        // if before_leaf == empty_leaf && nullifier == 0
-        // -> check for valid nullifier and valid membership proof
-        ((before_leaf.is_eq(&empty_leaf)? & nullifier_var.is_eq(&zero_var)?)
-            | (nullifier.is_eq(&nullifier_var)? & is_before_membership_valid))
-            .enforce_equal(&Boolean::TRUE)?;
+        // escrow ciphertext encrypts address || diff to the auditor
+        if let Some(escrow_var) = escrow_var {
+            let _ns = ns!(cs, "escrow");
+            let message = [vec![address_var.clone()], diff_var].concat();
+            escrow_var.enforce_encryption(&message, &hasher_var)?;
+        }
 
-        Ok(())
+        Ok(MainVars {
+            address: address_var,
+            public_address: public_address_var,
+            nullifier: nullifier_var,
+            diff_hash: diff_hash_var,
+            aux: aux_var,
+            hasher: hasher_var,
+        })
     }
 }
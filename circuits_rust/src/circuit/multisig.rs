@@ -0,0 +1,135 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::Field;
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{AllocVar, Boolean, EqGadget, FieldVar},
+};
+use ark_relations::{
+    ns,
+    r1cs::{self, ConstraintSynthesizer, ConstraintSystemRef},
+};
+
+use super::{MainCircuit, MULTISIG_NOTE};
+use crate::{
+    curve::{Curve, EmbeddedAffine, EmbeddedVar},
+    gadgets::{enforce_bits, greater_or_equal, hash_vector},
+    hash::{Domain, TwoToOneHash},
+    multisig::{MultisigPolicy, Signature, SignatureVar},
+    poseidon::PoseidonHash,
+};
+
+/// Note update of a multisig note, `main.address` is the policy's owner
+/// commitment. Instead of binding the public address to the owner, at least
+/// `threshold` of the `K` signers sign
+/// intent = H(H(H(nullifier, diff_hash), public_address), aux).
+#[derive(Debug, Clone)]
+pub struct MultisigCircuit<
+    const L: usize,
+    const N: usize,
+    const K: usize,
+    E: Curve = Bn254,
+    H: TwoToOneHash<E::ScalarField> = PoseidonHash<<E as Pairing>::ScalarField>,
+> {
+    pub main: MainCircuit<L, N, E, H>,
+    pub threshold: E::ScalarField,
+    pub signers: [EmbeddedAffine<E>; K],
+    pub signatures: [Option<Signature<E>>; K], // None for signers that did not sign
+}
+
+impl<const L: usize, const N: usize, const K: usize, E: Curve, H: TwoToOneHash<E::ScalarField>>
+    MultisigCircuit<L, N, K, E, H>
+{
+    pub fn empty(hasher: H) -> Self {
+        Self {
+            main: MainCircuit::empty(hasher),
+            threshold: E::ScalarField::ONE,
+            signers: [EmbeddedAffine::<E>::generator(); K],
+            signatures: [None; K],
+        }
+    }
+}
+
+impl<const L: usize, const N: usize, const K: usize, H: TwoToOneHash<Fr>>
+    MultisigCircuit<L, N, K, Bn254, H>
+{
+    pub fn new(
+        main: MainCircuit<L, N, Bn254, H>,
+        policy: &MultisigPolicy,
+        signatures: [Option<Signature>; K],
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            main,
+            threshold: Fr::from(policy.threshold()),
+            signers: policy.signers().try_into().map_err(|_| {
+                anyhow::anyhow!(
+                    "Policy has {} signers, expected {}",
+                    policy.signers().len(),
+                    K
+                )
+            })?,
+            signatures,
+        })
+    }
+}
+
+impl<const L: usize, const N: usize, const K: usize, E: Curve, H: TwoToOneHash<E::ScalarField>>
+    ConstraintSynthesizer<E::ScalarField> for MultisigCircuit<L, N, K, E, H>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<E::ScalarField>) -> r1cs::Result<()> {
+        let main = self.main.synthesize(cs.clone(), MULTISIG_NOTE)?;
+
+        let threshold_var = FpVar::new_witness(ns!(cs, "threshold"), || Ok(self.threshold))?;
+        let signer_vars = self
+            .signers
+            .iter()
+            .map(|a| EmbeddedVar::<E>::new_witness(ns!(cs, "signer"), || Ok(a.into_group())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signed_vars = self
+            .signatures
+            .iter()
+            .map(|s| Boolean::new_witness(ns!(cs, "signed"), || Ok(s.is_some())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signature_vars = self
+            .signatures
+            .iter()
+            .map(|s| SignatureVar::new_witness(ns!(cs, "signature"), || Ok(s.unwrap_or_default())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // owner = H(H(H(H(threshold, A[0].x), A[0].y), A[1].x), ...), in the
+        // multisig domain
+        let owner = hash_vector(
+            &main.hasher,
+            Domain::Multisig,
+            &threshold_var,
+            signer_vars.iter().flat_map(|a| [&a.x, &a.y]),
+        )?;
+        owner.enforce_equal(&main.address)?;
+
+        // intent = H(H(H(nullifier, diff_hash), public_address), aux), likewise
+        let intent = hash_vector(
+            &main.hasher,
+            Domain::Multisig,
+            &main.nullifier,
+            [&main.diff_hash, &main.public_address, &main.aux],
+        )?;
+
+        // every signer marked as signed has a valid signature
+        let mut count = FpVar::<E::ScalarField>::zero();
+        for ((signer, signed), signature) in
+            signer_vars.iter().zip(&signed_vars).zip(&signature_vars)
+        {
+            (!signed | signature.verify(signer, &intent, &main.hasher)?)
+                .enforce_equal(&Boolean::TRUE)?;
+            count += FpVar::from(signed.clone());
+        }
+
+        // 0 < threshold <= count, both below 2^bits as count <= K
+        let bits = (usize::BITS - K.leading_zeros()) as usize;
+        enforce_bits(&threshold_var, bits)?;
+        threshold_var.is_zero()?.enforce_equal(&Boolean::FALSE)?;
+        greater_or_equal(&count, &threshold_var, bits)?.enforce_equal(&Boolean::TRUE)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{anyhow, ensure};
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+
+use crate::{
+    gadgets::to_i128,
+    poseidon::{poseidon_bn254, PoseidonHash, DIFF_HASH_DOMAIN},
+    utils::fr_to_hex,
+    ASSET_SIZE,
+};
+
+/// Diff of one transition keyed by coin type, mirroring `coin_diff.move`.
+/// Its slots follow the pool's `allowed_tokens`, the order `core::allow_token`
+/// was called in, so it hashes to the `diff_hash` the contract checks.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoinDiff {
+    total: usize,
+    map: Vec<(String, Fr)>,
+}
+
+impl CoinDiff {
+    /// `coin_diff::empty`, failing where the contract would abort.
+    pub fn empty(total: usize, allowed_tokens: &[impl AsRef<str>]) -> anyhow::Result<Self> {
+        let mut map: Vec<(String, Fr)> = vec![];
+        for token in allowed_tokens {
+            let token = type_name(token.as_ref())?;
+            ensure!(
+                map.iter().all(|(t, _)| *t != token),
+                "Coin type {token} is allowed twice"
+            );
+            map.push((token, Fr::ZERO));
+        }
+
+        Ok(Self { total, map })
+    }
+
+    /// `coin_diff::add_coin`.
+    pub fn add_coin(&mut self, coin_type: &str, amount: u64) -> anyhow::Result<()> {
+        *self.get_mut(coin_type)? += Fr::from(amount);
+        Ok(())
+    }
+
+    /// `coin_diff::sub_coin`, a negative diff is `p - amount`.
+    pub fn sub_coin(&mut self, coin_type: &str, amount: u64) -> anyhow::Result<()> {
+        *self.get_mut(coin_type)? -= Fr::from(amount);
+        Ok(())
+    }
+
+    /// Slot of `coin_type` in the diff.
+    pub fn index_of(&self, coin_type: &str) -> anyhow::Result<usize> {
+        let coin_type = type_name(coin_type)?;
+        self.map
+            .iter()
+            .position(|(t, _)| *t == coin_type)
+            .ok_or_else(|| anyhow!("Coin type {coin_type} is not allowed"))
+    }
+
+    /// Values `final_repr` hashes, padded with zeros to `total`.
+    pub fn values(&self) -> Vec<Fr> {
+        let mut values = self.map.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        values.resize(values.len().max(self.total), Fr::ZERO);
+        values
+    }
+
+    /// `coin_diff::final_repr`,
+    /// diff_hash = H(H("diff", diff[0]), diff[1]), ....
+    pub fn final_repr(&self, hasher: &PoseidonHash) -> Fr {
+        self.values()
+            .iter()
+            .fold(Fr::from(DIFF_HASH_DOMAIN), |acc, v| hasher.hash(&acc, v))
+    }
+
+    /// The diff as the circuit takes it.
+    pub fn diff(&self) -> anyhow::Result<[Fr; ASSET_SIZE]> {
+        let values = self.values();
+        let len = values.len();
+        values
+            .try_into()
+            .map_err(|_| anyhow!("Diff has {len} slots, the circuit takes {ASSET_SIZE}"))
+    }
+
+    /// The diff as signed amounts.
+    pub fn diffs(&self) -> anyhow::Result<[i128; ASSET_SIZE]> {
+        let diff = self.diff()?;
+        let mut diffs = [0; ASSET_SIZE];
+        for (d, v) in diffs.iter_mut().zip(diff) {
+            *d = to_i128(&v)
+                .ok_or_else(|| anyhow!("Diff {} is not a signed amount", fr_to_hex(&v)))?;
+        }
+        Ok(diffs)
+    }
+
+    fn get_mut(&mut self, coin_type: &str) -> anyhow::Result<&mut Fr> {
+        let index = self.index_of(coin_type)?;
+        Ok(&mut self.map[index].1)
+    }
+}
+
+#[wasm_bindgen]
+impl CoinDiff {
+    #[wasm_bindgen(js_name = new)]
+    pub fn wasm_new(total: usize, allowed_tokens: Vec<String>) -> Result<CoinDiff, JsError> {
+        Self::empty(total, &allowed_tokens).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = addCoin)]
+    pub fn wasm_add_coin(&mut self, coin_type: String, amount: u64) -> Result<(), JsError> {
+        self.add_coin(&coin_type, amount)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = subCoin)]
+    pub fn wasm_sub_coin(&mut self, coin_type: String, amount: u64) -> Result<(), JsError> {
+        self.sub_coin(&coin_type, amount)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = getDiffHash)]
+    pub fn wasm_get_diff_hash(&self) -> String {
+        fr_to_hex(&self.final_repr(&PoseidonHash::new(poseidon_bn254())))
+    }
+
+    /// Diffs to pass to `prove`.
+    #[wasm_bindgen(js_name = getDiffs)]
+    pub fn wasm_get_diffs(&self) -> Result<Vec<String>, JsError> {
+        self.diffs()
+            .map(|diffs| diffs.iter().map(i128::to_string).collect())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+/// String of the `TypeName` that `type_name::get` gives for `coin_type`, the
+/// addresses zero-padded to 32 bytes of lowercase hex without `0x`, e.g.
+/// `0x2::sui::SUI` is `0000...0002::sui::SUI`. Generic arguments are
+/// normalized the same way.
+pub fn type_name(coin_type: &str) -> anyhow::Result<String> {
+    let mut normalized = String::new();
+    let mut rest = coin_type.trim();
+    loop {
+        let end = rest.find(['<', '>', ',']).unwrap_or(rest.len());
+        let segment = rest[..end].trim();
+        if let Some((address, path)) = segment.split_once("::") {
+            let address = address.to_lowercase();
+            let address = address.trim_start_matches("0x");
+            ensure!(
+                !address.is_empty()
+                    && address.len() <= 64
+                    && address.chars().all(|c| c.is_ascii_hexdigit()),
+                "Invalid address in coin type {coin_type}"
+            );
+            normalized.push_str(&format!("{address:0>64}::{path}"));
+        } else {
+            normalized.push_str(segment);
+        }
+
+        match rest[end..].chars().next() {
+            Some(delimiter) => {
+                normalized.push(delimiter);
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    Ok(normalized)
+}
@@ -0,0 +1,479 @@
+use ark_ec::{CurveGroup, PrimeGroup};
+use ark_ed_on_bn254::{EdwardsAffine, EdwardsProjective, Fr as EdwardsFr};
+use std::fmt::Write;
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, Field};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+
+use crate::{
+    escrow::Escrow,
+    gadgets::checked_add_diff,
+    hash::Domain,
+    merkle_tree::SparseMerkleTree,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    utils::{address_to_fr, fr_hex, fr_to_hex},
+    wasm::{diff_hash, history_step, Account},
+    Circuit, ASSET_SIZE, LEVEL,
+};
+
+const CONFORMANCE_VERSION: u8 = 3;
+
+/// Values of the protocol computed by this crate, the reference that the
+/// Move contracts are checked against through [`move_test`]. Field elements
+/// are big-endian hex, byte strings hex and amounts decimal strings, as u64
+/// amounts do not fit a JSON number.
+///
+/// `circuits_circom` is not covered: it is the prototype of an earlier
+/// protocol, with other sizes and leaf formula, and no deployment proves with
+/// it. Only the circomlib Poseidon both share is checked, by `poseidon_kat`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConformanceSuite {
+    pub version: u8,
+    pub curve: &'static str,
+    pub level: usize,
+    pub asset_size: usize,
+    pub leaves: Vec<LeafVector>,
+    pub diff_hashes: Vec<DiffHashVector>,
+    pub merkle: MerkleVector,
+    pub public_inputs: Vec<PublicInputsVector>,
+}
+
+/// Leaf and nullifier of a note.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeafVector {
+    /// Sui address of the owner.
+    pub address: String,
+    /// `fr::from_address_bytes` of the address.
+    #[serde(with = "fr_hex")]
+    pub address_fr: Fr,
+    #[serde(with = "fr_hex")]
+    pub spending_key: Fr,
+    #[serde(with = "fr_hex")]
+    pub viewing_key: Fr,
+    #[serde(with = "fr_hex")]
+    pub prehash: Fr,
+    pub balance: Vec<String>,
+    pub counter: u64,
+    #[serde(with = "fr_hex")]
+    pub history_root: Fr,
+    #[serde(with = "fr_hex")]
+    pub leaf: Fr,
+    #[serde(with = "fr_hex")]
+    pub nullifier: Fr,
+}
+
+/// `coin_diff::final_repr` of a diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffHashVector {
+    /// Signed decimal amounts, as `wasm::prove` takes them.
+    pub diff: Vec<String>,
+    /// The diff as field elements, negatives as `sub_coin` computes them.
+    pub diff_fr: Vec<String>,
+    #[serde(with = "fr_hex")]
+    pub diff_hash: Fr,
+}
+
+/// Tree of `merkle::new(level, _, default_leaf)` as leaves are inserted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MerkleVector {
+    #[serde(with = "fr_hex")]
+    pub default_leaf: Fr,
+    /// `zeros` of `merkle::new`, zeros[i] is the root of an empty subtree of
+    /// height i.
+    pub zeros: Vec<String>,
+    pub leaves: Vec<String>,
+    /// roots[k] is the root after inserting the first k leaves. Like
+    /// `merkle::new`, the empty tree starts from zeros[level - 1], while
+    /// inserting hashes up `level` times.
+    pub roots: Vec<String>,
+}
+
+/// Public inputs of a note update, as `core::verify_proof` builds them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublicInputsVector {
+    pub name: &'static str,
+    #[serde(with = "fr_hex")]
+    pub merkle_root: Fr,
+    #[serde(with = "fr_hex")]
+    pub diff_hash: Fr,
+    #[serde(with = "fr_hex")]
+    pub nullifier: Fr,
+    #[serde(with = "fr_hex")]
+    pub after_leaf: Fr,
+    #[serde(with = "fr_hex")]
+    pub public_address: Fr,
+    #[serde(with = "fr_hex")]
+    pub aux: Fr,
+    /// Escrow ciphertext bytes, empty without an auditor.
+    pub escrow: String,
+    /// Concatenation of every public input, the bytes passed to
+    /// `groth16::public_proof_inputs_from_bytes`.
+    pub bytes: String,
+}
+
+/// Owner of the notes of the suite, with a fixed spending key.
+struct Owner {
+    address: [u8; 32],
+    spending_key: Fr,
+    account: Account,
+}
+
+impl Owner {
+    fn new(address: [u8; 32], spending_key: u64) -> Self {
+        let spending_key = Fr::from(spending_key);
+        Self {
+            address,
+            spending_key,
+            account: Account::new(address_to_fr(&address), spending_key),
+        }
+    }
+
+    fn with_note(
+        mut self,
+        balance: [u64; ASSET_SIZE],
+        counter: u64,
+        history_root: Fr,
+        index: usize,
+    ) -> Self {
+        self.account.set_balance(balance);
+        self.account.set_history(counter, history_root);
+        self.account.wasm_set_index(index);
+        self
+    }
+
+    fn leaf_vector(&self, hasher: &PoseidonHash) -> LeafVector {
+        let account = &self.account;
+        let leaf = account.leaf(hasher);
+        LeafVector {
+            address: format!("0x{}", hex::encode(self.address)),
+            address_fr: account.address_fr(),
+            spending_key: self.spending_key,
+            viewing_key: account.viewing_key(),
+            prehash: account.prehash(hasher),
+            balance: account.balance().iter().map(u64::to_string).collect(),
+            counter: account.counter(),
+            history_root: account.history_root(),
+            leaf,
+            nullifier: account.nullifier_of(&leaf, hasher),
+        }
+    }
+}
+
+/// Note updates the public input vectors are taken from, valid witnesses of
+/// the main circuit over the tree of [`merkle_leaves`].
+pub fn note_updates() -> Vec<(&'static str, Circuit)> {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let leaves = merkle_leaves(&hasher);
+    let tree =
+        SparseMerkleTree::<LEVEL>::new_sequential(&leaves, &hasher, &Fr::ZERO).expect("Valid tree");
+    let auditor = (EdwardsProjective::generator() * EdwardsFr::from(0xa0d17u64)).into_affine();
+
+    let fresh = owners(&hasher).remove(0);
+    let funded = owners(&hasher).remove(1);
+    let full = owners(&hasher).remove(2);
+    vec![
+        (
+            "first deposit",
+            note_update(&fresh, &tree, [1000, 0, 0, 0, 0], true, None, &hasher),
+        ),
+        (
+            "private swap",
+            note_update(&funded, &tree, [-500, 0, 250, 0, 0], false, None, &hasher),
+        ),
+        (
+            "withdraw with escrow",
+            note_update(
+                &funded,
+                &tree,
+                [0, -7, 0, 0, 0],
+                true,
+                Some(auditor),
+                &hasher,
+            ),
+        ),
+        (
+            "withdraw a full balance",
+            note_update(
+                &full,
+                &tree,
+                [-i128::from(u64::MAX), 0, 0, 0, 0],
+                true,
+                None,
+                &hasher,
+            ),
+        ),
+    ]
+}
+
+/// Generates the suite, it only depends on this crate's version.
+pub fn generate() -> ConformanceSuite {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+
+    let leaves = owners(&hasher)
+        .iter()
+        .map(|o| o.leaf_vector(&hasher))
+        .collect();
+
+    let diff_hashes = [
+        [0; ASSET_SIZE],
+        [1000, 0, 0, 0, 0],
+        [-1000, 0, 0, 0, 0],
+        [-500, 0, 250, 0, 0],
+        [i64::MAX.into(), i64::MIN.into(), 1, -1, 0],
+        [u64::MAX.into(), -i128::from(u64::MAX), 0, 0, 0],
+    ]
+    .into_iter()
+    .map(|diff| {
+        let diff_fr = diff.map(Fr::from);
+        DiffHashVector {
+            diff: diff.iter().map(i128::to_string).collect(),
+            diff_fr: diff_fr.iter().map(fr_to_hex).collect(),
+            diff_hash: diff_hash(&diff_fr, &hasher),
+        }
+    })
+    .collect();
+
+    let merkle_leaves = merkle_leaves(&hasher);
+    let zeros = std::iter::successors(Some(Fr::ZERO), |z| Some(hasher.hash(z, z)))
+        .take(LEVEL)
+        .collect::<Vec<_>>();
+    let roots = (0..=merkle_leaves.len())
+        .map(|k| {
+            SparseMerkleTree::<LEVEL>::new_sequential(&merkle_leaves[..k], &hasher, &Fr::ZERO)
+                .expect("Valid tree")
+                .root()
+        })
+        .collect::<Vec<_>>();
+
+    let public_inputs = note_updates()
+        .into_iter()
+        .map(|(name, c)| PublicInputsVector {
+            name,
+            merkle_root: c.merkle_root,
+            diff_hash: c.diff_hash,
+            nullifier: c.nullifier,
+            after_leaf: c.after_leaf,
+            public_address: c.public_address,
+            aux: c.aux,
+            escrow: hex::encode(
+                c.escrow
+                    .as_ref()
+                    .map(|e| e.ciphertext.to_bytes())
+                    .unwrap_or_default(),
+            ),
+            bytes: hex::encode(c.public_input_bytes()),
+        })
+        .collect();
+
+    ConformanceSuite {
+        version: CONFORMANCE_VERSION,
+        curve: "bn254",
+        level: LEVEL,
+        asset_size: ASSET_SIZE,
+        leaves,
+        diff_hashes,
+        merkle: MerkleVector {
+            default_leaf: Fr::ZERO,
+            zeros: zeros.iter().map(fr_to_hex).collect(),
+            leaves: merkle_leaves.iter().map(fr_to_hex).collect(),
+            roots: roots.iter().map(fr_to_hex).collect(),
+        },
+        public_inputs,
+    }
+}
+
+/// A fresh account, one with notes and one at the balance limit.
+fn owners(hasher: &PoseidonHash) -> Vec<Owner> {
+    vec![
+        Owner::new([0x11; 32], 1),
+        Owner::new([0x22; 32], 2).with_note(
+            [1500, 20, 0, 0, 3],
+            2,
+            history_step(
+                &history_step(&Fr::ZERO, &Fr::from(1u64), 1, hasher),
+                &Fr::from(2u64),
+                2,
+                hasher,
+            ),
+            1,
+        ),
+        Owner::new([0xff; 32], 3).with_note([u64::MAX; ASSET_SIZE], 1, Fr::ONE, 2),
+    ]
+}
+
+/// Leaves of the suite's tree, the notes of [`owners`] at their index, the
+/// fresh account's being the empty leaf, followed by filler leaves.
+fn merkle_leaves(hasher: &PoseidonHash) -> Vec<Fr> {
+    owners(hasher)
+        .iter()
+        .map(|o| o.account.leaf(hasher))
+        .chain((0..5u64).map(|i| hasher.hash_in(Domain::Leaf, &Fr::from(i), &Fr::ZERO)))
+        .collect()
+}
+
+fn note_update(
+    owner: &Owner,
+    tree: &SparseMerkleTree<LEVEL>,
+    diff: [i128; ASSET_SIZE],
+    is_public: bool,
+    auditor: Option<EdwardsAffine>,
+    hasher: &PoseidonHash,
+) -> Circuit {
+    let account = &owner.account;
+    let before = account.balance();
+    let after = std::array::from_fn(|i| {
+        checked_add_diff(before[i], diff[i]).expect("Valid balance update")
+    });
+    let diff = diff.map(Fr::from);
+    let diff_hash = diff_hash(&diff, hasher);
+    let after_history = account.next_history_root(&diff_hash, hasher);
+    let nullifier = account.nullifier(hasher);
+    let escrow = auditor.map(|auditor| {
+        let message = [&[account.address_fr()][..], &diff[..]].concat();
+        Escrow::new(auditor, &message, &mut StdRng::seed_from_u64(0), hasher)
+    });
+
+    Circuit {
+        spending_key: owner.spending_key,
+        address: account.address_fr(),
+        public_address: if is_public {
+            account.address_fr()
+        } else {
+            Fr::ZERO
+        },
+        before: before.map(Fr::from),
+        diff,
+        after: after.map(Fr::from),
+        history: account.history_root(),
+        counter: Fr::from(account.counter()),
+        merkle_root: tree.root(),
+        merkle_path: tree.generate_membership_proof(account.index().unwrap_or(0) as u64),
+        diff_hash,
+        nullifier,
+        after_leaf: account.leaf_of(&after, account.counter() + 1, &after_history, hasher),
+        hasher: hasher.clone(),
+        aux: Fr::ZERO,
+        escrow,
+    }
+}
+
+/// `contracts/tests/conformance_test.move`, the vectors of `suite` asserted
+/// against `coin_diff::final_repr`, `merkle::insert`,
+/// `fr::from_address_bytes` and `core::public_inputs_bytes`.
+pub fn move_test(suite: &ConformanceSuite) -> String {
+    const COINS: [&str; ASSET_SIZE] = ["u8", "u16", "u32", "u64", "u128"];
+    let mut out = String::new();
+    let mut line = |s: &str| writeln!(out, "{s}").expect("Writing to a string");
+
+    line("// Generated from conformance.json by `cargo run --bin conformance conformance.json");
+    line("// ../contracts/tests/conformance_test.move` in circuits_rust, do not edit.");
+    line("#[test_only]");
+    line("module shroud::conformance_test;");
+    line("");
+    line("use shroud::coin_diff::{Self, CoinDiff};");
+    line("use shroud::core;");
+    line("use shroud::fr;");
+    line("use shroud::merkle;");
+    line("use std::type_name::get;");
+    line("use std::unit_test::{assert_eq, destroy};");
+    line("");
+    line("// one coin type for every asset, in the order they are allowed");
+    line("fun coin_diff(): CoinDiff {");
+    line(&format!(
+        "    coin_diff::empty({ASSET_SIZE}, vector[{}])",
+        COINS.map(|c| format!("get<{c}>()")).join(", ")
+    ));
+    line("}");
+    line("");
+    line("#[test]");
+    line("fun diff_hashes_match_final_repr() {");
+    for vector in &suite.diff_hashes {
+        if vector.diff.iter().all(|d| d == "0") {
+            line("    let diff = coin_diff();");
+        } else {
+            line("    let mut diff = coin_diff();");
+        }
+        for (coin, amount) in COINS.iter().zip(&vector.diff) {
+            let amount: i128 = amount.parse().expect("Decimal amount");
+            match amount {
+                0 => {}
+                a if a > 0 => line(&format!("    diff.add_coin(get<{coin}>(), {a});")),
+                a => line(&format!("    diff.sub_coin(get<{coin}>(), {});", -a)),
+            }
+        }
+        line(&format!(
+            "    assert_eq!(diff.final_repr(), {});",
+            u256(&vector.diff_hash)
+        ));
+    }
+    line("}");
+    line("");
+    line("#[test]");
+    line("fun roots_match_merkle_insert() {");
+    line("    let mut ctx = tx_context::dummy();");
+    line(&format!(
+        "    let mut tree = merkle::new({}, 1, {}, &mut ctx);",
+        suite.level,
+        u256(&suite.merkle.default_leaf)
+    ));
+    line(&format!(
+        "    assert_eq!(tree.root(), 0x{});",
+        suite.merkle.roots[0]
+    ));
+    for (i, (leaf, root)) in suite
+        .merkle
+        .leaves
+        .iter()
+        .zip(&suite.merkle.roots[1..])
+        .enumerate()
+    {
+        line(&format!("    let (index, root) = tree.insert(0x{leaf});"));
+        line(&format!("    assert_eq!(index, {i});"));
+        line(&format!("    assert_eq!(root, 0x{root});"));
+    }
+    line("    destroy(tree);");
+    line("}");
+    line("");
+    line("#[test]");
+    line("fun addresses_match_from_address_bytes() {");
+    for leaf in &suite.leaves {
+        line(&format!(
+            "    assert_eq!(fr::from_address_bytes(@{}).repr(), {});",
+            leaf.address,
+            u256(&leaf.address_fr)
+        ));
+    }
+    line("}");
+    line("");
+    line("#[test]");
+    line("fun public_inputs_match_verify_proof() {");
+    for vector in &suite.public_inputs {
+        line(&format!("    // {}", vector.name));
+        line("    assert_eq!(");
+        line("        core::public_inputs_bytes(");
+        for input in [
+            &vector.merkle_root,
+            &vector.diff_hash,
+            &vector.nullifier,
+            &vector.after_leaf,
+            &vector.public_address,
+            &vector.aux,
+        ] {
+            line(&format!("            {},", u256(input)));
+        }
+        line(&format!("            x\"{}\",", vector.escrow));
+        line("        ),");
+        line(&format!("        x\"{}\",", vector.bytes));
+        line("    );");
+    }
+    line("}");
+
+    out
+}
+
+/// A field element as a Move u256 literal.
+fn u256(fr: &Fr) -> String {
+    format!("0x{}", fr_to_hex(fr))
+}
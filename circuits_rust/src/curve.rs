@@ -0,0 +1,57 @@
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_ec::{
+    pairing::Pairing,
+    twisted_edwards::{Affine, Projective, TECurveConfig},
+    CurveConfig,
+};
+use ark_r1cs_std::{fields::fp::FpVar, groups::curves::twisted_edwards::AffineVar};
+
+use crate::poseidon::{poseidon_bls12_381, poseidon_bn254};
+
+/// Pairing curve the Groth16 proofs are over, one of the two supported by
+/// Sui's `groth16` module. The circuits work over its scalar field, where
+/// `Embedded` is the twisted Edwards curve of the escrow and multisig keys.
+///
+/// BLS12-381 is native only. The contracts verify BN254 proofs alone: `core`
+/// prepares every key with `groth16::bn254()` and hashes the tree and the
+/// diffs with Sui's `poseidon_bn254`, so keys and proofs over BLS12-381 cannot
+/// be verified on chain.
+pub trait Curve: Pairing {
+    type Embedded: TECurveConfig<BaseField = Self::ScalarField>;
+
+    /// Name of the curve in Sui's `groth16` module.
+    const NAME: &'static str;
+
+    fn poseidon() -> PoseidonConfig<Self::ScalarField>;
+}
+
+impl Curve for Bn254 {
+    /// BabyJubJub.
+    type Embedded = ark_ed_on_bn254::EdwardsConfig;
+
+    const NAME: &'static str = "bn254";
+
+    fn poseidon() -> PoseidonConfig<Self::ScalarField> {
+        poseidon_bn254()
+    }
+}
+
+impl Curve for Bls12_381 {
+    /// Jubjub.
+    type Embedded = ark_ed_on_bls12_381::JubjubConfig;
+
+    const NAME: &'static str = "bls12381";
+
+    fn poseidon() -> PoseidonConfig<Self::ScalarField> {
+        poseidon_bls12_381()
+    }
+}
+
+/// Point of the embedded curve of `E`.
+pub type EmbeddedAffine<E> = Affine<<E as Curve>::Embedded>;
+pub type EmbeddedProjective<E> = Projective<<E as Curve>::Embedded>;
+pub type EmbeddedScalar<E> = <<E as Curve>::Embedded as CurveConfig>::ScalarField;
+/// Gadget of an [`EmbeddedAffine`] point.
+pub type EmbeddedVar<E> = AffineVar<<E as Curve>::Embedded, FpVar<<E as Pairing>::ScalarField>>;
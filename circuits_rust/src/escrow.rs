@@ -0,0 +1,285 @@
+use anyhow::{anyhow, ensure};
+use ark_bn254::Bn254;
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField, UniformRand};
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    groups::CurveVar,
+    prelude::{AllocVar, Boolean, EqGadget, FieldVar},
+};
+use ark_relations::{
+    ns,
+    r1cs::{ConstraintSystemRef, SynthesisError},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+
+use crate::{
+    curve::{Curve, EmbeddedAffine, EmbeddedProjective, EmbeddedScalar, EmbeddedVar},
+    gadgets::to_i128,
+    hash::{Domain, TwoToOneHash, TwoToOneHashVar},
+};
+
+/// Ciphertext of the owner address and diff of one transition, published as
+/// public inputs so that the proof binds it to the note update.
+///
+/// R = r * G, S = r * PK, c[i] = m[i] + H_escrow(H_escrow(S.x, S.y), i)
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct EscrowCiphertext<E: Curve = Bn254> {
+    pub ephemeral: EmbeddedAffine<E>,
+    pub data: Vec<E::ScalarField>,
+}
+
+impl<E: Curve> EscrowCiphertext<E> {
+    /// Public inputs in circuit order: R.x, R.y, c[0], c[1], ....
+    pub fn public_inputs(&self) -> Vec<E::ScalarField> {
+        [self.ephemeral.x, self.ephemeral.y]
+            .into_iter()
+            .chain(self.data.iter().copied())
+            .collect()
+    }
+
+    /// Public inputs as 32-byte little-endian words, as appended on chain.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for input in self.public_inputs() {
+            input
+                .serialize_compressed(&mut bytes)
+                .expect("Failed to serialize escrow input");
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        ensure!(
+            bytes.len() >= 64 && bytes.len().is_multiple_of(32),
+            "Invalid escrow ciphertext length {}",
+            bytes.len()
+        );
+        let inputs = bytes
+            .chunks(32)
+            .map(E::ScalarField::deserialize_compressed)
+            .collect::<Result<Vec<_>, _>>()?;
+        let ephemeral = EmbeddedAffine::<E>::new_unchecked(inputs[0], inputs[1]);
+        ensure!(
+            ephemeral.is_on_curve(),
+            "Escrow ephemeral key is not on the curve"
+        );
+
+        Ok(Self {
+            ephemeral,
+            data: inputs[2..].to_vec(),
+        })
+    }
+}
+
+/// Decrypted escrow message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscrowPlaintext<E: Curve = Bn254> {
+    pub address: E::ScalarField,
+    pub diff: Vec<i128>,
+}
+
+/// Auditor secret key, the matching public key is baked into the circuit.
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AuditorKey<E: Curve = Bn254> {
+    secret: EmbeddedScalar<E>,
+}
+
+impl<E: Curve> AuditorKey<E> {
+    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+        Self {
+            secret: EmbeddedScalar::<E>::rand(rng),
+        }
+    }
+
+    pub fn public_key(&self) -> EmbeddedAffine<E> {
+        (EmbeddedProjective::<E>::generator() * self.secret).into_affine()
+    }
+
+    /// Decrypts the raw field elements of the message.
+    pub fn decrypt(
+        &self,
+        ciphertext: &EscrowCiphertext<E>,
+        hasher: &impl TwoToOneHash<E::ScalarField>,
+    ) -> Vec<E::ScalarField> {
+        let key = shared_key::<E>(&(ciphertext.ephemeral * self.secret).into_affine(), hasher);
+        ciphertext
+            .data
+            .iter()
+            .enumerate()
+            .map(|(i, c)| *c - keystream(&key, i, hasher))
+            .collect()
+    }
+
+    /// Decrypts and decodes `address || diff`, failing if a diff is not a
+    /// signed amount, which means the key or ciphertext is wrong.
+    pub fn open(
+        &self,
+        ciphertext: &EscrowCiphertext<E>,
+        hasher: &impl TwoToOneHash<E::ScalarField>,
+    ) -> anyhow::Result<EscrowPlaintext<E>> {
+        let message = self.decrypt(ciphertext, hasher);
+        let (address, diff) = message
+            .split_first()
+            .ok_or_else(|| anyhow!("Empty escrow ciphertext"))?;
+        let diff = diff
+            .iter()
+            .map(|d| to_i128(d).ok_or_else(|| anyhow!("Decrypted diff out of range")))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(EscrowPlaintext {
+            address: *address,
+            diff,
+        })
+    }
+}
+
+/// Escrow part of the circuit: the auditor key is a constant, the randomness
+/// a witness and the ciphertext public.
+#[derive(Debug, Clone)]
+pub struct Escrow<E: Curve = Bn254> {
+    pub auditor: EmbeddedAffine<E>,
+    pub randomness: EmbeddedScalar<E>,
+    pub ciphertext: EscrowCiphertext<E>,
+}
+
+impl<E: Curve> Escrow<E> {
+    /// Placeholder for setup, with room for a message of `size` elements.
+    pub fn empty(auditor: EmbeddedAffine<E>, size: usize) -> Self {
+        Self {
+            auditor,
+            randomness: EmbeddedScalar::<E>::ZERO,
+            ciphertext: EscrowCiphertext {
+                ephemeral: EmbeddedAffine::<E>::zero(),
+                data: vec![E::ScalarField::ZERO; size],
+            },
+        }
+    }
+
+    /// Encrypts `message` to `auditor` under fresh randomness.
+    pub fn new<R: Rng>(
+        auditor: EmbeddedAffine<E>,
+        message: &[E::ScalarField],
+        rng: &mut R,
+        hasher: &impl TwoToOneHash<E::ScalarField>,
+    ) -> Self {
+        let randomness = EmbeddedScalar::<E>::rand(rng);
+        let key = shared_key::<E>(&(auditor * randomness).into_affine(), hasher);
+
+        Self {
+            auditor,
+            randomness,
+            ciphertext: EscrowCiphertext {
+                ephemeral: (EmbeddedProjective::<E>::generator() * randomness).into_affine(),
+                data: message
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| *m + keystream(&key, i, hasher))
+                    .collect(),
+            },
+        }
+    }
+}
+
+pub struct EscrowVar<E: Curve = Bn254> {
+    auditor: EmbeddedAffine<E>,
+    randomness: Vec<Boolean<E::ScalarField>>,
+    ephemeral: (FpVar<E::ScalarField>, FpVar<E::ScalarField>),
+    data: Vec<FpVar<E::ScalarField>>,
+}
+
+impl<E: Curve> EscrowVar<E> {
+    /// Allocates the ciphertext as public inputs and the randomness bits as
+    /// witnesses.
+    pub fn new(
+        cs: ConstraintSystemRef<E::ScalarField>,
+        escrow: &Escrow<E>,
+    ) -> Result<Self, SynthesisError> {
+        let ephemeral = (
+            FpVar::new_input(ns!(cs, "escrow_ephemeral_x"), || {
+                Ok(escrow.ciphertext.ephemeral.x)
+            })?,
+            FpVar::new_input(ns!(cs, "escrow_ephemeral_y"), || {
+                Ok(escrow.ciphertext.ephemeral.y)
+            })?,
+        );
+        let data = Vec::<FpVar<E::ScalarField>>::new_input(ns!(cs, "escrow_data"), || {
+            Ok(escrow.ciphertext.data.clone())
+        })?;
+        let randomness =
+            Vec::<Boolean<E::ScalarField>>::new_witness(ns!(cs, "escrow_randomness"), || {
+                Ok(scalar_bits::<E>(&escrow.randomness))
+            })?;
+
+        Ok(Self {
+            auditor: escrow.auditor,
+            randomness,
+            ephemeral,
+            data,
+        })
+    }
+
+    /// Enforces that the public ciphertext encrypts `message`.
+    pub fn enforce_encryption(
+        &self,
+        message: &[FpVar<E::ScalarField>],
+        hasher: &impl TwoToOneHashVar<E::ScalarField>,
+    ) -> Result<(), SynthesisError> {
+        if message.len() != self.data.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // R = r * G
+        let ephemeral =
+            fixed_base_mul::<E>(EmbeddedProjective::<E>::generator(), &self.randomness)?;
+        ephemeral.x.enforce_equal(&self.ephemeral.0)?;
+        ephemeral.y.enforce_equal(&self.ephemeral.1)?;
+
+        // S = r * PK
+        let shared = fixed_base_mul::<E>(self.auditor.into_group(), &self.randomness)?;
+        let key = hasher.hash_in(Domain::Escrow, &shared.x, &shared.y)?;
+
+        // c[i] = m[i] + H_escrow(key, i)
+        for (i, (m, c)) in message.iter().zip(self.data.iter()).enumerate() {
+            let pad = hasher.hash_in(
+                Domain::Escrow,
+                &key,
+                &FpVar::constant(E::ScalarField::from(i as u64)),
+            )?;
+            (m + pad).enforce_equal(c)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn fixed_base_mul<E: Curve>(
+    base: EmbeddedProjective<E>,
+    bits: &[Boolean<E::ScalarField>],
+) -> Result<EmbeddedVar<E>, SynthesisError> {
+    let multiples = std::iter::successors(Some(base), |b| Some(b.double()))
+        .take(bits.len())
+        .collect::<Vec<_>>();
+    let mut result = EmbeddedVar::<E>::zero();
+    result.precomputed_base_scalar_mul_le(bits.iter().zip(multiples.iter()))?;
+    Ok(result)
+}
+
+/// Little-endian bits of an embedded scalar, as multiplied in the circuit.
+pub(crate) fn scalar_bits<E: Curve>(scalar: &EmbeddedScalar<E>) -> Vec<bool> {
+    scalar.into_bigint().to_bits_le()[..EmbeddedScalar::<E>::MODULUS_BIT_SIZE as usize].to_vec()
+}
+
+/// k = H_escrow(S.x, S.y)
+fn shared_key<E: Curve>(
+    shared: &EmbeddedAffine<E>,
+    hasher: &impl TwoToOneHash<E::ScalarField>,
+) -> E::ScalarField {
+    hasher.hash_in(Domain::Escrow, &shared.x, &shared.y)
+}
+
+/// pad[i] = H_escrow(k, i)
+fn keystream<F: PrimeField>(key: &F, i: usize, hasher: &impl TwoToOneHash<F>) -> F {
+    hasher.hash_in(Domain::Escrow, key, &F::from(i as u64))
+}
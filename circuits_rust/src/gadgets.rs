@@ -0,0 +1,248 @@
+use std::borrow::Borrow;
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{
+    fields::fp::FpVar,
+    prelude::{AllocVar, AllocationMode, Boolean, CondSelectGadget, EqGadget, FieldVar},
+    R1CSVar,
+};
+use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+
+use crate::hash::{Domain, TwoToOneHashVar};
+
+/// Enforces that `value` is below 2^bits and returns its little-endian bits,
+/// `bits + 1` constraints.
+pub fn enforce_bits<F: PrimeField>(
+    value: &FpVar<F>,
+    bits: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    assert!(
+        bits < F::MODULUS_BIT_SIZE as usize,
+        "Range of {bits} bits wraps around the field"
+    );
+    let cs = value.cs();
+    let mode = witness_mode(&cs);
+    let value_bits = value.value().ok().map(|v| v.into_bigint().to_bits_le());
+    let bits = (0..bits)
+        .map(|i| {
+            Boolean::new_variable(
+                cs.clone(),
+                || {
+                    value_bits
+                        .as_ref()
+                        .map(|b| b[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+                mode,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp(&bits)?.enforce_equal(value)?;
+
+    Ok(bits)
+}
+
+/// Whether `a >= b`, for `a` and `b` already known to be below 2^bits.
+/// a - b + 2^bits is in 0..2^(bits + 1) and has its top bit set exactly when
+/// a >= b.
+pub fn greater_or_equal<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    let shifted = a - b + F::from(2u64).pow([bits as u64]);
+    let mut shifted_bits = enforce_bits(&shifted, bits + 1)?;
+    Ok(shifted_bits.pop().expect("bits + 1 > 0"))
+}
+
+/// Whether `a < b`, for `a` and `b` already known to be below 2^bits.
+pub fn less_than<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    bits: usize,
+) -> Result<Boolean<F>, SynthesisError> {
+    Ok(!greater_or_equal(a, b, bits)?)
+}
+
+/// Amount in 0..2^64, its range is enforced whenever one is created.
+#[derive(Debug, Clone)]
+pub struct UInt64Var<F: PrimeField> {
+    value: FpVar<F>,
+}
+
+impl<F: PrimeField> UInt64Var<F> {
+    pub const BITS: usize = 64;
+
+    /// Enforces that `value` is an amount.
+    pub fn from_fp(value: FpVar<F>) -> Result<Self, SynthesisError> {
+        enforce_bits(&value, Self::BITS)?;
+        Ok(Self { value })
+    }
+
+    pub fn constant(value: u64) -> Self {
+        Self {
+            value: FpVar::constant(F::from(value)),
+        }
+    }
+
+    pub fn as_fp(&self) -> &FpVar<F> {
+        &self.value
+    }
+
+    pub fn is_lt(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        less_than(&self.value, &other.value, Self::BITS)
+    }
+
+    pub fn is_ge(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        greater_or_equal(&self.value, &other.value, Self::BITS)
+    }
+
+    /// self + diff, enforcing that it neither underflows nor overflows. The
+    /// sum is in -2^64..2^65 before the range check, so a negative one wraps
+    /// to just below the modulus and is rejected.
+    pub fn add_signed(&self, diff: &SignedVar<F>) -> Result<Self, SynthesisError> {
+        Self::from_fp(&self.value + diff.to_fp()?)
+    }
+}
+
+impl<F: PrimeField> AllocVar<u64, F> for UInt64Var<F> {
+    fn new_variable<T: Borrow<u64>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let value = FpVar::new_variable(cs, || f().map(|v| F::from(*v.borrow())), mode)?;
+        match mode {
+            AllocationMode::Constant => Ok(Self { value }),
+            _ => Self::from_fp(value),
+        }
+    }
+}
+
+/// Signed amount, `magnitude` or `-magnitude` as a field element. Diffs are
+/// encoded this way, negatives as `p - |d|`.
+#[derive(Debug, Clone)]
+pub struct SignedVar<F: PrimeField> {
+    pub is_negative: Boolean<F>,
+    pub magnitude: UInt64Var<F>,
+}
+
+impl<F: PrimeField> SignedVar<F> {
+    /// Splits `value` into its sign and magnitude, enforcing that the
+    /// magnitude is below 2^64. Zero satisfies either sign bit, callers must
+    /// not give the sign of a zero amount a meaning.
+    pub fn decompose(value: &FpVar<F>) -> Result<Self, SynthesisError> {
+        let cs = value.cs();
+        let mode = witness_mode(&cs);
+        let signed = value
+            .value()
+            .ok()
+            .map(|v| to_signed(&v).unwrap_or_default());
+        let is_negative = Boolean::new_variable(
+            cs.clone(),
+            || {
+                signed
+                    .map(|(n, _)| n)
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+            mode,
+        )?;
+        let magnitude = UInt64Var::new_variable(
+            cs,
+            || {
+                signed
+                    .map(|(_, m)| m)
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+            mode,
+        )?;
+        let signed = Self {
+            is_negative,
+            magnitude,
+        };
+        signed.to_fp()?.enforce_equal(value)?;
+
+        Ok(signed)
+    }
+
+    pub fn to_fp(&self) -> Result<FpVar<F>, SynthesisError> {
+        let magnitude = self.magnitude.as_fp();
+        FpVar::conditionally_select(&self.is_negative, &magnitude.negate()?, magnitude)
+    }
+}
+
+/// Element of `leaves` at the index with little-endian bits `position`, of
+/// which there are `log2(leaves.len())`.
+pub fn select_leaf<F: PrimeField>(
+    position: &[Boolean<F>],
+    leaves: &[FpVar<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    assert_eq!(
+        leaves.len(),
+        1 << position.len(),
+        "One leaf for every position"
+    );
+    let big_endian = position.iter().rev().cloned().collect::<Vec<_>>();
+    FpVar::conditionally_select_power_of_two_vector(&big_endian, leaves)
+}
+
+/// H(...H(H(init, values[0]), values[1])..., values[n - 1]), how the
+/// balances, diffs and signer keys are committed to.
+pub fn hash_vector<'a, F: PrimeField>(
+    hasher: &impl TwoToOneHashVar<F>,
+    domain: Domain,
+    init: &FpVar<F>,
+    values: impl IntoIterator<Item = &'a FpVar<F>>,
+) -> Result<FpVar<F>, SynthesisError> {
+    values
+        .into_iter()
+        .try_fold(init.clone(), |acc, v| hasher.hash_in(domain, &acc, v))
+}
+
+/// Reads a field element as a signed amount, negatives are `p - |d|`.
+/// Returns whether it is negative and its magnitude.
+pub fn to_signed<F: PrimeField>(value: &F) -> Option<(bool, u64)> {
+    let small = |f: F| {
+        let limbs = f.into_bigint();
+        let limbs = limbs.as_ref();
+        limbs[1..].iter().all(|l| *l == 0).then_some(limbs[0])
+    };
+    small(*value)
+        .map(|m| (false, m))
+        .or_else(|| small(-*value).map(|m| (true, m)))
+}
+
+/// Reads a field element as a signed amount in -(2^64 - 1)..2^64, the range
+/// of a diff between two balances.
+pub fn to_i128<F: PrimeField>(value: &F) -> Option<i128> {
+    to_signed(value).map(|(is_negative, m)| {
+        if is_negative {
+            -i128::from(m)
+        } else {
+            i128::from(m)
+        }
+    })
+}
+
+/// Field element of a signed amount, `None` unless its magnitude is a u64.
+pub fn from_i128<F: PrimeField>(amount: i128) -> Option<F> {
+    u64::try_from(amount.unsigned_abs())
+        .is_ok()
+        .then(|| F::from(amount))
+}
+
+/// balance + diff, `None` if it is not a u64.
+pub fn checked_add_diff(balance: u64, diff: i128) -> Option<u64> {
+    i128::from(balance)
+        .checked_add(diff)
+        .and_then(|b| u64::try_from(b).ok())
+}
+
+/// Constants have no constraint system, what they derive is constant too.
+fn witness_mode<F: PrimeField>(cs: &ConstraintSystemRef<F>) -> AllocationMode {
+    if cs.is_none() {
+        AllocationMode::Constant
+    } else {
+        AllocationMode::Witness
+    }
+}
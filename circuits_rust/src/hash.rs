@@ -0,0 +1,61 @@
+use std::fmt::Debug;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_relations::r1cs::SynthesisError;
+
+/// Role of a hash, mixed into it so that an output of one role is never a
+/// valid output of another. Tree nodes must hash as on chain, `merkle.move`
+/// computes them with plain circomlib Poseidon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    Node = 0,
+    /// Leaf prehash and the balance, counter and history chain.
+    Leaf = 1,
+    Nullifier = 2,
+    History = 3,
+    /// Viewing key of a spending key.
+    ViewingKey = 4,
+    /// Memo key of a viewing key.
+    MemoKey = 5,
+    /// Multisig owner commitment and the intent its signers sign.
+    Multisig = 6,
+    /// Signature nonces and challenges.
+    Signature = 7,
+    /// Escrow shared key and keystream.
+    Escrow = 8,
+}
+
+/// Two-to-one hash the Merkle tree and the circuits are built on, with
+/// [`TwoToOneHash::Var`] as its gadget. The gadget of a hasher allocated as a
+/// constant must compute the same outputs.
+pub trait TwoToOneHash<F: PrimeField>: Debug + Clone {
+    type Var: TwoToOneHashVar<F> + AllocVar<Self, F>;
+
+    fn hash_in(&self, domain: Domain, left: &F, right: &F) -> F;
+
+    /// Hash in the [`Domain::Node`] domain.
+    fn hash(&self, left: &F, right: &F) -> F {
+        self.hash_in(Domain::Node, left, right)
+    }
+
+    /// [`TwoToOneHash::hash`] of every pair, such as one level of a tree.
+    fn hash_many_pairs(&self, pairs: &[(F, F)]) -> Vec<F> {
+        pairs.iter().map(|(l, r)| self.hash(l, r)).collect()
+    }
+}
+
+/// Gadget half of a [`TwoToOneHash`].
+pub trait TwoToOneHashVar<F: PrimeField> {
+    fn hash_in(
+        &self,
+        domain: Domain,
+        left: &FpVar<F>,
+        right: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError>;
+
+    /// Hash in the [`Domain::Node`] domain.
+    fn hash(&self, left: &FpVar<F>, right: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+        self.hash_in(Domain::Node, left, right)
+    }
+}
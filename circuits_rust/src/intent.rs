@@ -0,0 +1,285 @@
+use anyhow::ensure;
+use ark_bn254::{Bn254, Fr};
+use ark_ed_on_bn254::EdwardsAffine;
+use ark_ff::AdditiveGroup;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
+
+use crate::{
+    circuit::SINGLE_NOTE,
+    coin_diff::CoinDiff,
+    prover,
+    registry::AssetRegistry,
+    utils::{fr_hex, u64_decimal},
+    wasm::{parse_auditor, NoteUpdate, State},
+};
+
+/// Operation on the account's note, each one the counterpart of a `core`
+/// entry point. Amounts are in base units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Intent {
+    /// `core::deposit`, crediting `amount` of `coin_type`.
+    Deposit {
+        coin_type: String,
+        #[serde(with = "u64_decimal")]
+        amount: u64,
+    },
+    /// `core::withdraw`, debiting `amount` of `coin_type`.
+    Withdraw {
+        coin_type: String,
+        #[serde(with = "u64_decimal")]
+        amount: u64,
+    },
+    /// `core::start_swap`, debiting `amount` of `from` and crediting `min_out`
+    /// of `to`, whatever the swap returns above it stays in the pool.
+    Swap {
+        from: String,
+        to: String,
+        #[serde(with = "u64_decimal")]
+        amount: u64,
+        #[serde(with = "u64_decimal")]
+        min_out: u64,
+    },
+    /// Empty public operation, re-inserting the note under a fresh nullifier
+    /// so that it is spendable against the latest root.
+    Refresh,
+}
+
+/// Argument of a Move call, in the order the entry point takes them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MoveArg {
+    /// Shared object.
+    Object { id: String },
+    /// Result of an earlier call of the same transaction.
+    Result { index: usize },
+    /// Coin of exactly `amount`, split from the sender's coins.
+    Coin {
+        coin_type: String,
+        #[serde(with = "u64_decimal")]
+        amount: u64,
+    },
+    U64 {
+        #[serde(with = "u64_decimal")]
+        value: u64,
+    },
+    U256 {
+        #[serde(with = "fr_hex")]
+        value: Fr,
+    },
+    /// `vector<u8>`, hex.
+    Bytes { value: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoveCall {
+    /// `package::module::function`.
+    pub target: String,
+    pub type_arguments: Vec<String>,
+    pub arguments: Vec<MoveArg>,
+}
+
+impl Intent {
+    /// The diff `core` computes for this intent, from the same `add_coin` and
+    /// `sub_coin` calls.
+    pub fn coin_diff(&self, registry: &AssetRegistry) -> anyhow::Result<CoinDiff> {
+        let mut coin_diff = registry.coin_diff();
+        match self {
+            Self::Deposit { coin_type, amount } => coin_diff.add_coin(coin_type, *amount)?,
+            Self::Withdraw { coin_type, amount } => coin_diff.sub_coin(coin_type, *amount)?,
+            Self::Swap {
+                from,
+                to,
+                amount,
+                min_out,
+            } => {
+                ensure!(
+                    registry.slot(from)? != registry.slot(to)?,
+                    "Swap of {from} to itself"
+                );
+                coin_diff.sub_coin(from, *amount)?;
+                coin_diff.add_coin(to, *min_out)?;
+            }
+            Self::Refresh => {}
+        }
+        Ok(coin_diff)
+    }
+
+    /// Whether the proof is bound to the sender, every operation except
+    /// swaps, which `core::start_swap` verifies for address zero.
+    pub fn is_public(&self) -> bool {
+        !matches!(self, Self::Swap { .. })
+    }
+
+    /// `aux` of the proof, `core` passes zero for every operation.
+    pub fn aux(&self) -> Fr {
+        Fr::ZERO
+    }
+
+    /// Note update of the account of `state` carrying out this intent.
+    pub fn note_update(
+        &self,
+        registry: &AssetRegistry,
+        state: &State,
+        auditor: Option<EdwardsAffine>,
+    ) -> anyhow::Result<NoteUpdate> {
+        ensure!(
+            state.account().note_type() == SINGLE_NOTE,
+            "Multisig notes are proven with proveMultisig"
+        );
+        let public_address = if self.is_public() {
+            state.account().address_fr()
+        } else {
+            Fr::ZERO
+        };
+        NoteUpdate::new(
+            state,
+            self.coin_diff(registry)?.diffs()?,
+            public_address,
+            self.aux(),
+            auditor,
+        )
+    }
+
+    /// Calls of the transaction submitting `update` with its `proof`. A
+    /// withdrawal returns the withdrawn coin and a swap the origin coin and
+    /// `SwapBalance`, which the caller passes on.
+    pub fn move_calls(
+        &self,
+        registry: &AssetRegistry,
+        package_id: &str,
+        shroud_id: &str,
+        update: &NoteUpdate,
+        proof: &[u8],
+    ) -> anyhow::Result<Vec<MoveCall>> {
+        let call =
+            |function: &str, type_arguments: Vec<String>, arguments: Vec<MoveArg>| MoveCall {
+                target: format!("{package_id}::core::{function}"),
+                type_arguments,
+                arguments,
+            };
+        let shroud = || MoveArg::Object {
+            id: shroud_id.to_string(),
+        };
+        let coin_type = |coin_type: &str| -> anyhow::Result<String> {
+            Ok(format!("0x{}", registry.asset(coin_type)?.coin_type))
+        };
+        let c = &update.circuit;
+        let proof_args = vec![
+            MoveArg::U256 {
+                value: c.merkle_root,
+            },
+            MoveArg::U256 { value: c.nullifier },
+            MoveArg::U256 {
+                value: c.after_leaf,
+            },
+            MoveArg::U64 { value: SINGLE_NOTE },
+            MoveArg::Bytes {
+                value: hex::encode(proof),
+            },
+            MoveArg::Bytes {
+                value: hex::encode(&update.memo),
+            },
+            MoveArg::Bytes {
+                value: hex::encode(&update.escrow),
+            },
+        ];
+
+        Ok(match self {
+            Self::Deposit {
+                coin_type: t,
+                amount,
+            } => vec![call(
+                "deposit",
+                vec![coin_type(t)?],
+                [
+                    vec![
+                        shroud(),
+                        MoveArg::Coin {
+                            coin_type: coin_type(t)?,
+                            amount: *amount,
+                        },
+                    ],
+                    proof_args,
+                ]
+                .concat(),
+            )],
+            Self::Withdraw {
+                coin_type: t,
+                amount,
+            } => vec![call(
+                "withdraw",
+                vec![coin_type(t)?],
+                [vec![shroud(), MoveArg::U64 { value: *amount }], proof_args].concat(),
+            )],
+            Self::Swap {
+                from,
+                to,
+                amount,
+                min_out,
+            } => vec![call(
+                "start_swap",
+                vec![coin_type(from)?, coin_type(to)?],
+                [
+                    vec![
+                        shroud(),
+                        MoveArg::U64 { value: *amount },
+                        MoveArg::U64 { value: *min_out },
+                    ],
+                    proof_args,
+                ]
+                .concat(),
+            )],
+            Self::Refresh => vec![
+                call("start_public_operation", vec![], vec![shroud()]),
+                call(
+                    "finish_public_operation",
+                    vec![],
+                    [vec![shroud(), MoveArg::Result { index: 0 }], proof_args].concat(),
+                ),
+            ],
+        })
+    }
+}
+
+/// Proves `intent`, e.g. `{ type: "swap", from, to, amount, min_out }` with
+/// amounts as decimal strings of base units, and returns the proof as `prove`
+/// does with the Move calls submitting it under `calls`.
+#[wasm_bindgen(js_name = proveIntent)]
+pub fn prove_intent(
+    state: State,
+    pk_bytes: Vec<u8>,
+    registry: &AssetRegistry,
+    intent: JsValue,
+    auditor: Option<String>,
+    package_id: String,
+    shroud_id: String,
+) -> Result<JsValue, JsError> {
+    let intent: Intent = from_value(intent)?;
+    let prove = || {
+        let update = intent.note_update(registry, &state, parse_auditor(auditor)?)?;
+        let proof =
+            prover::prove::<Bn254, _>(&pk_bytes, update.circuit.clone(), &mut thread_rng())?;
+        let calls = intent.move_calls(registry, &package_id, &shroud_id, &update, &proof)?;
+        anyhow::Ok((update, proof, calls))
+    };
+    let (update, proof, calls) = prove().map_err(|e| JsError::new(&e.to_string()))?;
+
+    let mut json = update.proof_json(&proof);
+    json["calls"] = serde_json::to_value(&calls)?;
+    Ok(to_value(&json)?)
+}
+
+/// Diffs of `intent` to show before proving, one decimal string per asset.
+#[wasm_bindgen(js_name = intentDiffs)]
+pub fn intent_diffs(registry: &AssetRegistry, intent: JsValue) -> Result<Vec<String>, JsError> {
+    let intent: Intent = from_value(intent)?;
+    intent
+        .coin_diff(registry)
+        .and_then(|d| d.diffs())
+        .map(|diffs| diffs.iter().map(i128::to_string).collect())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
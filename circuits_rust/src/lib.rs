@@ -1,10 +1,28 @@
-use circuit::MainCircuit;
+use ark_bn254::Bn254;
+use circuit::{multisig::MultisigCircuit, MainCircuit};
 
+pub mod audit;
 pub mod circuit;
+pub mod coin_diff;
+pub mod conformance;
+pub mod curve;
+pub mod escrow;
+pub mod gadgets;
+pub mod hash;
+pub mod intent;
+pub mod memo;
 pub mod merkle_tree;
+pub mod multisig;
 pub mod poseidon;
+pub mod prover;
+pub mod registry;
+pub mod sync;
+pub mod utils;
 pub mod wasm;
 
 pub const LEVEL: usize = 20;
 pub const ASSET_SIZE: usize = 5;
-pub type Circuit = MainCircuit<LEVEL, ASSET_SIZE>;
+pub type Circuit<E = Bn254> = MainCircuit<LEVEL, ASSET_SIZE, E>;
+/// Number of signers of a multisig note.
+pub const SIGNERS: usize = 3;
+pub type Multisig<E = Bn254> = MultisigCircuit<LEVEL, ASSET_SIZE, SIGNERS, E>;
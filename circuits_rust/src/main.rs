@@ -11,7 +11,7 @@ use circuits_rust::{
 fn main() -> anyhow::Result<()> {
     let cs = ConstraintSystem::<Fr>::new_ref();
     let poseidon = PoseidonHash::new(poseidon_bn254());
-    Circuit::empty(poseidon.clone()).generate_constraints(cs.clone())?;
+    <Circuit>::empty(poseidon.clone()).generate_constraints(cs.clone())?;
 
     println!("Constraints: {}", cs.num_constraints());
 
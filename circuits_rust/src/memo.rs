@@ -0,0 +1,142 @@
+use anyhow::{anyhow, ensure};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::{thread_rng, RngCore};
+
+use crate::{hash::Domain, poseidon::PoseidonHash, wasm::Account, ASSET_SIZE};
+
+const MEMO_VERSION: u8 = 1;
+
+/// Plaintext of the memo published next to a new leaf.
+///
+/// Memos are unauthenticated hints: the proof does not bind them, so whoever
+/// submits the transaction can replace or drop the memo of a leaf. A replaced
+/// memo can only make a note unrecoverable from the chain, never recover a
+/// wrong one, as [`MemoScanner::scan`] only accepts memos whose contents hash
+/// to the leaf they were published with.
+#[derive(Debug, Clone, Copy, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct NoteMemo {
+    pub balance: [u64; ASSET_SIZE],
+    /// Tree size when the proof was generated, the leaf index is at least this.
+    pub index_hint: u64,
+    pub counter: u64,
+    pub history_root: Fr,
+}
+
+/// A memo that decrypted under the account key and matches its leaf.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecoveredNote {
+    pub index: u64,
+    pub leaf: Fr,
+    pub memo: NoteMemo,
+}
+
+impl Account {
+    /// Symmetric memo key, K = H_memo(viewing_key, 0).
+    pub fn memo_key(&self, hasher: &PoseidonHash) -> [u8; 32] {
+        hasher
+            .hash_in(Domain::MemoKey, &self.viewing_key(), &Fr::ZERO)
+            .into_bigint()
+            .to_bytes_be()
+            .try_into()
+            .expect("Field element is 32 bytes")
+    }
+
+    /// Encrypts `memo` as `version || nonce || ciphertext`.
+    pub fn encrypt_memo(&self, memo: &NoteMemo, hasher: &PoseidonHash) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0u8; 24];
+        thread_rng().fill_bytes(&mut nonce);
+
+        let mut plaintext = vec![];
+        memo.serialize_compressed(&mut plaintext)?;
+
+        let ciphertext = XChaCha20Poly1305::new(&self.memo_key(hasher).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &[MEMO_VERSION],
+                },
+            )
+            .map_err(|_| anyhow!("Memo encryption failed"))?;
+
+        let mut data = vec![MEMO_VERSION];
+        data.extend(nonce);
+        data.extend(ciphertext);
+        Ok(data)
+    }
+
+    /// Trial-decrypts a memo, failing if it was not encrypted to this account.
+    pub fn decrypt_memo(&self, data: &[u8], hasher: &PoseidonHash) -> anyhow::Result<NoteMemo> {
+        ensure!(data.len() > 25, "Memo too short");
+        ensure!(
+            data[0] == MEMO_VERSION,
+            "Unsupported memo version {}",
+            data[0]
+        );
+
+        let plaintext = XChaCha20Poly1305::new(&self.memo_key(hasher).into())
+            .decrypt(
+                XNonce::from_slice(&data[1..25]),
+                Payload {
+                    msg: &data[25..],
+                    aad: &data[..1],
+                },
+            )
+            .map_err(|_| anyhow!("Memo is not for this account"))?;
+
+        Ok(NoteMemo::deserialize_compressed(&plaintext[..])?)
+    }
+}
+
+/// Scans a stream of `(index, leaf, memo)` in chain order and keeps the
+/// latest note belonging to the account.
+#[derive(Debug, Clone)]
+pub struct MemoScanner {
+    account: Account,
+    hasher: PoseidonHash,
+    latest: Option<RecoveredNote>,
+}
+
+impl MemoScanner {
+    pub fn new(account: Account, hasher: PoseidonHash) -> Self {
+        Self {
+            account,
+            hasher,
+            latest: None,
+        }
+    }
+
+    /// Returns the recovered note if the memo is ours and commits to `leaf`.
+    pub fn scan(&mut self, index: u64, leaf: &Fr, memo: &[u8]) -> Option<RecoveredNote> {
+        let memo = self.account.decrypt_memo(memo, &self.hasher).ok()?;
+        if self.account.leaf_of(
+            &memo.balance,
+            memo.counter,
+            &memo.history_root,
+            &self.hasher,
+        ) != *leaf
+        {
+            return None;
+        }
+
+        let note = RecoveredNote {
+            index,
+            leaf: *leaf,
+            memo,
+        };
+        if self.latest.is_none_or(|latest| latest.index < index) {
+            self.latest = Some(note);
+        }
+        Some(note)
+    }
+
+    pub fn latest(&self) -> Option<&RecoveredNote> {
+        self.latest.as_ref()
+    }
+}
@@ -3,9 +3,9 @@ use std::{
     collections::{BTreeMap, BTreeSet},
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, ensure};
 use ark_bn254::Fr;
-use ark_ff::{AdditiveGroup, Field};
+use ark_ff::PrimeField;
 use ark_r1cs_std::{
     fields::fp::FpVar,
     prelude::{AllocVar, AllocationMode, Boolean, EqGadget, FieldVar},
@@ -13,18 +13,18 @@ use ark_r1cs_std::{
 };
 use ark_relations::r1cs::{Namespace, SynthesisError};
 
-use crate::poseidon::{PoseidonHash, PoseidonHashVar};
+use crate::hash::{TwoToOneHash, TwoToOneHashVar};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Path<const N: usize> {
-    pub path: [(Fr, Fr); N],
+pub struct Path<const N: usize, F: PrimeField = Fr> {
+    pub path: [(F, F); N],
 }
 
-impl<const N: usize> Path<N> {
+impl<const N: usize, F: PrimeField> Path<N, F> {
     /// Creates a new empty path.
     pub fn empty() -> Self {
         Self {
-            path: [(Fr::ZERO, Fr::ZERO); N],
+            path: [(F::ZERO, F::ZERO); N],
         }
     }
 
@@ -33,15 +33,15 @@ impl<const N: usize> Path<N> {
     /// the expected hash.
     pub fn check_membership(
         &self,
-        root_hash: &Fr,
-        leaf: &Fr,
-        hasher: &PoseidonHash,
+        root_hash: &F,
+        leaf: &F,
+        hasher: &impl TwoToOneHash<F>,
     ) -> anyhow::Result<bool> {
         let root = self.calculate_root(leaf, hasher)?;
         Ok(root == *root_hash)
     }
 
-    pub fn calculate_root(&self, leaf: &Fr, hasher: &PoseidonHash) -> anyhow::Result<Fr> {
+    pub fn calculate_root(&self, leaf: &F, hasher: &impl TwoToOneHash<F>) -> anyhow::Result<F> {
         if *leaf != self.path[0].0 && *leaf != self.path[0].1 {
             return Err(anyhow!("Invalid leaf"));
         }
@@ -63,17 +63,17 @@ impl<const N: usize> Path<N> {
     /// does indeed belong to a tree with the given `root_hash`
     pub fn get_index(
         &self,
-        root_hash: &Fr,
-        leaf: &Fr,
-        hasher: &PoseidonHash,
-    ) -> anyhow::Result<Fr> {
+        root_hash: &F,
+        leaf: &F,
+        hasher: &impl TwoToOneHash<F>,
+    ) -> anyhow::Result<F> {
         if !self.check_membership(root_hash, leaf, hasher)? {
             return Err(anyhow!("Invalid leaf"));
         }
 
         let mut prev = *leaf;
-        let mut index = Fr::ZERO;
-        let mut twopower = Fr::ONE;
+        let mut index = F::ZERO;
+        let mut twopower = F::ONE;
         // Check levels between leaf level and root
         for (left_hash, right_hash) in &self.path {
             // Check if the previous hash is for a left node or right node
@@ -89,20 +89,20 @@ impl<const N: usize> Path<N> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct SparseMerkleTree<const N: usize> {
+pub struct SparseMerkleTree<const N: usize, F: PrimeField = Fr> {
     /// A map from leaf indices to leaf data stored as field elements.
-    pub tree: BTreeMap<u64, Fr>,
+    pub tree: BTreeMap<u64, F>,
     /// An array of default hashes hashed with themselves `N` times.
-    empty_hashes: [Fr; N],
+    empty_hashes: [F; N],
 }
 
-impl<const N: usize> SparseMerkleTree<N> {
+impl<const N: usize, F: PrimeField> SparseMerkleTree<N, F> {
     /// Takes a batch of field elements, inserts
     /// these hashes into the tree, and updates the merkle root.
     pub fn insert_batch(
         &mut self,
-        leaves: &BTreeMap<u32, Fr>,
-        hasher: &PoseidonHash,
+        leaves: &BTreeMap<u32, F>,
+        hasher: &impl TwoToOneHash<F>,
     ) -> anyhow::Result<()> {
         let last_level_index: u64 = (1u64 << N) - 1;
 
@@ -114,22 +114,23 @@ impl<const N: usize> SparseMerkleTree<N> {
         }
 
         for level in 0..N {
+            let empty_hash = self.empty_hashes[level];
+            let pairs = level_idxs
+                .iter()
+                .map(|i| {
+                    let left = self.tree.get(&(2 * i + 1)).unwrap_or(&empty_hash);
+                    let right = self.tree.get(&(2 * i + 2)).unwrap_or(&empty_hash);
+                    (*left, *right)
+                })
+                .collect::<Vec<_>>();
+            let hashes = hasher.hash_many_pairs(&pairs);
+
             let mut new_idxs: BTreeSet<u64> = BTreeSet::new();
-            for i in level_idxs {
-                let left_index = 2 * i + 1;
-                let right_index = 2 * i + 2;
-
-                let empty_hash = self.empty_hashes[level];
-                let left = self.tree.get(&left_index).unwrap_or(&empty_hash);
-                let right = self.tree.get(&right_index).unwrap_or(&empty_hash);
-                let hashed = hasher.hash(left, right);
+            for (i, hashed) in level_idxs.into_iter().zip(hashes) {
                 self.tree.insert(i, hashed);
-
-                let parent = match i > 0 {
-                    true => (i - 1) >> 1,
-                    false => break,
-                };
-                new_idxs.insert(parent);
+                if i > 0 {
+                    new_idxs.insert((i - 1) >> 1);
+                }
             }
             level_idxs = new_idxs;
         }
@@ -140,20 +141,17 @@ impl<const N: usize> SparseMerkleTree<N> {
     /// Creates a new Sparse Merkle Tree from a map of indices to field
     /// elements.
     pub fn new(
-        leaves: &BTreeMap<u32, Fr>,
-        hasher: &PoseidonHash,
-        empty_leaf: &Fr,
+        leaves: &BTreeMap<u32, F>,
+        hasher: &impl TwoToOneHash<F>,
+        empty_leaf: &F,
     ) -> anyhow::Result<Self> {
         // Ensure the tree can hold this many leaves
-        let last_level_size = leaves.len().next_power_of_two();
-        let tree_size = 2 * last_level_size - 1;
-        let tree_height = ark_std::log2(tree_size);
-        assert!(tree_height <= N as u32);
+        assert!(leaves.len() <= 1 << N, "Too many leaves for the tree");
 
         // Initialize the merkle tree
-        let tree: BTreeMap<u64, Fr> = BTreeMap::new();
+        let tree: BTreeMap<u64, F> = BTreeMap::new();
         let empty_hashes = {
-            let mut empty_hashes = [Fr::ZERO; N];
+            let mut empty_hashes = [F::ZERO; N];
 
             let mut empty_hash = *empty_leaf;
             empty_hashes[0] = empty_hash;
@@ -166,7 +164,7 @@ impl<const N: usize> SparseMerkleTree<N> {
             anyhow::Ok(empty_hashes)
         }?;
 
-        let mut smt = SparseMerkleTree::<N> { tree, empty_hashes };
+        let mut smt = SparseMerkleTree::<N, F> { tree, empty_hashes };
         smt.insert_batch(leaves, hasher)?;
 
         Ok(smt)
@@ -174,11 +172,11 @@ impl<const N: usize> SparseMerkleTree<N> {
 
     /// Creates a new Sparse Merkle Tree from an array of field elements.
     pub fn new_sequential(
-        leaves: &[Fr],
-        hasher: &PoseidonHash,
-        empty_leaf: &Fr,
+        leaves: &[F],
+        hasher: &impl TwoToOneHash<F>,
+        empty_leaf: &F,
     ) -> anyhow::Result<Self> {
-        let pairs: BTreeMap<u32, Fr> = leaves
+        let pairs: BTreeMap<u32, F> = leaves
             .iter()
             .enumerate()
             .map(|(i, l)| (i as u32, *l))
@@ -189,7 +187,7 @@ impl<const N: usize> SparseMerkleTree<N> {
     }
 
     /// Returns the Merkle tree root.
-    pub fn root(&self) -> Fr {
+    pub fn root(&self) -> F {
         self.tree
             .get(&0)
             .cloned()
@@ -199,8 +197,8 @@ impl<const N: usize> SparseMerkleTree<N> {
     /// Give the path leading from the leaf at `index` up to the root.  This is
     /// a "proof" in the sense of "valid path in a Merkle tree", not a ZK
     /// argument.
-    pub fn generate_membership_proof(&self, index: u64) -> Path<N> {
-        let mut path = [(Fr::ZERO, Fr::ZERO); N];
+    pub fn generate_membership_proof(&self, index: u64) -> Path<N, F> {
+        let mut path = [(F::ZERO, F::ZERO); N];
 
         let tree_index = index + (1u64 << N) - 1;
 
@@ -232,21 +230,120 @@ impl<const N: usize> SparseMerkleTree<N> {
     }
 }
 
+/// Rust model of `merkle.move`, computing exactly what the contract does as
+/// leaves are inserted. The tree keeps the frontier `hashes` instead of the
+/// nodes, so it cannot give paths, only the roots the contract will accept.
+///
+/// `insert` drops the oldest root before appending the new one regardless of
+/// `valid_size`, so `valid_roots` only ever holds the latest root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncrementalTree<F: PrimeField = Fr> {
+    hashes: Vec<F>,
+    zeros: Vec<F>,
+    leafs: Vec<F>,
+    level: u8,
+    root: F,
+    valid_size: u64,
+    valid_roots: Vec<F>,
+}
+
+impl<F: PrimeField> IncrementalTree<F> {
+    /// `merkle::new`, `zeros[i]` is the root of an empty subtree of height i
+    /// and the empty tree starts from `zeros[level - 1]`.
+    pub fn new(
+        level: u8,
+        valid_size: u64,
+        default_leaf: F,
+        hasher: &impl TwoToOneHash<F>,
+    ) -> anyhow::Result<Self> {
+        ensure!(level > 1, "Invalid level {level} (EINVALID_LEVEL)");
+        ensure!(valid_size > 0, "Invalid valid size (EINVALID_VALID_SIZE)");
+
+        let hashes = std::iter::successors(Some(default_leaf), |h| Some(hasher.hash(h, h)))
+            .take(level as usize)
+            .collect::<Vec<_>>();
+        let root = hashes[hashes.len() - 1];
+
+        Ok(Self {
+            zeros: hashes.clone(),
+            hashes,
+            leafs: vec![],
+            level,
+            root,
+            valid_size,
+            valid_roots: vec![root],
+        })
+    }
+
+    /// `merkle::insert`, returns the index of the leaf and the new root.
+    pub fn insert(&mut self, leaf: F, hasher: &impl TwoToOneHash<F>) -> anyhow::Result<(u64, F)> {
+        let index = self.leafs.len() as u64;
+        ensure!(
+            index < 2u64.pow(self.level as u32),
+            "Tree is full at {index} leaves (EINVALID_INDEX)"
+        );
+        self.leafs.push(leaf);
+
+        let mut cur_hash = leaf;
+        let mut cur_idx = index;
+        for i in 0..self.level as usize {
+            let (left, right) = if cur_idx.is_multiple_of(2) {
+                self.hashes[i] = cur_hash;
+                (cur_hash, self.zeros[i])
+            } else {
+                (self.hashes[i], cur_hash)
+            };
+            cur_hash = hasher.hash(&left, &right);
+            cur_idx /= 2;
+        }
+
+        self.root = cur_hash;
+        self.valid_roots.remove(0);
+        self.valid_roots.push(cur_hash);
+
+        Ok((index, cur_hash))
+    }
+
+    pub fn root(&self) -> F {
+        self.root
+    }
+
+    pub fn is_valid_root(&self, root: &F) -> bool {
+        self.valid_roots.contains(root)
+    }
+
+    pub fn size(&self) -> u64 {
+        self.leafs.len() as u64
+    }
+
+    pub fn leafs(&self) -> &[F] {
+        &self.leafs
+    }
+
+    pub fn valid_size(&self) -> u64 {
+        self.valid_size
+    }
+
+    pub fn valid_roots(&self) -> &[F] {
+        &self.valid_roots
+    }
+}
+
 /// Gadgets for one Merkle tree path
 #[derive(Debug, Clone)]
-pub struct PathVar<const N: usize> {
-    path: [(FpVar<Fr>, FpVar<Fr>); N],
+pub struct PathVar<const N: usize, F: PrimeField = Fr> {
+    path: [(FpVar<F>, FpVar<F>); N],
 }
 
-impl<const N: usize> PathVar<N> {
+impl<const N: usize, F: PrimeField> PathVar<N, F> {
     /// check whether path belongs to merkle path (does not check if indexes
     /// match)
     pub fn check_membership(
         &self,
-        root: &FpVar<Fr>,
-        leaf: &FpVar<Fr>,
-        hasher: &PoseidonHashVar,
-    ) -> Result<Boolean<Fr>, SynthesisError> {
+        root: &FpVar<F>,
+        leaf: &FpVar<F>,
+        hasher: &impl TwoToOneHashVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
         let computed_root = self.root_hash(leaf, hasher)?;
 
         root.is_eq(&computed_root)
@@ -255,9 +352,9 @@ impl<const N: usize> PathVar<N> {
     /// Creates circuit to calculate merkle root and deny any invalid paths
     pub fn root_hash(
         &self,
-        leaf: &FpVar<Fr>,
-        hasher: &PoseidonHashVar,
-    ) -> Result<FpVar<Fr>, SynthesisError> {
+        leaf: &FpVar<F>,
+        hasher: &impl TwoToOneHashVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
         assert_eq!(self.path.len(), N);
         let mut previous_hash = leaf.clone();
 
@@ -278,12 +375,12 @@ impl<const N: usize> PathVar<N> {
     /// Creates circuit to get index of a leaf hash
     pub fn get_index(
         &self,
-        leaf: &FpVar<Fr>,
-        hasher: &PoseidonHashVar,
-    ) -> Result<FpVar<Fr>, SynthesisError> {
-        let mut index = FpVar::<Fr>::zero();
-        let mut twopower = FpVar::<Fr>::one();
-        let mut rightvalue: FpVar<Fr>;
+        leaf: &FpVar<F>,
+        hasher: &impl TwoToOneHashVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut index = FpVar::<F>::zero();
+        let mut twopower = FpVar::<F>::one();
+        let mut rightvalue: FpVar<F>;
 
         // Check levels between leaf level and root.
         let mut previous_hash = leaf.clone();
@@ -292,19 +389,19 @@ impl<const N: usize> PathVar<N> {
             let previous_is_left = previous_hash.is_eq(left_hash)?;
 
             rightvalue = &index + &twopower;
-            index = FpVar::<Fr>::conditionally_select(&previous_is_left, &index, &rightvalue)?;
+            index = FpVar::<F>::conditionally_select(&previous_is_left, &index, &rightvalue)?;
             twopower = &twopower + &twopower;
 
-            previous_hash = hasher.hash(&left_hash, &right_hash)?;
+            previous_hash = hasher.hash(left_hash, right_hash)?;
         }
 
         Ok(index)
     }
 }
 
-impl<const N: usize> AllocVar<Path<N>, Fr> for PathVar<N> {
-    fn new_variable<T: Borrow<Path<N>>>(
-        cs: impl Into<Namespace<Fr>>,
+impl<const N: usize, F: PrimeField> AllocVar<Path<N, F>, F> for PathVar<N, F> {
+    fn new_variable<T: Borrow<Path<N, F>>>(
+        cs: impl Into<Namespace<F>>,
         f: impl FnOnce() -> Result<T, SynthesisError>,
         mode: AllocationMode,
     ) -> Result<Self, SynthesisError> {
@@ -315,16 +412,16 @@ impl<const N: usize> AllocVar<Path<N>, Fr> for PathVar<N> {
         let path_obj = f()?;
         for (l, r) in &path_obj.borrow().path {
             let l_hash =
-                FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "l_child"), || Ok(*l), mode)?;
+                FpVar::<F>::new_variable(ark_relations::ns!(cs, "l_child"), || Ok(*l), mode)?;
             let r_hash =
-                FpVar::<Fr>::new_variable(ark_relations::ns!(cs, "r_child"), || Ok(*r), mode)?;
+                FpVar::<F>::new_variable(ark_relations::ns!(cs, "r_child"), || Ok(*r), mode)?;
             path.push((l_hash, r_hash));
         }
 
         Ok(PathVar {
             path: path.try_into().unwrap_or_else(
                 #[allow(clippy::type_complexity)]
-                |v: Vec<(FpVar<Fr>, FpVar<Fr>)>| {
+                |v: Vec<(FpVar<F>, FpVar<F>)>| {
                     panic!("Expected a Vec of length {} but it was {}", N, v.len())
                 },
             ),
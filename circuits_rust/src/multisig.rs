@@ -0,0 +1,294 @@
+use std::borrow::Borrow;
+
+use anyhow::{ensure, Context};
+use ark_bn254::{Bn254, Fr};
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
+use ark_ed_on_bn254::{EdwardsAffine, EdwardsProjective, Fr as ScalarField};
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField, UniformRand};
+use ark_r1cs_std::{
+    alloc::AllocationMode,
+    fields::fp::FpVar,
+    groups::CurveVar,
+    prelude::{AllocVar, Boolean, EqGadget, ToBitsGadget},
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+
+use crate::{
+    curve::{Curve, EmbeddedAffine, EmbeddedProjective, EmbeddedScalar, EmbeddedVar},
+    escrow::{fixed_base_mul, scalar_bits},
+    gadgets::hash_vector,
+    hash::{Domain, TwoToOneHash, TwoToOneHashVar},
+    poseidon::PoseidonHash,
+    utils::{fr_from_hex, fr_to_hex},
+};
+
+/// Signers and threshold of a multisig note. The note's address is the
+/// commitment [`MultisigPolicy::owner`] instead of a Sui address.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MultisigPolicy {
+    threshold: u64,
+    signers: Vec<EdwardsAffine>,
+}
+
+impl MultisigPolicy {
+    pub fn new(threshold: u64, signers: Vec<EdwardsAffine>) -> anyhow::Result<Self> {
+        ensure!(
+            threshold > 0 && threshold <= signers.len() as u64,
+            "Invalid threshold {} for {} signers",
+            threshold,
+            signers.len()
+        );
+        // a repeated signer would count once per slot towards the threshold
+        ensure!(
+            signers
+                .iter()
+                .enumerate()
+                .all(|(i, a)| !signers[..i].contains(a)),
+            "Duplicate signer in multisig policy"
+        );
+        Ok(Self { threshold, signers })
+    }
+
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    pub fn signers(&self) -> &[EdwardsAffine] {
+        &self.signers
+    }
+
+    /// owner = H(H(H(H(threshold, A[0].x), A[0].y), A[1].x), ...), hashed in
+    /// [`Domain::Multisig`].
+    pub fn owner(&self, hasher: &PoseidonHash) -> Fr {
+        self.signers
+            .iter()
+            .flat_map(|a| [a.x, a.y])
+            .fold(Fr::from(self.threshold), |acc, v| {
+                hasher.hash_in(Domain::Multisig, &acc, &v)
+            })
+    }
+}
+
+#[wasm_bindgen]
+impl MultisigPolicy {
+    #[wasm_bindgen(constructor)]
+    pub fn wasm_new(threshold: u64, signers_hex: Vec<String>) -> Result<MultisigPolicy, JsError> {
+        let new = || {
+            let signers = signers_hex
+                .iter()
+                .map(|pk| {
+                    let bytes = hex::decode(pk).context("Invalid signer hex string")?;
+                    EdwardsAffine::deserialize_compressed(&bytes[..])
+                        .context("Invalid signer public key")
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Self::new(threshold, signers)
+        };
+        new().map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Owner commitment, the address of the policy's multisig account. It is
+    /// already a field element, create the account with `Account.multisig`
+    /// since `new Account` encodes a Sui address.
+    #[wasm_bindgen(js_name = getOwner)]
+    pub fn wasm_get_owner(&self) -> String {
+        fr_to_hex(&self.owner(&PoseidonHash::new(crate::poseidon::poseidon_bn254())))
+    }
+}
+
+/// EdDSA signing key of one multisig signer.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SignerKey {
+    secret: ScalarField,
+}
+
+impl SignerKey {
+    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+        Self {
+            secret: ScalarField::rand(rng),
+        }
+    }
+
+    pub fn public_key(&self) -> EdwardsAffine {
+        (EdwardsProjective::generator() * self.secret).into_affine()
+    }
+
+    /// Deterministic signature, the nonce is H_signature(secret, msg).
+    pub fn sign(&self, msg: &Fr, hasher: &PoseidonHash) -> Signature {
+        let secret = Fr::from_bigint(self.secret.into_bigint()).expect("Scalar fits in Fr");
+        let k = to_scalar::<Bn254>(&hasher.hash_in(Domain::Signature, &secret, msg));
+        let r = (EdwardsProjective::generator() * k).into_affine();
+        let c = to_scalar::<Bn254>(&challenge::<Bn254>(&r, &self.public_key(), msg, hasher));
+
+        Signature {
+            r,
+            s: k + c * self.secret,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SignerKey {
+    #[wasm_bindgen(js_name = generate)]
+    pub fn wasm_generate() -> Self {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    #[wasm_bindgen(js_name = getPublicKey)]
+    pub fn wasm_get_public_key(&self) -> String {
+        let mut bytes = vec![];
+        self.public_key()
+            .serialize_compressed(&mut bytes)
+            .expect("Failed to serialize public key");
+        hex::encode(bytes)
+    }
+
+    /// Signs an intent returned by `multisigIntent`.
+    #[wasm_bindgen(js_name = sign)]
+    pub fn wasm_sign(&self, intent_hex: String) -> Result<String, JsError> {
+        let intent =
+            fr_from_hex(&intent_hex).map_err(|_| JsError::new("Invalid intent hex string"))?;
+        let mut bytes = vec![];
+        self.sign(
+            &intent,
+            &PoseidonHash::new(crate::poseidon::poseidon_bn254()),
+        )
+        .serialize_compressed(&mut bytes)
+        .expect("Failed to serialize signature");
+        Ok(hex::encode(bytes))
+    }
+
+    #[wasm_bindgen(js_name = export)]
+    pub fn wasm_export(&self) -> Vec<u8> {
+        let mut data = vec![];
+        self.serialize_compressed(&mut data)
+            .expect("Failed to serialize");
+        data
+    }
+
+    #[wasm_bindgen(js_name = import)]
+    pub fn wasm_import(data: &[u8]) -> Result<SignerKey, JsError> {
+        Self::deserialize_compressed(data).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+/// EdDSA signature over the embedded curve, s * G = R + c * A.
+#[derive(Debug, Clone, Copy, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Signature<E: Curve = Bn254> {
+    pub r: EmbeddedAffine<E>,
+    pub s: EmbeddedScalar<E>,
+}
+
+impl<E: Curve> Signature<E> {
+    pub fn verify(
+        &self,
+        public_key: &EmbeddedAffine<E>,
+        msg: &E::ScalarField,
+        hasher: &impl TwoToOneHash<E::ScalarField>,
+    ) -> bool {
+        let c = to_scalar::<E>(&challenge::<E>(&self.r, public_key, msg, hasher));
+        EmbeddedProjective::<E>::generator() * self.s == self.r + *public_key * c
+    }
+}
+
+impl<E: Curve> Default for Signature<E> {
+    /// Placeholder for signers that did not sign, never valid.
+    fn default() -> Self {
+        Self {
+            r: EmbeddedAffine::<E>::generator(),
+            s: EmbeddedScalar::<E>::ZERO,
+        }
+    }
+}
+
+/// intent = H(H(H(nullifier, diff_hash), public_address), aux), hashed in
+/// [`Domain::Multisig`].
+pub fn intent_hash<F: PrimeField>(
+    nullifier: &F,
+    diff_hash: &F,
+    public_address: &F,
+    aux: &F,
+    hasher: &impl TwoToOneHash<F>,
+) -> F {
+    [diff_hash, public_address, aux]
+        .into_iter()
+        .fold(*nullifier, |acc, v| {
+            hasher.hash_in(Domain::Multisig, &acc, v)
+        })
+}
+
+/// c = H(H(H(H(R.x, R.y), A.x), A.y), msg), hashed in [`Domain::Signature`].
+fn challenge<E: Curve>(
+    r: &EmbeddedAffine<E>,
+    a: &EmbeddedAffine<E>,
+    msg: &E::ScalarField,
+    hasher: &impl TwoToOneHash<E::ScalarField>,
+) -> E::ScalarField {
+    [r.y, a.x, a.y, *msg]
+        .iter()
+        .fold(r.x, |acc, v| hasher.hash_in(Domain::Signature, &acc, v))
+}
+
+/// Reduces a hash into the scalar field, as the circuit multiplies a point of
+/// prime order by its bits.
+fn to_scalar<E: Curve>(value: &E::ScalarField) -> EmbeddedScalar<E> {
+    EmbeddedScalar::<E>::from_le_bytes_mod_order(&value.into_bigint().to_bytes_le())
+}
+
+pub struct SignatureVar<E: Curve = Bn254> {
+    r: EmbeddedVar<E>,
+    s: Vec<Boolean<E::ScalarField>>,
+}
+
+impl<E: Curve> SignatureVar<E> {
+    /// Returns whether the signature is valid, without enforcing it.
+    pub fn verify(
+        &self,
+        public_key: &EmbeddedVar<E>,
+        msg: &FpVar<E::ScalarField>,
+        hasher: &impl TwoToOneHashVar<E::ScalarField>,
+    ) -> Result<Boolean<E::ScalarField>, SynthesisError> {
+        // c = H(H(H(H(R.x, R.y), A.x), A.y), msg), in the signature domain
+        let c = hash_vector(
+            hasher,
+            Domain::Signature,
+            &self.r.x,
+            [&self.r.y, &public_key.x, &public_key.y, msg],
+        )?;
+
+        // s * G == R + c * A
+        let lhs = fixed_base_mul::<E>(EmbeddedProjective::<E>::generator(), &self.s)?;
+        let rhs = self.r.clone() + public_key.scalar_mul_le(c.to_bits_le()?.iter())?;
+        lhs.is_eq(&rhs)
+    }
+}
+
+impl<E: Curve> AllocVar<Signature<E>, E::ScalarField> for SignatureVar<E> {
+    fn new_variable<T: Borrow<Signature<E>>>(
+        cs: impl Into<Namespace<E::ScalarField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let signature = f().map(|s| *s.borrow());
+
+        let r = EmbeddedVar::<E>::new_variable(
+            cs.clone(),
+            || signature.map(|s| s.r.into_group()),
+            mode,
+        )?;
+        let s = Vec::<Boolean<E::ScalarField>>::new_variable(
+            cs,
+            || signature.map(|sig| scalar_bits::<E>(&sig.s)),
+            mode,
+        )?;
+
+        Ok(Self { r, s })
+    }
+}
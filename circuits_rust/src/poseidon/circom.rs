@@ -0,0 +1,69 @@
+use std::sync::OnceLock;
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{constraints::PoseidonSpongeVar, PoseidonConfig, PoseidonSponge},
+    CryptographicSponge,
+};
+use ark_r1cs_std::{fields::fp::FpVar, R1CSVar};
+use ark_relations::r1cs::SynthesisError;
+
+use super::grain::PoseidonParameters;
+
+/// Largest number of inputs supported by circomlib and Sui's
+/// `poseidon::poseidon_bn254`.
+pub const MAX_ARITY: usize = 16;
+
+/// Partial rounds for widths 2 to 17, as in circomlib's `PoseidonEx`.
+const PARTIAL_ROUNDS: [usize; MAX_ARITY] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+/// circomlib's `Poseidon(arity)` instance: width `arity + 1`, alpha = 5, 8
+/// full rounds and circomlib's partial rounds for 128-bit security.
+pub(crate) fn circom_parameters(arity: usize) -> PoseidonParameters {
+    assert!(
+        (1..=MAX_ARITY).contains(&arity),
+        "Unsupported Poseidon arity {}",
+        arity
+    );
+    PoseidonParameters {
+        width: arity + 1,
+        alpha: 5,
+        full_rounds: 8,
+        partial_rounds: PARTIAL_ROUNDS[arity - 1],
+    }
+}
+
+/// Sponge parameters of circomlib's `Poseidon(arity)`, generated once on
+/// first use.
+pub fn circom_config(arity: usize) -> &'static PoseidonConfig<Fr> {
+    static CONFIGS: [OnceLock<PoseidonConfig<Fr>>; MAX_ARITY] =
+        [const { OnceLock::new() }; MAX_ARITY];
+    let parameters = circom_parameters(arity);
+    CONFIGS[arity - 1].get_or_init(|| parameters.generate())
+}
+
+/// circomlib `Poseidon(inputs.len())`: the inputs fill the rate with a zero
+/// capacity and the output is the first element of the permuted state.
+pub fn circom_hash(inputs: &[Fr]) -> Fr {
+    let mut sponge = PoseidonSponge::new(circom_config(inputs.len()));
+    sponge.absorb(&inputs);
+    sponge.squeeze_field_elements::<Fr>(1);
+    sponge.state[0]
+}
+
+/// Gadget of [`circom_hash`].
+pub fn circom_hash_var(inputs: &[FpVar<Fr>]) -> Result<FpVar<Fr>, SynthesisError> {
+    let cs = inputs.cs();
+
+    if cs.is_none() {
+        Ok(FpVar::Constant(circom_hash(&inputs.value()?)))
+    } else {
+        let mut sponge = PoseidonSpongeVar::new(cs, circom_config(inputs.len()));
+        sponge.absorb(&inputs)?;
+        sponge.squeeze_field_elements(1)?;
+        Ok(sponge.state[0].clone())
+    }
+}
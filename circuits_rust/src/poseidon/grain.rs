@@ -0,0 +1,147 @@
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_ff::{BigInteger, PrimeField};
+
+/// A Poseidon instance with the x^alpha S-box, the inputs of the reference
+/// `generate_parameters_grain.sage` script. The round counts are supplied by
+/// the caller and taken as they are: nothing here derives them from a
+/// security level or checks them against one. circomlib's are in
+/// [`super::circom::circom_parameters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoseidonParameters {
+    pub width: usize,
+    pub alpha: u64,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+}
+
+impl PoseidonParameters {
+    /// Generates the round constants and the MDS matrix over `F` with the
+    /// Grain LFSR, for a sponge of rate `width - 1` and capacity 1.
+    ///
+    /// The MDS matrix is not checked: the reference script rejects matrices
+    /// that fail its invariant subspace checks and samples again, this keeps
+    /// the first one. It is only used for circomlib's instances, whose first
+    /// matrices are the ones circomlib ships.
+    pub fn generate<F: PrimeField>(&self) -> PoseidonConfig<F> {
+        let field_bits = F::MODULUS_BIT_SIZE as usize;
+        let mut grain = Grain::new(
+            field_bits,
+            self.width,
+            self.full_rounds,
+            self.partial_rounds,
+        );
+
+        // constants are rejection sampled below the modulus
+        let ark = (0..self.full_rounds + self.partial_rounds)
+            .map(|_| {
+                (0..self.width)
+                    .map(|_| loop {
+                        if let Some(c) = F::from_bigint(grain.next_bigint::<F>(field_bits)) {
+                            break c;
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        PoseidonConfig {
+            full_rounds: self.full_rounds,
+            partial_rounds: self.partial_rounds,
+            alpha: self.alpha,
+            ark,
+            mds: cauchy_mds(&mut grain, field_bits, self.width),
+            rate: self.width - 1,
+            capacity: 1,
+        }
+    }
+}
+
+/// M[i][j] = 1 / (x[i] + y[j]) for distinct x and y sampled from the LFSR,
+/// resampled until no sum is zero.
+fn cauchy_mds<F: PrimeField>(grain: &mut Grain, field_bits: usize, width: usize) -> Vec<Vec<F>> {
+    loop {
+        let sample = loop {
+            let sample = (0..2 * width)
+                .map(|_| {
+                    F::from_le_bytes_mod_order(&grain.next_bigint::<F>(field_bits).to_bytes_le())
+                })
+                .collect::<Vec<_>>();
+            if (1..sample.len()).all(|i| !sample[..i].contains(&sample[i])) {
+                break sample;
+            }
+        };
+        let (xs, ys) = sample.split_at(width);
+
+        let mds = xs
+            .iter()
+            .map(|x| ys.iter().map(|y| (*x + y).inverse()).collect())
+            .collect::<Option<Vec<Vec<_>>>>();
+        if let Some(mds) = mds {
+            return mds;
+        }
+    }
+}
+
+/// The 80-bit Grain LFSR of the Poseidon reference implementation, seeded
+/// with the instance parameters.
+struct Grain {
+    state: [bool; 80],
+    /// Index of the oldest bit.
+    head: usize,
+}
+
+impl Grain {
+    fn new(field_bits: usize, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = [true; 80];
+        // field = 1 (prime field, 2 bits), sbox = 0 (x^alpha, 4 bits), then the
+        // field size, width and round numbers, padded with ones
+        let seed = [
+            (1, 2),
+            (0, 4),
+            (field_bits, 12),
+            (width, 12),
+            (full_rounds, 10),
+            (partial_rounds, 10),
+        ];
+        let mut i = 0;
+        for (value, bits) in seed {
+            for bit in (0..bits).rev() {
+                state[i] = (value >> bit) & 1 == 1;
+                i += 1;
+            }
+        }
+
+        let mut grain = Self { state, head: 0 };
+        for _ in 0..160 {
+            grain.clock();
+        }
+        grain
+    }
+
+    /// b[i + 80] = b[i + 62] ^ b[i + 51] ^ b[i + 38] ^ b[i + 23] ^ b[i + 13] ^ b[i]
+    fn clock(&mut self) -> bool {
+        let bit = |offset: usize| self.state[(self.head + offset) % 80];
+        let new = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.state[self.head] = new;
+        self.head = (self.head + 1) % 80;
+        new
+    }
+
+    /// Self-shrinking output: bits are read in pairs and the second one is
+    /// kept only if the first is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.clock();
+            let bit = self.clock();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// `bits` output bits as a big-endian integer.
+    fn next_bigint<F: PrimeField>(&mut self, bits: usize) -> F::BigInt {
+        let bits = (0..bits).map(|_| self.next_bit()).collect::<Vec<_>>();
+        F::BigInt::from_bits_be(&bits)
+    }
+}
@@ -0,0 +1,195 @@
+use std::{borrow::Borrow, sync::OnceLock};
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{constraints::PoseidonSpongeVar, PoseidonConfig},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::fp::FpVar,
+    R1CSVar,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+
+use self::optimized::OptimizedConfig;
+use crate::hash::{Domain, TwoToOneHash, TwoToOneHashVar};
+
+pub mod circom;
+pub(crate) mod grain;
+pub mod optimized;
+
+/// Poseidon width = 3 and alpha = 5 for BN254, circomlib's `Poseidon(2)`.
+pub fn poseidon_bn254() -> PoseidonConfig<Fr> {
+    circom::circom_config(2).clone()
+}
+
+/// The instance of [`poseidon_bn254`] generated for the scalar field of
+/// BLS12-381. The round counts are reused as they are for this field of
+/// about the same size, and its MDS matrix is not checked against the
+/// invariant subspace checks of the reference script.
+pub fn poseidon_bls12_381() -> PoseidonConfig<ark_bls12_381::Fr> {
+    static CONFIG: OnceLock<PoseidonConfig<ark_bls12_381::Fr>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| circom::circom_parameters(2).generate())
+        .clone()
+}
+
+/// "diff", start of the diff hash chain. The chain is computed on chain by
+/// `coin_diff.move` with Sui's native Poseidon, which has no capacity input,
+/// so it is separated from tree nodes by its first input instead.
+pub const DIFF_HASH_DOMAIN: u64 = 0x64696666;
+
+/// Fewest pairs worth a thread of their own in
+/// [`PoseidonHash::hash_many_pairs`].
+const MIN_PAIRS_PER_THREAD: usize = 1 << 10;
+
+#[derive(Debug, Clone)]
+pub struct PoseidonHash<F: PrimeField = Fr> {
+    pub config: PoseidonConfig<F>,
+    /// Constants of the native permutation.
+    optimized: OptimizedConfig<F>,
+}
+
+impl<F: PrimeField> PoseidonHash<F> {
+    pub fn new(config: PoseidonConfig<F>) -> Self {
+        assert_eq!(
+            (config.rate, config.capacity),
+            (2, 1),
+            "PoseidonHash needs a width 3 sponge"
+        );
+        let optimized = OptimizedConfig::new(&config);
+        Self { config, optimized }
+    }
+
+    /// Hash in the [`Domain::Node`] domain.
+    pub fn hash(&self, left: &F, right: &F) -> F {
+        self.hash_in(Domain::Node, left, right)
+    }
+
+    /// The domain is the initial capacity element, [`Domain::Node`] keeps it
+    /// zero as in circomlib.
+    pub fn hash_in(&self, domain: Domain, left: &F, right: &F) -> F {
+        let mut state = [F::from(domain as u64), *left, *right];
+        self.optimized.permute(&mut state);
+        state[0]
+    }
+
+    /// [`PoseidonHash::hash`] of every pair, split over the available cores
+    /// when there are enough pairs. wasm has no threads and hashes them in
+    /// order.
+    pub fn hash_many_pairs(&self, pairs: &[(F, F)]) -> Vec<F> {
+        let cores = match cfg!(target_arch = "wasm32") {
+            true => 1,
+            false => std::thread::available_parallelism().map_or(1, usize::from),
+        };
+        let threads = cores.min(pairs.len() / MIN_PAIRS_PER_THREAD).max(1);
+        if threads == 1 {
+            return pairs.iter().map(|(l, r)| self.hash(l, r)).collect();
+        }
+
+        std::thread::scope(|scope| {
+            pairs
+                .chunks(pairs.len().div_ceil(threads))
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(l, r)| self.hash(l, r))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Hashing thread panicked"))
+                .collect()
+        })
+    }
+}
+
+pub struct PoseidonHashVar<F: PrimeField = Fr> {
+    pub hasher: PoseidonHash<F>,
+}
+
+impl<F: PrimeField> PoseidonHashVar<F> {
+    /// Hash in the [`Domain::Node`] domain.
+    pub fn hash(&self, left: &FpVar<F>, right: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+        self.hash_in(Domain::Node, left, right)
+    }
+
+    pub fn hash_in(
+        &self,
+        domain: Domain,
+        left: &FpVar<F>,
+        right: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let cs = left.cs().or(right.cs());
+
+        if cs.is_none() {
+            Ok(FpVar::Constant(self.hasher.hash_in(
+                domain,
+                &left.value()?,
+                &right.value()?,
+            )))
+        } else {
+            let mut sponge = PoseidonSpongeVar::new(cs, &self.hasher.config);
+            sponge.state[0] = FpVar::Constant(F::from(domain as u64));
+            sponge.absorb(left)?;
+            sponge.absorb(right)?;
+            sponge.squeeze_field_elements(1)?;
+            Ok(sponge.state[0].clone())
+        }
+    }
+}
+
+impl<F: PrimeField> TwoToOneHash<F> for PoseidonHash<F> {
+    type Var = PoseidonHashVar<F>;
+
+    fn hash_in(&self, domain: Domain, left: &F, right: &F) -> F {
+        PoseidonHash::hash_in(self, domain, left, right)
+    }
+
+    fn hash_many_pairs(&self, pairs: &[(F, F)]) -> Vec<F> {
+        PoseidonHash::hash_many_pairs(self, pairs)
+    }
+}
+
+impl<F: PrimeField> TwoToOneHashVar<F> for PoseidonHashVar<F> {
+    fn hash_in(
+        &self,
+        domain: Domain,
+        left: &FpVar<F>,
+        right: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        PoseidonHashVar::hash_in(self, domain, left, right)
+    }
+}
+
+impl<F: PrimeField> AllocVar<PoseidonHash<F>, F> for PoseidonHashVar<F> {
+    fn new_variable<T: Borrow<PoseidonHash<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        // the parameters are always constants
+        let _ = (cs, mode);
+        f().map(|param| Self {
+            hasher: param.borrow().clone(),
+        })
+    }
+}
+
+impl<F: PrimeField> AllocVar<PoseidonConfig<F>, F> for PoseidonHashVar<F> {
+    fn new_variable<T: Borrow<PoseidonConfig<F>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        // the parameters are always constants
+        let _ = (cs, mode);
+        f().map(|param| Self {
+            hasher: PoseidonHash::new(param.borrow().clone()),
+        })
+    }
+}
@@ -0,0 +1,192 @@
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_ff::PrimeField;
+
+/// Matrix of a partial round after the optimisation, the identity except for
+/// its first row and column:
+/// s'[0] = row . s, s'[i] = column[i - 1] * s[0] + s[i].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<F> {
+    pub row: Vec<F>,
+    pub column: Vec<F>,
+}
+
+/// Poseidon constants rewritten for the partial-round optimisation of the
+/// Poseidon paper, appendix B. Every MDS multiplication of a partial round is
+/// split into a sparse matrix and a matrix that leaves `s[0]` alone. The
+/// latter commutes with the S-box of the partial round and is pushed back
+/// into the previous round, so that only the first partial round keeps a
+/// dense matrix. Computes the same permutation as the [`PoseidonConfig`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizedConfig<F> {
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub alpha: u64,
+    pub width: usize,
+    /// Round constants, those of partial rounds are premultiplied by the
+    /// matrices pushed back through them.
+    pub ark: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+    /// Matrix of the first partial round.
+    pub pre_sparse: Vec<Vec<F>>,
+    /// Matrices of the other partial rounds.
+    pub sparse: Vec<SparseMatrix<F>>,
+}
+
+impl<F: PrimeField> OptimizedConfig<F> {
+    pub fn new(config: &PoseidonConfig<F>) -> Self {
+        assert_eq!(config.alpha, 5, "Only the x^5 S-box is supported");
+        let width = config.rate + config.capacity;
+        let first_partial = config.full_rounds / 2;
+        let mut ark = config.ark.clone();
+        let mut sparse = vec![];
+
+        // the matrix applied after the S-box of the current round, starting
+        // from the last partial round
+        let mut acc = config.mds.clone();
+        for r in (first_partial + 1..first_partial + config.partial_rounds).rev() {
+            // acc = sparse * diag(1, hat)
+            let hat = acc[1..]
+                .iter()
+                .map(|row| row[1..].to_vec())
+                .collect::<Vec<_>>();
+            let hat_inv = inverse(&hat);
+            let row = std::iter::once(acc[0][0])
+                .chain(
+                    (0..width - 1)
+                        .map(|j| (0..width - 1).map(|k| acc[0][k + 1] * hat_inv[k][j]).sum()),
+                )
+                .collect();
+            let column = acc[1..].iter().map(|row| row[0]).collect();
+            sparse.push(SparseMatrix { row, column });
+
+            // diag(1, hat) moves before the S-box and the round constants
+            let pre = (0..width)
+                .map(|i| {
+                    (0..width)
+                        .map(|j| match (i, j) {
+                            (0, 0) => F::ONE,
+                            (0, _) | (_, 0) => F::ZERO,
+                            _ => hat[i - 1][j - 1],
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            ark[r] = mul_vec(&pre, &ark[r]);
+            acc = mul(&pre, &config.mds);
+        }
+        sparse.reverse();
+
+        Self {
+            full_rounds: config.full_rounds,
+            partial_rounds: config.partial_rounds,
+            alpha: config.alpha,
+            width,
+            ark,
+            mds: config.mds.clone(),
+            pre_sparse: acc,
+            sparse,
+        }
+    }
+}
+
+impl<F: PrimeField> OptimizedConfig<F> {
+    /// Poseidon permutation of `state` in place, without allocating. The
+    /// matrix rows are inner products with a single modular reduction.
+    pub fn permute<const W: usize>(&self, state: &mut [F; W]) {
+        assert_eq!(W, self.width, "Invalid Poseidon state width");
+        let half = self.full_rounds / 2;
+        let sbox = |x: &mut F| *x *= x.square().square();
+        let dense = |matrix: &[Vec<F>], state: &mut [F; W]| {
+            let old = *state;
+            for (s, row) in state.iter_mut().zip(matrix) {
+                *s = F::sum_of_products(row.as_slice().try_into().expect("Square matrix"), &old);
+            }
+        };
+        let add = |constants: &[F], state: &mut [F; W]| {
+            state.iter_mut().zip(constants).for_each(|(s, c)| *s += c);
+        };
+
+        for r in 0..half {
+            add(&self.ark[r], state);
+            state.iter_mut().for_each(sbox);
+            dense(&self.mds, state);
+        }
+
+        add(&self.ark[half], state);
+        sbox(&mut state[0]);
+        dense(&self.pre_sparse, state);
+        for (r, matrix) in (half + 1..).zip(&self.sparse) {
+            add(&self.ark[r], state);
+            sbox(&mut state[0]);
+            let first = F::sum_of_products(
+                matrix
+                    .row
+                    .as_slice()
+                    .try_into()
+                    .expect("Row of the state width"),
+                state,
+            );
+            let head = state[0];
+            for (s, c) in state[1..].iter_mut().zip(&matrix.column) {
+                *s += *c * head;
+            }
+            state[0] = first;
+        }
+
+        for r in half + self.partial_rounds..self.full_rounds + self.partial_rounds {
+            add(&self.ark[r], state);
+            state.iter_mut().for_each(sbox);
+            dense(&self.mds, state);
+        }
+    }
+}
+
+fn mul<F: PrimeField>(a: &[Vec<F>], b: &[Vec<F>]) -> Vec<Vec<F>> {
+    a.iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|j| row.iter().zip(b).map(|(x, b)| *x * b[j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+fn mul_vec<F: PrimeField>(a: &[Vec<F>], v: &[F]) -> Vec<F> {
+    a.iter()
+        .map(|row| row.iter().zip(v).map(|(x, y)| *x * y).sum())
+        .collect()
+}
+
+/// Gauss-Jordan inverse, the submatrices of an MDS matrix are invertible.
+fn inverse<F: PrimeField>(m: &[Vec<F>]) -> Vec<Vec<F>> {
+    let n = m.len();
+    let mut a = m
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            row.extend((0..n).map(|j| if i == j { F::ONE } else { F::ZERO }));
+            row
+        })
+        .collect::<Vec<_>>();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .find(|i| !a[*i][col].is_zero())
+            .expect("Singular MDS submatrix");
+        a.swap(col, pivot);
+        let inv = a[col][col].inverse().expect("Nonzero pivot");
+        a[col].iter_mut().for_each(|x| *x *= inv);
+        for i in (0..n).filter(|i| *i != col) {
+            let factor = a[i][col];
+            if !factor.is_zero() {
+                let pivot_row = a[col].clone();
+                a[i].iter_mut()
+                    .zip(&pivot_row)
+                    .for_each(|(x, p)| *x -= factor * p);
+            }
+        }
+    }
+
+    a.into_iter().map(|row| row[n..].to_vec()).collect()
+}
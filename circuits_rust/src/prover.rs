@@ -0,0 +1,44 @@
+use anyhow::ensure;
+use ark_crypto_primitives::snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_groth16::{Groth16, ProvingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{CryptoRng, RngCore};
+
+use crate::curve::Curve;
+
+/// Groth16 keys of `circuit` over `E`, the proving key uncompressed and the
+/// verifying key compressed as Sui's `groth16::prepare_verifying_key` takes it.
+pub fn setup<E: Curve, C: ConstraintSynthesizer<E::ScalarField>>(
+    circuit: C,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let (pk, vk) = Groth16::<E>::setup(circuit, rng)?;
+
+    let mut pk_bytes = vec![];
+    pk.serialize_uncompressed(&mut pk_bytes)?;
+    let mut vk_bytes = vec![];
+    vk.serialize_compressed(&mut vk_bytes)?;
+
+    Ok((pk_bytes, vk_bytes))
+}
+
+/// Checks that `circuit` is satisfied and proves it with a proving key from
+/// [`setup`], returning the compressed proof.
+pub fn prove<E: Curve, C: ConstraintSynthesizer<E::ScalarField> + Clone>(
+    pk_bytes: &[u8],
+    circuit: C,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> anyhow::Result<Vec<u8>> {
+    let pk = ProvingKey::<E>::deserialize_uncompressed_unchecked(pk_bytes)?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.clone().generate_constraints(cs.clone())?;
+    ensure!(cs.is_satisfied()?, "Constraints are not satisfied");
+
+    let proof = Groth16::<E>::prove(&pk, circuit, rng)?;
+    let mut proof_bytes = vec![];
+    proof.serialize_compressed(&mut proof_bytes)?;
+
+    Ok(proof_bytes)
+}
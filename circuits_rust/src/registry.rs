@@ -0,0 +1,295 @@
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::Fr;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+
+use crate::{
+    coin_diff::{type_name, CoinDiff},
+    gadgets::from_i128,
+    ASSET_SIZE,
+};
+
+/// Largest number of decimals whose unit, 10^decimals, is a u64.
+const MAX_DECIMALS: u8 = 19;
+
+/// Assets of a deployed pool, as read from its `Shroud` object and the coin
+/// metadata of its tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolDescriptor {
+    /// `allowed_token_length` of the pool, the number of slots of a diff.
+    pub allowed_token_length: usize,
+    /// `allowed_tokens`, in the order `core::allow_token` was called.
+    pub allowed_tokens: Vec<AssetInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetInfo {
+    pub coin_type: String,
+    #[serde(default)]
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Circuit slot and decimals of every coin type of a pool. The slot of a coin
+/// type is its index in `allowed_tokens`, as in `coin_diff::empty`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetRegistry {
+    total: usize,
+    assets: Vec<AssetInfo>,
+}
+
+impl AssetRegistry {
+    pub fn new(descriptor: PoolDescriptor) -> anyhow::Result<Self> {
+        let PoolDescriptor {
+            allowed_token_length: total,
+            allowed_tokens,
+        } = descriptor;
+        ensure!(
+            total == ASSET_SIZE,
+            "Pool has {total} slots, the circuit takes {ASSET_SIZE}"
+        );
+        ensure!(
+            allowed_tokens.len() <= total,
+            "Pool allows {} tokens in {total} slots",
+            allowed_tokens.len()
+        );
+
+        let mut assets: Vec<AssetInfo> = vec![];
+        for asset in allowed_tokens {
+            let coin_type = type_name(&asset.coin_type)?;
+            ensure!(
+                assets.iter().all(|a| a.coin_type != coin_type),
+                "Coin type {coin_type} is allowed twice"
+            );
+            ensure!(
+                asset.decimals <= MAX_DECIMALS,
+                "{coin_type} has {} decimals, at most {MAX_DECIMALS} are supported",
+                asset.decimals
+            );
+            assets.push(AssetInfo { coin_type, ..asset });
+        }
+
+        Ok(Self { total, assets })
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    /// Assets by slot.
+    pub fn assets(&self) -> &[AssetInfo] {
+        &self.assets
+    }
+
+    /// Slot of `coin_type` in balances and diffs.
+    pub fn slot(&self, coin_type: &str) -> anyhow::Result<usize> {
+        let coin_type = type_name(coin_type)?;
+        self.assets
+            .iter()
+            .position(|a| a.coin_type == coin_type)
+            .ok_or_else(|| anyhow!("Coin type {coin_type} is not allowed"))
+    }
+
+    pub fn asset(&self, coin_type: &str) -> anyhow::Result<&AssetInfo> {
+        Ok(&self.assets[self.slot(coin_type)?])
+    }
+
+    /// Empty diff of the pool.
+    pub fn coin_diff(&self) -> CoinDiff {
+        let coin_types = self.assets.iter().map(|a| &a.coin_type).collect::<Vec<_>>();
+        CoinDiff::empty(self.total, &coin_types).expect("Coin types are normalized and unique")
+    }
+
+    /// Diff in base units of changing the balance of each coin type by its
+    /// amount, amounts of the same coin type add up.
+    pub fn diff(&self, amounts: &[(&str, i128)]) -> anyhow::Result<[i128; ASSET_SIZE]> {
+        let mut diff = [0i128; ASSET_SIZE];
+        for (coin_type, amount) in amounts {
+            let slot = self.slot(coin_type)?;
+            diff[slot] = diff[slot]
+                .checked_add(*amount)
+                .filter(|d| from_i128::<Fr>(*d).is_some())
+                .ok_or_else(|| anyhow!("Diff of {coin_type} is out of range"))?;
+        }
+        Ok(diff)
+    }
+
+    /// Diff of a public operation, depositing the positive amounts and
+    /// withdrawing the negative ones, as `core::deposit_coin` and
+    /// `core::withdraw_coin` add them up.
+    pub fn operation_diff(&self, amounts: &[(&str, i128)]) -> anyhow::Result<CoinDiff> {
+        let mut coin_diff = self.coin_diff();
+        for (coin_type, amount) in amounts {
+            let magnitude = u64::try_from(amount.unsigned_abs())
+                .map_err(|_| anyhow!("Amount {amount} of {coin_type} is out of range"))?;
+            if *amount < 0 {
+                coin_diff.sub_coin(coin_type, magnitude)?;
+            } else {
+                coin_diff.add_coin(coin_type, magnitude)?;
+            }
+        }
+        // the circuit only takes diffs between two balances
+        coin_diff.diffs()?;
+        Ok(coin_diff)
+    }
+
+    /// Parses a decimal amount of `coin_type`, e.g. "1.5", into base units.
+    pub fn parse_amount(&self, coin_type: &str, amount: &str) -> anyhow::Result<u64> {
+        let (is_negative, units) = parse_units(amount, self.asset(coin_type)?.decimals)?;
+        ensure!(!is_negative, "Amount {amount} is negative");
+        Ok(units)
+    }
+
+    /// Parses a signed decimal amount of `coin_type`, e.g. "-1.5", into base
+    /// units.
+    pub fn parse_signed(&self, coin_type: &str, amount: &str) -> anyhow::Result<i128> {
+        let (is_negative, units) = parse_units(amount, self.asset(coin_type)?.decimals)?;
+        Ok(if is_negative {
+            -i128::from(units)
+        } else {
+            i128::from(units)
+        })
+    }
+
+    /// Formats base units of `coin_type` as a decimal amount, without
+    /// trailing zeros.
+    pub fn format_amount(&self, coin_type: &str, units: u64) -> anyhow::Result<String> {
+        Ok(format_units(units, self.asset(coin_type)?.decimals))
+    }
+
+    /// Signed decimal amounts of the coin types at the same positions.
+    fn parse_amounts(
+        &self,
+        coin_types: &[String],
+        amounts: &[String],
+    ) -> anyhow::Result<Vec<(String, i128)>> {
+        ensure!(
+            coin_types.len() == amounts.len(),
+            "{} coin types for {} amounts",
+            coin_types.len(),
+            amounts.len()
+        );
+        coin_types
+            .iter()
+            .zip(amounts)
+            .map(|(c, a)| Ok((c.clone(), self.parse_signed(c, a)?)))
+            .collect()
+    }
+
+    fn borrow_amounts(amounts: &[(String, i128)]) -> Vec<(&str, i128)> {
+        amounts.iter().map(|(c, a)| (c.as_str(), *a)).collect()
+    }
+}
+
+#[wasm_bindgen]
+impl AssetRegistry {
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn wasm_from_json(json: &str) -> Result<AssetRegistry, JsError> {
+        Self::from_json(json).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = getCoinTypes)]
+    pub fn wasm_get_coin_types(&self) -> Vec<String> {
+        self.assets.iter().map(|a| a.coin_type.clone()).collect()
+    }
+
+    #[wasm_bindgen(js_name = getSlot)]
+    pub fn wasm_get_slot(&self, coin_type: String) -> Result<usize, JsError> {
+        self.slot(&coin_type)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = getDecimals)]
+    pub fn wasm_get_decimals(&self, coin_type: String) -> Result<u8, JsError> {
+        self.asset(&coin_type)
+            .map(|a| a.decimals)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = parseAmount)]
+    pub fn wasm_parse_amount(&self, coin_type: String, amount: String) -> Result<u64, JsError> {
+        self.parse_amount(&coin_type, &amount)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = formatAmount)]
+    pub fn wasm_format_amount(&self, coin_type: String, units: u64) -> Result<String, JsError> {
+        self.format_amount(&coin_type, units)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Diffs to pass to `prove` from signed decimal amounts, e.g. "-1.5", of
+    /// the coin types at the same positions.
+    #[wasm_bindgen(js_name = getDiffs)]
+    pub fn wasm_get_diffs(
+        &self,
+        coin_types: Vec<String>,
+        amounts: Vec<String>,
+    ) -> Result<Vec<String>, JsError> {
+        self.parse_amounts(&coin_types, &amounts)
+            .and_then(|amounts| self.diff(&Self::borrow_amounts(&amounts)))
+            .map(|diff| diff.iter().map(i128::to_string).collect())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Diff of a public operation to pass to `provePublicOperation`, from
+    /// decimal amounts to deposit, e.g. "1.5", or to withdraw, e.g. "-1.5".
+    #[wasm_bindgen(js_name = getOperationDiff)]
+    pub fn wasm_get_operation_diff(
+        &self,
+        coin_types: Vec<String>,
+        amounts: Vec<String>,
+    ) -> Result<CoinDiff, JsError> {
+        self.parse_amounts(&coin_types, &amounts)
+            .and_then(|amounts| self.operation_diff(&Self::borrow_amounts(&amounts)))
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = coinDiff)]
+    pub fn wasm_coin_diff(&self) -> CoinDiff {
+        self.coin_diff()
+    }
+}
+
+/// Sign and base units of a decimal amount with at most `decimals` fractional
+/// digits.
+fn parse_units(amount: &str, decimals: u8) -> anyhow::Result<(bool, u64)> {
+    let trimmed = amount.trim();
+    let (is_negative, digits) = match trimmed.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() && fraction.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        bail!("Invalid amount {amount}");
+    }
+    ensure!(
+        fraction.len() <= decimals as usize,
+        "Amount {amount} has more than {decimals} decimals"
+    );
+
+    let units = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    let units = units.trim_start_matches('0');
+    let units = if units.is_empty() {
+        0
+    } else {
+        units
+            .parse::<u64>()
+            .map_err(|_| anyhow!("Amount {amount} is out of range"))?
+    };
+
+    Ok((is_negative && units != 0, units))
+}
+
+fn format_units(units: u64, decimals: u8) -> String {
+    let unit = 10u64.pow(decimals as u32);
+    let fraction = format!("{:0>width$}", units % unit, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (units / unit).to_string()
+    } else {
+        format!("{}.{fraction}", units / unit)
+    }
+}
@@ -0,0 +1,427 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
+
+use crate::{
+    memo::{MemoScanner, RecoveredNote},
+    merkle_tree::SparseMerkleTree,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    wasm::State,
+    LEVEL,
+};
+
+/// Position in the `core` module event stream, as returned by `queryEvents`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CanonicalSerialize, CanonicalDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCursor {
+    pub tx_digest: String,
+    pub event_seq: String,
+}
+
+/// Event as returned by the Sui JSON-RPC `queryEvents` endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawEvent {
+    pub id: SyncCursor,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub parsed_json: Value,
+}
+
+/// One page of `queryEvents` results.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+    pub data: Vec<RawEvent>,
+    pub next_cursor: Option<SyncCursor>,
+    pub has_next_page: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafInserted {
+    pub index: u64,
+    pub value: Fr,
+    pub new_root: Fr,
+    /// Encrypted [`crate::memo::NoteMemo`], empty for leaves without one.
+    pub memo: Vec<u8>,
+}
+
+/// Escrow ciphertext of the transition that inserted leaf `index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escrowed {
+    pub index: u64,
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullifierUsed {
+    pub nullifier: Fr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deposited {
+    pub coin_type: String,
+    pub account: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Withdrawn {
+    pub coin_type: String,
+    pub account: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Swapped {
+    pub origin: String,
+    pub target: String,
+    pub origin_amount: u64,
+    pub target_amount: u64,
+}
+
+/// Events emitted by `shroud::core`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShroudEvent {
+    LeafInserted(LeafInserted),
+    NullifierUsed(NullifierUsed),
+    Escrowed(Escrowed),
+    Deposited(Deposited),
+    Withdrawn(Withdrawn),
+    Swapped(Swapped),
+}
+
+impl ShroudEvent {
+    /// Decodes a raw event emitted by `package_id`. Returns `None` for events
+    /// that do not belong to `shroud::core`.
+    pub fn decode(package_id: &str, event: &RawEvent) -> anyhow::Result<Option<Self>> {
+        let (base, type_args) = split_type(&event.event_type)?;
+        let Some(name) = base
+            .rsplit_once("::")
+            .filter(|(module, _)| *module == format!("{}::core", normalize_address(package_id)))
+            .map(|(_, name)| name)
+        else {
+            return Ok(None);
+        };
+        let fields = &event.parsed_json;
+
+        let decoded = match (name, type_args.as_slice()) {
+            ("LeafInserted", []) => Self::LeafInserted(LeafInserted {
+                index: parse_u64(&fields["index"])?,
+                value: parse_u256(&fields["value"])?,
+                new_root: parse_u256(&fields["new_root"])?,
+                memo: parse_bytes(&fields["memo"])?,
+            }),
+            ("NullifierUsed", []) => Self::NullifierUsed(NullifierUsed {
+                nullifier: parse_u256(&fields["nullifier"])?,
+            }),
+            ("Escrowed", []) => Self::Escrowed(Escrowed {
+                index: parse_u64(&fields["index"])?,
+                ciphertext: parse_bytes(&fields["ciphertext"])?,
+            }),
+            ("Deposited", [coin_type]) => Self::Deposited(Deposited {
+                coin_type: coin_type.clone(),
+                account: parse_string(&fields["account"])?,
+                amount: parse_u64(&fields["amount"])?,
+            }),
+            ("Withdrawn", [coin_type]) => Self::Withdrawn(Withdrawn {
+                coin_type: coin_type.clone(),
+                account: parse_string(&fields["account"])?,
+                amount: parse_u64(&fields["amount"])?,
+            }),
+            ("Swapped", [origin, target]) => Self::Swapped(Swapped {
+                origin: origin.clone(),
+                target: target.clone(),
+                origin_amount: parse_u64(&fields["origin_amount"])?,
+                target_amount: parse_u64(&fields["target_amount"])?,
+            }),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(decoded))
+    }
+}
+
+/// Outcome of applying a page of events to a [`State`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    /// Number of new leaves appended to the local tree.
+    pub leafs_added: usize,
+    /// Set when the account's current leaf was found in the tree.
+    pub own_index: Option<usize>,
+    /// Set when the account's current nullifier was used on chain in any page
+    /// applied so far, i.e. the note was spent by another device and the
+    /// local balances are stale.
+    pub own_nullifier_spent: bool,
+    /// Latest note recovered from a memo, already applied to the account.
+    pub recovered: Option<RecoveredNote>,
+    /// Decoded events, in chain order.
+    pub events: Vec<ShroudEvent>,
+}
+
+/// Incrementally rebuilds the merkle tree of a [`State`] from chain events,
+/// checking every `LeafInserted::new_root` against the locally computed root.
+#[wasm_bindgen]
+pub struct Synchronizer {
+    package_id: String,
+    tree: SparseMerkleTree<LEVEL>,
+    hasher: PoseidonHash,
+    /// Nullifiers used in every page applied so far, the account's note may
+    /// be spent in an earlier page than the one that updates the account.
+    used_nullifiers: BTreeSet<Fr>,
+}
+
+impl Synchronizer {
+    pub fn new(package_id: &str, state: &State) -> anyhow::Result<Self> {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        let tree = SparseMerkleTree::new_sequential(state.leafs(), &hasher, &Fr::ZERO)?;
+
+        Ok(Self {
+            package_id: package_id.to_string(),
+            tree,
+            hasher,
+            used_nullifiers: BTreeSet::new(),
+        })
+    }
+
+    /// Applies one page of events and advances the state cursor. On error the
+    /// state keeps every leaf verified so far but the cursor is not advanced,
+    /// so the same page can be applied again.
+    pub fn apply_page(
+        &mut self,
+        state: &mut State,
+        page: &EventPage,
+    ) -> anyhow::Result<SyncReport> {
+        let events = page
+            .data
+            .iter()
+            .map(|e| ShroudEvent::decode(&self.package_id, e))
+            .filter_map(Result::transpose)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let report = self.apply(state, events)?;
+
+        if let Some(cursor) = page
+            .next_cursor
+            .clone()
+            .or_else(|| page.data.last().map(|e| e.id.clone()))
+        {
+            state.set_cursor(cursor);
+        }
+
+        Ok(report)
+    }
+
+    /// Applies decoded events in chain order. Memos that decrypt under the
+    /// account key restore its balances and index.
+    pub fn apply(
+        &mut self,
+        state: &mut State,
+        events: Vec<ShroudEvent>,
+    ) -> anyhow::Result<SyncReport> {
+        let mut report = SyncReport::default();
+        let own_leaf = state.account().leaf(&self.hasher);
+        let mut scanner = MemoScanner::new(state.account().clone(), self.hasher.clone());
+
+        for event in &events {
+            match event {
+                ShroudEvent::LeafInserted(leaf) => {
+                    if self.insert_leaf(state, leaf)? {
+                        report.leafs_added += 1;
+                    }
+                    if leaf.value == own_leaf {
+                        report.own_index = Some(leaf.index as usize);
+                    }
+                    scanner.scan(leaf.index, &leaf.value, &leaf.memo);
+                }
+                ShroudEvent::NullifierUsed(used) => {
+                    self.used_nullifiers.insert(used.nullifier);
+                }
+                _ => {}
+            }
+        }
+
+        let size = state.leafs().len() as u64;
+        let account = state.account_mut();
+        report.recovered = scanner
+            .latest()
+            .filter(|note| account.index().is_none_or(|i| note.index as usize >= i))
+            .copied();
+        if let Some(note) = &report.recovered {
+            account.restore(note);
+            report.own_index = Some(note.index as usize);
+        } else if let Some(index) = report.own_index {
+            account.wasm_set_index(index);
+        }
+        report.own_nullifier_spent = self
+            .used_nullifiers
+            .contains(&account.nullifier(&self.hasher));
+        account.set_synced_leafs(size);
+        report.events = events;
+
+        Ok(report)
+    }
+
+    /// Returns whether the leaf was new.
+    fn insert_leaf(&mut self, state: &mut State, leaf: &LeafInserted) -> anyhow::Result<bool> {
+        let index = leaf.index as usize;
+        let size = state.leafs().len();
+
+        if index < size {
+            ensure!(
+                state.leafs()[index] == leaf.value,
+                "Leaf {} conflicts with the local tree",
+                index
+            );
+            return Ok(false);
+        }
+        ensure!(
+            index == size,
+            "Missing leaves {}..{} before leaf {}",
+            size,
+            index,
+            index
+        );
+
+        self.tree
+            .insert_batch(&BTreeMap::from([(index as u32, leaf.value)]), &self.hasher)?;
+        if self.tree.root() != leaf.new_root {
+            // roll back so the tree stays in line with the state
+            self.tree
+                .insert_batch(&BTreeMap::from([(index as u32, Fr::ZERO)]), &self.hasher)?;
+            bail!(
+                "Root mismatch at leaf {}: expected {}, computed {}",
+                index,
+                leaf.new_root,
+                self.tree.root()
+            );
+        }
+        state.push_leaf(leaf.value);
+
+        Ok(true)
+    }
+}
+
+#[wasm_bindgen]
+impl Synchronizer {
+    #[wasm_bindgen(constructor)]
+    pub fn wasm_new(package_id: String, state: &State) -> Result<Synchronizer, JsError> {
+        Self::new(&package_id, state).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = applyPage)]
+    pub fn wasm_apply_page(
+        &mut self,
+        state: &mut State,
+        page: JsValue,
+    ) -> Result<JsValue, JsError> {
+        let page: EventPage = from_value(page)?;
+        let report = self
+            .apply_page(state, &page)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        Ok(to_value(&json!({
+            "leafs_added": report.leafs_added,
+            "own_index": report.own_index,
+            "own_nullifier_spent": report.own_nullifier_spent,
+            "recovered": report.recovered.map(|note| note.memo.balance),
+            "cursor": state.cursor(),
+            "has_next_page": page.has_next_page,
+        }))?)
+    }
+}
+
+/// Pads an address to 32 bytes and prefixes it with `0x`, as Sui does in
+/// event type names.
+fn normalize_address(address: &str) -> String {
+    let hex = address.trim_start_matches("0x").to_lowercase();
+    format!("0x{:0>64}", hex)
+}
+
+/// Splits `0x..::core::Swapped<A, B>` into its base type and type arguments.
+fn split_type(type_name: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let Some((base, rest)) = type_name.split_once('<') else {
+        return Ok((normalize_base(type_name), vec![]));
+    };
+    let args = rest
+        .strip_suffix('>')
+        .ok_or_else(|| anyhow!("Malformed event type {}", type_name))?;
+
+    let mut type_args = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                type_args.push(args[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    type_args.push(args[start..].trim().to_string());
+
+    Ok((normalize_base(base), type_args))
+}
+
+fn normalize_base(base: &str) -> String {
+    match base.split_once("::") {
+        Some((address, path)) => format!("{}::{}", normalize_address(address), path),
+        None => base.to_string(),
+    }
+}
+
+fn parse_string(value: &Value) -> anyhow::Result<String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Expected string, got {}", value))
+}
+
+fn parse_u64(value: &Value) -> anyhow::Result<u64> {
+    match value {
+        Value::String(s) => s.parse().with_context(|| format!("Invalid u64 {}", s)),
+        Value::Number(n) => n.as_u64().ok_or_else(|| anyhow!("Invalid u64 {}", n)),
+        _ => bail!("Expected u64, got {}", value),
+    }
+}
+
+/// Parses a `vector<u8>`, which Sui renders as an array of numbers.
+fn parse_bytes(value: &Value) -> anyhow::Result<Vec<u8>> {
+    match value {
+        Value::Null => Ok(vec![]),
+        Value::Array(bytes) => bytes
+            .iter()
+            .map(|b| {
+                b.as_u64()
+                    .and_then(|b| u8::try_from(b).ok())
+                    .ok_or_else(|| anyhow!("Invalid byte {}", b))
+            })
+            .collect(),
+        _ => bail!("Expected bytes, got {}", value),
+    }
+}
+
+/// Parses a decimal `u256` into a field element, rejecting values outside the
+/// field.
+fn parse_u256(value: &Value) -> anyhow::Result<Fr> {
+    let s = parse_string(value)?;
+    <Fr as PrimeField>::BigInt::from_str(&s)
+        .ok()
+        .and_then(Fr::from_bigint)
+        .ok_or_else(|| anyhow!("Invalid field element {}", s))
+}
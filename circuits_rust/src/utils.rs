@@ -0,0 +1,71 @@
+use anyhow::ensure;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+
+use crate::poseidon::circom::circom_hash;
+
+/// "addr", the first input of an encoded Sui address.
+pub const ADDRESS_DOMAIN: u64 = 0x61646472;
+
+/// Big-endian hex without `0x`, as returned to the frontend.
+pub fn fr_to_hex(fr: &Fr) -> String {
+    hex::encode(fr.into_bigint().to_bytes_be())
+}
+
+pub fn fr_from_hex(s: &str) -> anyhow::Result<Fr> {
+    Ok(Fr::from_be_bytes_mod_order(&hex::decode(
+        s.trim_start_matches("0x"),
+    )?))
+}
+
+/// Encodes a 32-byte Sui address as a field element,
+/// address_fr = Poseidon(ADDRESS_DOMAIN, hi, lo) where `hi` and `lo` are the
+/// big-endian 128-bit halves. Both limbs fit in the field, so unlike reducing
+/// the address modulo p, two addresses only collide on a Poseidon collision.
+/// The three-input circomlib hash, as `fr.move` computes it, shares no
+/// permutation with the two-to-one hashes of the circuits.
+pub fn address_to_fr(address: &[u8; 32]) -> Fr {
+    circom_hash(&[
+        Fr::from(ADDRESS_DOMAIN),
+        Fr::from_be_bytes_mod_order(&address[..16]),
+        Fr::from_be_bytes_mod_order(&address[16..]),
+    ])
+}
+
+/// Parses a Sui address, with or without `0x`, and encodes it with
+/// [`address_to_fr`].
+pub fn address_from_hex(s: &str) -> anyhow::Result<Fr> {
+    let bytes = hex::decode(s.trim_start_matches("0x"))?;
+    ensure!(bytes.len() == 32, "Invalid address length {}", bytes.len());
+    Ok(address_to_fr(&bytes.try_into().expect("Length checked")))
+}
+
+/// Serde adapter for field elements as big-endian hex strings.
+pub mod fr_hex {
+    use ark_bn254::Fr;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(fr: &Fr, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::fr_to_hex(fr))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fr, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::fr_from_hex(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Serde adapter for amounts as decimal strings, which JavaScript numbers
+/// cannot hold exactly above 2^53.
+pub mod u64_decimal {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(amount: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(amount)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
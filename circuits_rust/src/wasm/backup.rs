@@ -0,0 +1,167 @@
+use anyhow::{anyhow, ensure};
+use argon2::{Algorithm, Argon2, Params, Version};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::{thread_rng, RngCore};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+
+use super::Account;
+use crate::{ASSET_SIZE, LEVEL};
+
+const BACKUP_MAGIC: [u8; 4] = *b"SHRD";
+const BACKUP_VERSION: u8 = 1;
+
+/// Argon2id parameters, stored in the header so that they can be raised later
+/// without breaking older backups.
+#[derive(Debug, Clone, Copy, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB.
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// Largest parameters a backup may ask for, so that a crafted header
+    /// cannot make the KDF exhaust the memory or time of the wallet.
+    pub const MAX: Self = Self {
+        m_cost: 256 * 1024,
+        t_cost: 16,
+        p_cost: 4,
+    };
+
+    fn params(&self) -> anyhow::Result<Params> {
+        ensure!(
+            self.m_cost <= Self::MAX.m_cost
+                && self.t_cost <= Self::MAX.t_cost
+                && self.p_cost <= Self::MAX.p_cost,
+            "KDF parameters {self:?} exceed {:?}",
+            Self::MAX
+        );
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+            .map_err(|e| anyhow!("Invalid KDF parameters: {e}"))
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Plaintext header of an encrypted backup, authenticated as associated data.
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+struct BackupHeader {
+    magic: [u8; 4],
+    version: u8,
+    kdf: KdfParams,
+    level: u32,
+    asset_size: u32,
+    salt: [u8; 16],
+    nonce: [u8; 24],
+}
+
+impl BackupHeader {
+    fn derive_cipher(&self, password: &str) -> anyhow::Result<XChaCha20Poly1305> {
+        let params = self.kdf.params()?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), &self.salt, &mut key)
+            .map_err(|e| anyhow!("Key derivation failed: {e}"))?;
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+impl Account {
+    /// Serializes the account and encrypts it under a key derived from
+    /// `password`.
+    pub fn export_encrypted(&self, password: &str) -> anyhow::Result<Vec<u8>> {
+        self.export_encrypted_with(password, KdfParams::default())
+    }
+
+    pub fn export_encrypted_with(&self, password: &str, kdf: KdfParams) -> anyhow::Result<Vec<u8>> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 24];
+        thread_rng().fill_bytes(&mut salt);
+        thread_rng().fill_bytes(&mut nonce);
+
+        let header = BackupHeader {
+            magic: BACKUP_MAGIC,
+            version: BACKUP_VERSION,
+            kdf,
+            level: LEVEL as u32,
+            asset_size: ASSET_SIZE as u32,
+            salt,
+            nonce,
+        };
+        let mut data = vec![];
+        header.serialize_compressed(&mut data)?;
+
+        let mut plaintext = vec![];
+        self.serialize_compressed(&mut plaintext)?;
+
+        let ciphertext = header
+            .derive_cipher(password)?
+            .encrypt(
+                XNonce::from_slice(&header.nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &data,
+                },
+            )
+            .map_err(|_| anyhow!("Encryption failed"))?;
+        data.extend(ciphertext);
+
+        Ok(data)
+    }
+
+    /// Decrypts a backup produced by [`Account::export_encrypted`].
+    pub fn import_encrypted(data: &[u8], password: &str) -> anyhow::Result<Self> {
+        let mut reader = data;
+        let header = BackupHeader::deserialize_compressed(&mut reader)?;
+        ensure!(header.magic == BACKUP_MAGIC, "Not an account backup");
+        ensure!(
+            header.version == BACKUP_VERSION,
+            "Unsupported backup version {}",
+            header.version
+        );
+        ensure!(
+            header.level == LEVEL as u32 && header.asset_size == ASSET_SIZE as u32,
+            "Backup is for a different circuit (level {}, {} assets)",
+            header.level,
+            header.asset_size
+        );
+
+        let aad = &data[..data.len() - reader.len()];
+        let plaintext = header
+            .derive_cipher(password)?
+            .decrypt(
+                XNonce::from_slice(&header.nonce),
+                Payload { msg: reader, aad },
+            )
+            .map_err(|_| anyhow!("Invalid password or corrupted backup"))?;
+
+        Ok(Self::deserialize_compressed(&plaintext[..])?)
+    }
+}
+
+#[wasm_bindgen]
+impl Account {
+    #[wasm_bindgen(js_name = exportEncrypted)]
+    pub fn wasm_export_encrypted(&self, password: String) -> Result<Vec<u8>, JsError> {
+        self.export_encrypted(&password)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = importEncrypted)]
+    pub fn wasm_import_encrypted(data: &[u8], password: String) -> Result<Account, JsError> {
+        Self::import_encrypted(data, &password).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
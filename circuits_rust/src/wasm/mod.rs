@@ -1,67 +1,118 @@
+use anyhow::{anyhow, ensure, Context};
 use ark_bn254::{Bn254, Fr};
-use ark_crypto_primitives::snark::SNARK;
+use ark_ed_on_bn254::EdwardsAffine;
 use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
-use ark_groth16::{Groth16, ProvingKey};
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_relations::r1cs::ConstraintSynthesizer;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use rand::thread_rng;
 use serde_json::json;
 use serde_wasm_bindgen::to_value;
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
 
 use crate::{
+    circuit::{MULTISIG_NOTE, SINGLE_NOTE},
+    coin_diff::CoinDiff,
+    escrow::Escrow,
+    gadgets::{checked_add_diff, from_i128},
+    hash::Domain,
+    memo::{NoteMemo, RecoveredNote},
     merkle_tree::SparseMerkleTree,
-    poseidon::{poseidon_bn254, PoseidonHash},
-    Circuit, ASSET_SIZE, LEVEL,
+    multisig::{intent_hash, MultisigPolicy, Signature},
+    poseidon::{poseidon_bn254, PoseidonHash, DIFF_HASH_DOMAIN},
+    prover,
+    registry::AssetRegistry,
+    sync::SyncCursor,
+    utils::{address_from_hex, fr_from_hex, fr_to_hex},
+    Circuit, Multisig, ASSET_SIZE, LEVEL, SIGNERS,
 };
 
+mod backup;
+
+pub use backup::KdfParams;
+
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
 }
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
-
-macro_rules! console_log {
-    // Note that this is using the `log` function imported above during
-    // `bare_bones`
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
-}
-
 #[wasm_bindgen]
 #[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Account {
     balance: [u64; ASSET_SIZE],
-    nonce: Fr,
+    /// None for watch-only accounts.
+    spending_key: Option<Fr>,
+    viewing_key: Fr,
     address_fr: Fr,
-    latest_seq_sync: u64,
+    /// `SINGLE_NOTE` or `MULTISIG_NOTE`, bound into every leaf.
+    note_type: u64,
+    /// Number of leaves of the local tree when the account was last synced.
+    synced_leafs: u64,
     index: Option<usize>,
+    counter: u64,
+    history_root: Fr,
 }
 
 #[wasm_bindgen]
 impl Account {
     #[wasm_bindgen(js_name = new)]
-    pub fn wasm_new(address_hex: String, nonce_bytes: String) -> Self {
-        Self {
-            balance: [0; ASSET_SIZE],
-            nonce: Fr::from_be_bytes_mod_order(
-                &hex::decode(&nonce_bytes).expect("Invalid nonce hex string"),
-            ),
-            address_fr: Fr::from_be_bytes_mod_order(
-                &hex::decode(&address_hex).expect("Invalid address hex string"),
-            ),
-            latest_seq_sync: 0,
-            index: None,
-        }
+    pub fn wasm_new(address_hex: String, spending_key_hex: String) -> Result<Account, JsError> {
+        let new = || {
+            Ok(Self::new(
+                address_from_hex(&address_hex).context("Invalid address hex string")?,
+                parse_spending_key(&spending_key_hex)?,
+            ))
+        };
+        new().map_err(|e: anyhow::Error| JsError::new(&e.to_string()))
+    }
+
+    /// Multisig account of `policy`, its address is the policy's owner
+    /// commitment instead of an encoded Sui address.
+    #[wasm_bindgen(js_name = multisig)]
+    pub fn wasm_multisig(
+        policy: &MultisigPolicy,
+        spending_key_hex: String,
+    ) -> Result<Account, JsError> {
+        parse_spending_key(&spending_key_hex)
+            .map(|spending_key| Self::multisig(policy, spending_key))
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = watchOnly)]
+    pub fn wasm_watch_only(
+        address_hex: String,
+        viewing_key_hex: String,
+    ) -> Result<Account, JsError> {
+        let watch_only = || {
+            Ok(Self::watch_only(
+                address_from_hex(&address_hex).context("Invalid address hex string")?,
+                fr_from_hex(&viewing_key_hex).context("Invalid viewing key hex string")?,
+                SINGLE_NOTE,
+            ))
+        };
+        watch_only().map_err(|e: anyhow::Error| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = getViewingKey)]
+    pub fn wasm_get_viewing_key(&self) -> String {
+        fr_to_hex(&self.viewing_key)
+    }
+
+    #[wasm_bindgen(js_name = isWatchOnly)]
+    pub fn wasm_is_watch_only(&self) -> bool {
+        self.is_watch_only()
+    }
+
+    #[wasm_bindgen(js_name = toWatchOnly)]
+    pub fn wasm_to_watch_only(&self) -> Self {
+        self.to_watch_only()
     }
 
     #[wasm_bindgen(js_name = getBalance)]
-    pub fn wasm_get_balance(&self, asset_id: u64) -> u64 {
-        self.balance[asset_id as usize]
+    pub fn wasm_get_balance(&self, asset_id: u64) -> Result<u64, JsError> {
+        self.balance
+            .get(asset_id as usize)
+            .copied()
+            .ok_or_else(|| JsError::new(&format!("Invalid asset id {asset_id}")))
     }
 
     #[wasm_bindgen(js_name = getBalances)]
@@ -70,8 +121,34 @@ impl Account {
     }
 
     #[wasm_bindgen(js_name = setBalance)]
-    pub fn wasm_set_balance(&mut self, asset_id: u64, balance: u64) {
-        self.balance[asset_id as usize] = balance;
+    pub fn wasm_set_balance(&mut self, asset_id: u64, balance: u64) -> Result<(), JsError> {
+        let slot = self
+            .balance
+            .get_mut(asset_id as usize)
+            .ok_or_else(|| JsError::new(&format!("Invalid asset id {asset_id}")))?;
+        *slot = balance;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = getBalanceOf)]
+    pub fn wasm_get_balance_of(
+        &self,
+        registry: &AssetRegistry,
+        coin_type: String,
+    ) -> Result<u64, JsError> {
+        self.balance_of(registry, &coin_type)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = setBalanceOf)]
+    pub fn wasm_set_balance_of(
+        &mut self,
+        registry: &AssetRegistry,
+        coin_type: String,
+        balance: u64,
+    ) -> Result<(), JsError> {
+        self.set_balance_of(registry, &coin_type, balance)
+            .map_err(|e| JsError::new(&e.to_string()))
     }
 
     #[wasm_bindgen(js_name = getIndex)]
@@ -84,6 +161,24 @@ impl Account {
         self.index = Some(index);
     }
 
+    #[wasm_bindgen(js_name = getCounter)]
+    pub fn wasm_get_counter(&self) -> u64 {
+        self.counter
+    }
+
+    #[wasm_bindgen(js_name = getHistoryRoot)]
+    pub fn wasm_get_history_root(&self) -> String {
+        fr_to_hex(&self.history_root)
+    }
+
+    #[wasm_bindgen(js_name = setHistory)]
+    pub fn wasm_set_history(&mut self, counter: u64, history_root: String) -> Result<(), JsError> {
+        let history_root = fr_from_hex(&history_root)
+            .map_err(|_| JsError::new("Invalid history root hex string"))?;
+        self.set_history(counter, history_root);
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = export)]
     pub fn wasm_export(&self) -> Vec<u8> {
         let mut data = vec![];
@@ -93,8 +188,175 @@ impl Account {
     }
 
     #[wasm_bindgen(js_name = import)]
-    pub fn wasm_import(data: &[u8]) -> Self {
-        Self::deserialize_compressed(data).expect("Failed to deserialize")
+    pub fn wasm_import(data: &[u8]) -> Result<Account, JsError> {
+        Self::deserialize_compressed(data).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+impl Account {
+    /// Creates an empty account that has no leaf in the tree yet.
+    pub fn new(address_fr: Fr, spending_key: Fr) -> Self {
+        Self::with_note_type(address_fr, spending_key, SINGLE_NOTE)
+    }
+
+    /// Creates an empty multisig account, owned by the signers of `policy`.
+    pub fn multisig(policy: &MultisigPolicy, spending_key: Fr) -> Self {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        Self::with_note_type(policy.owner(&hasher), spending_key, MULTISIG_NOTE)
+    }
+
+    fn with_note_type(address_fr: Fr, spending_key: Fr, note_type: u64) -> Self {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        Self {
+            spending_key: Some(spending_key),
+            ..Self::watch_only(address_fr, viewing_key(&spending_key, &hasher), note_type)
+        }
+    }
+
+    /// Creates an account that can recompute leaves and nullifiers and decrypt
+    /// memos, but cannot prove.
+    pub fn watch_only(address_fr: Fr, viewing_key: Fr, note_type: u64) -> Self {
+        Self {
+            balance: [0; ASSET_SIZE],
+            spending_key: None,
+            viewing_key,
+            address_fr,
+            note_type,
+            synced_leafs: 0,
+            index: None,
+            counter: 0,
+            history_root: Fr::ZERO,
+        }
+    }
+
+    pub fn address_fr(&self) -> Fr {
+        self.address_fr
+    }
+
+    pub fn viewing_key(&self) -> Fr {
+        self.viewing_key
+    }
+
+    pub fn note_type(&self) -> u64 {
+        self.note_type
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.spending_key.is_none()
+    }
+
+    /// Same account with the spending key dropped, safe to share.
+    pub fn to_watch_only(&self) -> Self {
+        Self {
+            spending_key: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn balance(&self) -> [u64; ASSET_SIZE] {
+        self.balance
+    }
+
+    pub fn set_balance(&mut self, balance: [u64; ASSET_SIZE]) {
+        self.balance = balance;
+    }
+
+    /// Balance of `coin_type` in the pool of `registry`.
+    pub fn balance_of(&self, registry: &AssetRegistry, coin_type: &str) -> anyhow::Result<u64> {
+        Ok(self.balance[registry.slot(coin_type)?])
+    }
+
+    pub fn set_balance_of(
+        &mut self,
+        registry: &AssetRegistry,
+        coin_type: &str,
+        balance: u64,
+    ) -> anyhow::Result<()> {
+        self.balance[registry.slot(coin_type)?] = balance;
+        Ok(())
+    }
+
+    /// Number of transitions applied to the current note.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Running commitment to every diff applied to the current note.
+    pub fn history_root(&self) -> Fr {
+        self.history_root
+    }
+
+    pub fn set_history(&mut self, counter: u64, history_root: Fr) {
+        self.counter = counter;
+        self.history_root = history_root;
+    }
+
+    /// P = H_leaf(H_leaf(address, viewing_key), note_type)
+    pub fn prehash(&self, hasher: &PoseidonHash) -> Fr {
+        hasher.hash_in(
+            Domain::Leaf,
+            &hasher.hash_in(Domain::Leaf, &self.address_fr, &self.viewing_key),
+            &Fr::from(self.note_type),
+        )
+    }
+
+    /// Leaf committing to the current balances and history.
+    pub fn leaf(&self, hasher: &PoseidonHash) -> Fr {
+        self.leaf_of(&self.balance, self.counter, &self.history_root, hasher)
+    }
+
+    /// L = H_leaf(H_leaf(H_leaf(H_leaf(P, balance[0]), balance[1]), ..., counter), history_root)
+    pub fn leaf_of(
+        &self,
+        balance: &[u64; ASSET_SIZE],
+        counter: u64,
+        history_root: &Fr,
+        hasher: &PoseidonHash,
+    ) -> Fr {
+        let balance_hash = balance.iter().fold(self.prehash(hasher), |acc, b| {
+            hasher.hash_in(Domain::Leaf, &acc, &Fr::from(*b))
+        });
+        hasher.hash_in(
+            Domain::Leaf,
+            &hasher.hash_in(Domain::Leaf, &balance_hash, &Fr::from(counter)),
+            history_root,
+        )
+    }
+
+    /// History root after applying a diff to the current note.
+    pub fn next_history_root(&self, diff_hash: &Fr, hasher: &PoseidonHash) -> Fr {
+        history_step(&self.history_root, diff_hash, self.counter + 1, hasher)
+    }
+
+    /// Nullifier of the current leaf. Before the first note it is the genesis
+    /// nullifier of the empty leaf, which the first note spends.
+    pub fn nullifier(&self, hasher: &PoseidonHash) -> Fr {
+        self.nullifier_of(&self.leaf(hasher), hasher)
+    }
+
+    /// N = H_nullifier(leaf, viewing_key)
+    pub fn nullifier_of(&self, leaf: &Fr, hasher: &PoseidonHash) -> Fr {
+        hasher.hash_in(Domain::Nullifier, leaf, &self.viewing_key)
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn synced_leafs(&self) -> u64 {
+        self.synced_leafs
+    }
+
+    pub(crate) fn set_synced_leafs(&mut self, synced_leafs: u64) {
+        self.synced_leafs = synced_leafs;
+    }
+
+    /// Replaces the current note with one recovered from its memo.
+    pub fn restore(&mut self, note: &RecoveredNote) {
+        self.balance = note.memo.balance;
+        self.index = Some(note.index as usize);
+        self.counter = note.memo.counter;
+        self.history_root = note.memo.history_root;
     }
 }
 
@@ -103,6 +365,33 @@ impl Account {
 pub struct State {
     account: Account,
     merkle_leafs: Vec<Fr>,
+    cursor: Option<SyncCursor>,
+}
+
+impl State {
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    pub fn leafs(&self) -> &[Fr] {
+        &self.merkle_leafs
+    }
+
+    pub fn cursor(&self) -> Option<&SyncCursor> {
+        self.cursor.as_ref()
+    }
+
+    pub(crate) fn account_mut(&mut self) -> &mut Account {
+        &mut self.account
+    }
+
+    pub(crate) fn push_leaf(&mut self, leaf: Fr) {
+        self.merkle_leafs.push(leaf);
+    }
+
+    pub(crate) fn set_cursor(&mut self, cursor: SyncCursor) {
+        self.cursor = Some(cursor);
+    }
 }
 
 #[wasm_bindgen]
@@ -112,26 +401,32 @@ impl State {
         Self {
             account,
             merkle_leafs: vec![],
+            cursor: None,
         }
     }
 
+    #[wasm_bindgen(js_name = getAccount)]
+    pub fn wasm_get_account(&self) -> Account {
+        self.account.clone()
+    }
+
+    /// Replaces the account, keeping the synced tree and cursor.
+    #[wasm_bindgen(js_name = setAccount)]
+    pub fn wasm_set_account(&mut self, account: Account) {
+        self.account = account;
+    }
+
     #[wasm_bindgen(js_name = setLeafs)]
-    pub fn wasm_set_leafs(&mut self, leafs: Vec<String>) {
-        self.merkle_leafs = leafs
-            .iter()
-            .map(|s| hex::decode(s).expect("Invalid hex string"))
-            .map(|b| Fr::from_be_bytes_mod_order(&b))
-            .collect();
+    pub fn wasm_set_leafs(&mut self, leafs: Vec<String>) -> Result<(), JsError> {
+        self.merkle_leafs = parse_leafs(&leafs).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = addLeafs)]
-    pub fn wasm_add_leafs(&mut self, leafs: Vec<String>) {
-        self.merkle_leafs.extend(
-            leafs
-                .iter()
-                .map(|s| hex::decode(s).expect("Invalid hex string"))
-                .map(|b| Fr::from_be_bytes_mod_order(&b)),
-        );
+    pub fn wasm_add_leafs(&mut self, leafs: Vec<String>) -> Result<(), JsError> {
+        self.merkle_leafs
+            .extend(parse_leafs(&leafs).map_err(|e| JsError::new(&e.to_string()))?);
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = getLeafs)]
@@ -146,153 +441,379 @@ impl State {
     pub fn wasm_get_leafs_length(&self) -> usize {
         self.merkle_leafs.len()
     }
+
+    #[wasm_bindgen(js_name = getCursor)]
+    pub fn wasm_get_cursor(&self) -> JsValue {
+        to_value(&self.cursor).expect("Failed to serialize cursor")
+    }
+
+    #[wasm_bindgen(js_name = export)]
+    pub fn wasm_export(&self) -> Vec<u8> {
+        let mut data = vec![];
+        self.serialize_compressed(&mut data)
+            .expect("Failed to serialize");
+        data
+    }
+
+    #[wasm_bindgen(js_name = import)]
+    pub fn wasm_import(data: &[u8]) -> Result<State, JsError> {
+        Self::deserialize_compressed(data).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+/// viewing_key = H_view(spending_key, 0)
+pub fn viewing_key(spending_key: &Fr, hasher: &PoseidonHash) -> Fr {
+    hasher.hash_in(Domain::ViewingKey, spending_key, &Fr::ZERO)
+}
+
+/// diff_hash = H(H("diff", diff[0]), diff[1]), ....
+pub fn diff_hash(diff: &[Fr; ASSET_SIZE], hasher: &PoseidonHash) -> Fr {
+    diff.iter()
+        .fold(Fr::from(DIFF_HASH_DOMAIN), |acc, d| hasher.hash(&acc, d))
+}
+
+/// history = H_history(H_history(prev_history, diff_hash), counter)
+pub fn history_step(prev: &Fr, diff_hash: &Fr, counter: u64, hasher: &PoseidonHash) -> Fr {
+    hasher.hash_in(
+        Domain::History,
+        &hasher.hash_in(Domain::History, prev, diff_hash),
+        &Fr::from(counter),
+    )
 }
 
 #[wasm_bindgen]
 pub fn prove(
     state: State,
     pk_bytes: Vec<u8>,
-    diffs: Vec<i64>,
+    diffs: Vec<String>,
     is_public: bool,
     aux: Option<String>,
-) -> JsValue {
-    // deserialize uncompressed pk
-    let pk = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(&pk_bytes[..])
-        .expect("Failed to deserialize pk");
-    let aux_fr = aux
-        .map(|a| Fr::from_be_bytes_mod_order(&hex::decode(&a).expect("Invalid aux hex string")))
-        .unwrap_or(Fr::ZERO);
-    let hasher = PoseidonHash::new(poseidon_bn254());
-    let before = state.account.balance.map(|b| Fr::from(b));
-    let diff: [Fr; ASSET_SIZE] = diffs
-        .iter()
-        .map(|b| Fr::from(*b))
-        .collect::<Vec<_>>()
-        .try_into()
-        .expect("Invalid diff sizes");
-    let after: [Fr; ASSET_SIZE] = before
-        .iter()
-        .zip(diff.iter())
-        .map(|(b, d)| b + d)
-        .collect::<Vec<_>>()
-        .try_into()
-        .expect("Invalid after sizes");
-
-    let merkle_tree =
-        SparseMerkleTree::<LEVEL>::new_sequential(&state.merkle_leafs, &hasher, &Fr::ZERO)
-            .expect("Invalid merkle tree construction");
-
-    let merkle_root = merkle_tree.root();
-    let merkle_path = state
-        .account
-        .index
-        .map(|i| merkle_tree.generate_membership_proof(i as u64))
-        .unwrap_or(merkle_tree.generate_membership_proof(0));
-
-    let diff_hash = diff.iter().fold(Fr::ZERO, |acc, d| hasher.hash(&acc, &d));
-
-    let prehash = hasher.hash(&state.account.address_fr, &state.account.nonce);
-    let before_leaf = before.iter().fold(prehash, |acc, b| hasher.hash(&acc, &b));
-    let after_leaf = after.iter().fold(prehash, |acc, b| hasher.hash(&acc, &b));
-
-    let nullifier = match state.account.index {
-        Some(_) => hasher.hash(&before_leaf, &state.account.nonce),
-        None => Fr::ZERO,
-    };
-    let after_nullifier = hasher.hash(&after_leaf, &state.account.nonce);
-
-    console_log!("Address: {}", state.account.address_fr);
-    console_log!("Nonce: {}", state.account.nonce);
-    console_log!(
-        "Public address: {}",
-        if is_public {
-            state.account.address_fr
-        } else {
-            Fr::ZERO
-        }
-    );
-    console_log!("Before: {:?}", before);
-    console_log!("Diff: {:?}", diff);
-    console_log!("After: {:?}", after);
-    console_log!("Nullifier: {}", nullifier);
-    console_log!("After nullifier: {}", after_nullifier);
-    console_log!("Diff hash: {}", diff_hash);
-    console_log!("Merkle root: {}", merkle_root);
-    console_log!("Merkle path: {:?}", merkle_path);
-    console_log!("Before leaf: {}", before_leaf);
-    console_log!("After leaf: {}", after_leaf);
-    console_log!("Aux: {}", aux_fr);
-
-    let circuit = Circuit {
-        nonce: state.account.nonce,
-        address: state.account.address_fr,
-        public_address: if is_public {
-            state.account.address_fr
-        } else {
-            Fr::ZERO
-        },
-        before,
-        diff,
-        after,
-        merkle_root,
-        merkle_path,
-        diff_hash,
-        nullifier,
-        after_leaf,
-        hasher,
-        aux: aux_fr,
-    };
-
-    let cs = ConstraintSystem::<Fr>::new_ref();
-    circuit
-        .clone()
-        .generate_constraints(cs.clone())
-        .expect("Failed to generate constraints");
-    if !cs.is_satisfied().expect("Failed to check constraints") {
-        panic!("Constraints are not satisfied");
-    }
-
-    let proof =
-        Groth16::<Bn254>::prove(&pk, circuit, &mut thread_rng()).expect("Proof generation failed");
-
-    let mut proof_bytes = vec![];
-    proof
-        .serialize_compressed(&mut proof_bytes)
-        .expect("Failed to serialize proof");
-
-    let mut public_inputs_serialized = Vec::new();
-    merkle_root
-        .serialize_compressed(&mut public_inputs_serialized)
-        .expect("Failed to serialize merkle root");
-    diff_hash
-        .serialize_compressed(&mut public_inputs_serialized)
-        .expect("Failed to serialize diff hash");
-    nullifier
-        .serialize_compressed(&mut public_inputs_serialized)
-        .expect("Failed to serialize nullifier");
-    after_leaf
-        .serialize_compressed(&mut public_inputs_serialized)
-        .expect("Failed to serialize after leaf");
-    (if is_public {
+    auditor: Option<String>,
+) -> Result<JsValue, JsError> {
+    let public_address = if is_public {
         state.account.address_fr
     } else {
         Fr::ZERO
+    };
+    let prove = || {
+        let update = NoteUpdate::new(
+            &state,
+            parse_diffs(&diffs)?,
+            public_address,
+            parse_aux(aux)?,
+            parse_auditor(auditor)?,
+        )?;
+        update.prove_js(&pk_bytes, update.circuit.clone())
+    };
+    prove().map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Proves a deposit and withdrawal of several coin types at once, `coin_diff`
+/// being the diff `core::finish_public_operation` computes from the
+/// `deposit_coin` and `withdraw_coin` calls of the operation.
+#[wasm_bindgen(js_name = provePublicOperation)]
+pub fn prove_public_operation(
+    state: State,
+    pk_bytes: Vec<u8>,
+    coin_diff: &CoinDiff,
+    auditor: Option<String>,
+) -> Result<JsValue, JsError> {
+    let prove = || {
+        let update = NoteUpdate::public_operation(&state, coin_diff, parse_auditor(auditor)?)?;
+        update.prove_js(&pk_bytes, update.circuit.clone())
+    };
+    prove().map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Intent the signers of a multisig note sign for this update.
+#[wasm_bindgen(js_name = multisigIntent)]
+pub fn multisig_intent(
+    state: &State,
+    diffs: Vec<String>,
+    public_address: Option<String>,
+    aux: Option<String>,
+) -> Result<String, JsError> {
+    let intent = || {
+        let update = NoteUpdate::new(
+            state,
+            parse_diffs(&diffs)?,
+            parse_public_address(public_address)?,
+            parse_aux(aux)?,
+            None,
+        )?;
+        anyhow::Ok(fr_to_hex(&update.intent()))
+    };
+    intent().map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Proves an update of a multisig note, `signatures` holds one hex signature
+/// per signer of `policy`, empty for signers that did not sign.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = proveMultisig)]
+pub fn prove_multisig(
+    state: State,
+    pk_bytes: Vec<u8>,
+    diffs: Vec<String>,
+    public_address: Option<String>,
+    aux: Option<String>,
+    auditor: Option<String>,
+    policy: &MultisigPolicy,
+    signatures: Vec<String>,
+) -> Result<JsValue, JsError> {
+    let prove = || {
+        let update = NoteUpdate::new(
+            &state,
+            parse_diffs(&diffs)?,
+            parse_public_address(public_address)?,
+            parse_aux(aux)?,
+            parse_auditor(auditor)?,
+        )?;
+        let signatures: [Option<Signature>; SIGNERS] = signatures
+            .iter()
+            .map(|s| parse_signature(s))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .try_into()
+            .map_err(|s: Vec<_>| anyhow!("{} signatures for {SIGNERS} signers", s.len()))?;
+        let circuit = Multisig::new(update.circuit.clone(), policy, signatures)?;
+        update.prove_js(&pk_bytes, circuit)
+    };
+    prove().map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn parse_public_address(public_address: Option<String>) -> anyhow::Result<Fr> {
+    public_address
+        .map(|a| address_from_hex(&a).context("Invalid address hex string"))
+        .unwrap_or(Ok(Fr::ZERO))
+}
+
+fn parse_spending_key(spending_key: &str) -> anyhow::Result<Fr> {
+    let bytes = hex::decode(spending_key).context("Invalid spending key hex string")?;
+    Ok(Fr::from_be_bytes_mod_order(&bytes))
+}
+
+/// Leaves as big-endian hex strings, all or none.
+fn parse_leafs(leafs: &[String]) -> anyhow::Result<Vec<Fr>> {
+    leafs
+        .iter()
+        .map(|s| {
+            let bytes = hex::decode(s).with_context(|| format!("Invalid leaf hex string {s}"))?;
+            Ok(Fr::from_be_bytes_mod_order(&bytes))
+        })
+        .collect()
+}
+
+fn parse_aux(aux: Option<String>) -> anyhow::Result<Fr> {
+    aux.map(|a| {
+        let bytes = hex::decode(&a).context("Invalid aux hex string")?;
+        Ok(Fr::from_be_bytes_mod_order(&bytes))
     })
-    .serialize_compressed(&mut public_inputs_serialized)
-    .expect("Failed to serialize public address");
-    aux_fr
-        .serialize_compressed(&mut public_inputs_serialized)
-        .expect("Failed to serialize aux");
-
-    to_value(&json!({
-        "proof": hex::encode(proof_bytes),
-        "address": hex::encode(state.account.address_fr.into_bigint().to_bytes_be()),
-        "nullifier": hex::encode(nullifier.into_bigint().to_bytes_be()),
-        "after_leaf": hex::encode(after_leaf.into_bigint().to_bytes_be()),
-        "after_nullifier": hex::encode(after_nullifier.into_bigint().to_bytes_be()),
-        "diff_hash": hex::encode(diff_hash.into_bigint().to_bytes_be()),
-        "merkle_root": hex::encode(merkle_root.into_bigint().to_bytes_be()),
-        "public_inputs": hex::encode(public_inputs_serialized),
-    }))
-    .expect("Failed to serialize proof")
+    .unwrap_or(Ok(Fr::ZERO))
+}
+
+pub(crate) fn parse_auditor(auditor: Option<String>) -> anyhow::Result<Option<EdwardsAffine>> {
+    auditor
+        .map(|pk| {
+            let bytes = hex::decode(&pk).context("Invalid auditor hex string")?;
+            EdwardsAffine::deserialize_compressed(&bytes[..]).context("Invalid auditor public key")
+        })
+        .transpose()
+}
+
+/// Signature of one signer, empty for signers that did not sign.
+fn parse_signature(signature: &str) -> anyhow::Result<Option<Signature>> {
+    if signature.is_empty() {
+        return Ok(None);
+    }
+    let bytes = hex::decode(signature).context("Invalid signature hex string")?;
+    let signature = Signature::deserialize_compressed(&bytes[..]).context("Invalid signature")?;
+    Ok(Some(signature))
+}
+
+/// Diffs as the wasm API takes them, one decimal string per asset so that
+/// the full -(2^64 - 1)..2^64 range survives JavaScript.
+pub fn parse_diffs(diffs: &[String]) -> anyhow::Result<[i128; ASSET_SIZE]> {
+    ensure!(
+        diffs.len() == ASSET_SIZE,
+        "{} diffs for {ASSET_SIZE} assets",
+        diffs.len()
+    );
+    let mut parsed = [0; ASSET_SIZE];
+    for (p, d) in parsed.iter_mut().zip(diffs) {
+        *p = d
+            .trim()
+            .parse()
+            .ok()
+            .filter(|d: &i128| from_i128::<Fr>(*d).is_some())
+            .ok_or_else(|| anyhow!("Invalid diff {d}"))?;
+    }
+    Ok(parsed)
+}
+
+/// Witness of a note update of `State`'s account and the values returned
+/// next to its proof.
+pub struct NoteUpdate {
+    pub circuit: Circuit,
+    /// Memo of the after note, encrypted to the account.
+    pub memo: Vec<u8>,
+    pub after_counter: u64,
+    pub after_history: Fr,
+    pub after_nullifier: Fr,
+    /// Escrow ciphertext bytes, empty without an auditor.
+    pub escrow: Vec<u8>,
+}
+
+impl NoteUpdate {
+    /// Applies `diff` to the account's note, binding the update to
+    /// `public_address` and `aux`, and escrows it to `auditor` if given.
+    pub fn new(
+        state: &State,
+        diff: [i128; ASSET_SIZE],
+        public_address: Fr,
+        aux_fr: Fr,
+        auditor: Option<EdwardsAffine>,
+    ) -> anyhow::Result<Self> {
+        let spending_key = state
+            .account
+            .spending_key
+            .ok_or_else(|| anyhow!("Watch-only account cannot prove"))?;
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        let before = state.account.balance.map(Fr::from);
+        let mut after_balance = [0; ASSET_SIZE];
+        for (i, a) in after_balance.iter_mut().enumerate() {
+            *a = checked_add_diff(state.account.balance[i], diff[i])
+                .ok_or_else(|| anyhow!("Balance {i} out of range after the update"))?;
+        }
+        // before and after are amounts, so the diff is in range
+        let diff = diff.map(Fr::from);
+        let after = after_balance.map(Fr::from);
+
+        let diff_hash = diff_hash(&diff, &hasher);
+        let after_counter = state.account.counter + 1;
+        let after_history = state.account.next_history_root(&diff_hash, &hasher);
+
+        let memo = NoteMemo {
+            balance: after_balance,
+            index_hint: state.merkle_leafs.len() as u64,
+            counter: after_counter,
+            history_root: after_history,
+        };
+        let memo_bytes = state.account.encrypt_memo(&memo, &hasher)?;
+
+        // escrow address || diff to the auditor, if the deployment has one
+        let escrow = auditor.map(|auditor| {
+            let message = [&[state.account.address_fr][..], &diff[..]].concat();
+            Escrow::new(auditor, &message, &mut thread_rng(), &hasher)
+        });
+        let escrow_bytes = escrow
+            .as_ref()
+            .map(|e| e.ciphertext.to_bytes())
+            .unwrap_or_default();
+
+        let merkle_tree =
+            SparseMerkleTree::<LEVEL>::new_sequential(&state.merkle_leafs, &hasher, &Fr::ZERO)?;
+
+        let merkle_root = merkle_tree.root();
+        let merkle_path = state
+            .account
+            .index
+            .map(|i| merkle_tree.generate_membership_proof(i as u64))
+            .unwrap_or(merkle_tree.generate_membership_proof(0));
+
+        let after_leaf =
+            state
+                .account
+                .leaf_of(&after_balance, after_counter, &after_history, &hasher);
+
+        let nullifier = state.account.nullifier(&hasher);
+        let after_nullifier = state.account.nullifier_of(&after_leaf, &hasher);
+
+        let circuit = Circuit {
+            spending_key,
+            address: state.account.address_fr,
+            public_address,
+            before,
+            diff,
+            after,
+            history: state.account.history_root,
+            counter: Fr::from(state.account.counter),
+            merkle_root,
+            merkle_path,
+            diff_hash,
+            nullifier,
+            after_leaf,
+            hasher,
+            aux: aux_fr,
+            escrow,
+        };
+
+        Ok(Self {
+            circuit,
+            memo: memo_bytes,
+            after_counter,
+            after_history,
+            after_nullifier,
+            escrow: escrow_bytes,
+        })
+    }
+
+    /// Public operation of the account's owner, the deposits and withdrawals
+    /// of `coin_diff` applied in one update.
+    pub fn public_operation(
+        state: &State,
+        coin_diff: &CoinDiff,
+        auditor: Option<EdwardsAffine>,
+    ) -> anyhow::Result<Self> {
+        Self::new(
+            state,
+            coin_diff.diffs()?,
+            state.account.address_fr,
+            Fr::ZERO,
+            auditor,
+        )
+    }
+
+    fn intent(&self) -> Fr {
+        let c = &self.circuit;
+        intent_hash(
+            &c.nullifier,
+            &c.diff_hash,
+            &c.public_address,
+            &c.aux,
+            &c.hasher,
+        )
+    }
+
+    /// Checks and proves `circuit`, which wraps this update, and serializes
+    /// the proof with its public inputs.
+    fn prove_js<C: ConstraintSynthesizer<Fr> + Clone>(
+        &self,
+        pk_bytes: &[u8],
+        circuit: C,
+    ) -> anyhow::Result<JsValue> {
+        let proof_bytes = prover::prove::<Bn254, _>(pk_bytes, circuit, &mut thread_rng())?;
+        to_value(&self.proof_json(&proof_bytes))
+            .map_err(|e| anyhow!("Failed to serialize proof: {e}"))
+    }
+
+    /// The proof and public inputs of this update, with the values the
+    /// account stores once it lands.
+    pub(crate) fn proof_json(&self, proof_bytes: &[u8]) -> serde_json::Value {
+        let c = &self.circuit;
+        let public_inputs_serialized = c.public_input_bytes();
+
+        json!({
+            "proof": hex::encode(proof_bytes),
+            "address": hex::encode(c.address.into_bigint().to_bytes_be()),
+            "nullifier": hex::encode(c.nullifier.into_bigint().to_bytes_be()),
+            "after_leaf": hex::encode(c.after_leaf.into_bigint().to_bytes_be()),
+            "after_nullifier": hex::encode(self.after_nullifier.into_bigint().to_bytes_be()),
+            "diff_hash": hex::encode(c.diff_hash.into_bigint().to_bytes_be()),
+            "merkle_root": hex::encode(c.merkle_root.into_bigint().to_bytes_be()),
+            "public_inputs": hex::encode(public_inputs_serialized),
+            "memo": hex::encode(&self.memo),
+            "counter": self.after_counter,
+            "history_root": fr_to_hex(&self.after_history),
+            "escrow": hex::encode(&self.escrow),
+        })
+    }
 }
@@ -0,0 +1,90 @@
+//! Encoding of Sui addresses as field elements, which must agree with
+//! `fr::from_address_bytes` of the contracts.
+
+use ark_bn254::Fr;
+use ark_ff::{Field, PrimeField};
+use circuits_rust::{
+    multisig::{MultisigPolicy, SignerKey},
+    poseidon::{circom::circom_hash, poseidon_bn254, PoseidonHash},
+    utils::{address_from_hex, address_to_fr, fr_to_hex, ADDRESS_DOMAIN},
+    wasm::Account,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn address(hex: &str) -> [u8; 32] {
+    hex::decode(format!("{:0>64}", hex.trim_start_matches("0x")))
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+/// Addresses and their encodings asserted by `fr_test.move`.
+const VECTORS: [(&str, &str); 4] = [
+    (
+        "0x0",
+        "7809808677678182919361798478645198704514003897184072159114288900413020862725",
+    ),
+    (
+        "0xb0b",
+        "17650599565037227231417921435348288663855929599621633977351081005832815255886",
+    ),
+    (
+        "0xcafe",
+        "3608769810656646089752900619526556407236928262508526470644518878056894752930",
+    ),
+    (
+        "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "4249344890785685112034644153849129056088785602574882636279911741559812077646",
+    ),
+];
+
+#[test]
+fn agrees_with_from_address_bytes() {
+    for (hex, expected) in VECTORS {
+        let bytes = address(hex);
+        assert_eq!(address_to_fr(&bytes).to_string(), expected, "{hex}");
+
+        // the u256 arithmetic of `from_address_bytes`, hashed with Sui's
+        // circomlib `poseidon_bn254`
+        let value = Fr::from_be_bytes_mod_order(&bytes);
+        let hi = Fr::from_be_bytes_mod_order(&bytes[..16]);
+        let lo = value - hi * Fr::from(2u64).pow([128]);
+        let inputs = [Fr::from(ADDRESS_DOMAIN), hi, lo];
+        assert_eq!(circom_hash(&inputs).to_string(), expected, "{hex}");
+        // a two-input hash of the same limbs is another value
+        assert_ne!(circom_hash(&inputs[1..]).to_string(), expected, "{hex}");
+    }
+}
+
+#[test]
+fn parses_addresses_with_or_without_prefix() {
+    let hex = format!("{:0>64}", "b0b");
+    let expected = address_to_fr(&address("0xb0b"));
+    assert_eq!(address_from_hex(&hex).unwrap(), expected);
+    assert_eq!(address_from_hex(&format!("0x{hex}")).unwrap(), expected);
+    assert!(address_from_hex("0xb0b").is_err());
+    assert!(address_from_hex(&format!("0x{hex}00")).is_err());
+    assert!(address_from_hex(&format!("0x{}", "z".repeat(64))).is_err());
+}
+
+#[test]
+fn wasm_accounts_take_the_encoding_they_expect() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let spending_key = fr_to_hex(&Fr::from(7u64));
+    let account = Account::wasm_new(format!("0x{:0>64}", "b0b"), spending_key.clone()).unwrap();
+    assert_eq!(account.address_fr(), address_to_fr(&address("0xb0b")));
+
+    // multisig accounts take the owner commitment as is
+    let mut rng = StdRng::seed_from_u64(0);
+    let policy = MultisigPolicy::new(
+        1,
+        (0..3)
+            .map(|_| SignerKey::generate(&mut rng).public_key())
+            .collect(),
+    )
+    .unwrap();
+    let multisig = Account::wasm_multisig(&policy, spending_key).unwrap();
+    assert_eq!(multisig.address_fr(), policy.owner(&hasher));
+    assert_eq!(policy.wasm_get_owner(), fr_to_hex(&multisig.address_fr()));
+    assert_eq!(multisig.viewing_key(), account.viewing_key());
+}
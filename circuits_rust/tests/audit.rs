@@ -0,0 +1,236 @@
+//! Audit bundles checked against the events of the transactions they cite.
+
+mod common;
+
+use ark_bn254::Fr;
+use circuits_rust::{
+    audit::{AuditBundle, ChainSnapshot},
+    merkle_tree::SparseMerkleTree,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    sync::RawEvent,
+    utils::fr_to_hex,
+    wasm::{Account, NoteUpdate, State},
+    LEVEL,
+};
+use common::PACKAGE;
+use serde_json::{json, Value};
+
+/// Account transacting on a simulated chain, recording every transition.
+struct Chain {
+    hasher: PoseidonHash,
+    state: State,
+    bundle: AuditBundle,
+    events: Vec<Value>,
+}
+
+impl Chain {
+    /// Chain with a leaf of another account before the first note.
+    fn new() -> Self {
+        let account = Account::new(Fr::from(0xa11ceu64), Fr::from(42u64));
+        let mut chain = Self {
+            hasher: PoseidonHash::new(poseidon_bn254()),
+            bundle: AuditBundle::new(&account),
+            state: State::wasm_new(account),
+            events: vec![],
+        };
+        chain.insert(Fr::from(1u64), vec![], "other");
+        chain
+    }
+
+    fn event(&mut self, digest: &str, name: &str, parsed_json: Value) {
+        self.events.push(json!({
+            "id": { "txDigest": digest, "eventSeq": self.events.len().to_string() },
+            "type": format!("{PACKAGE}::core::{name}"),
+            "parsedJson": parsed_json,
+        }));
+    }
+
+    fn insert(&mut self, leaf: Fr, memo: Vec<u8>, digest: &str) -> u64 {
+        let index = self.state.leafs().len() as u64;
+        self.state.wasm_add_leafs(vec![fr_to_hex(&leaf)]).unwrap();
+        let root = SparseMerkleTree::<LEVEL>::new_sequential(
+            self.state.leafs(),
+            &self.hasher,
+            &Fr::from(0u64),
+        )
+        .unwrap()
+        .root();
+        self.event(
+            digest,
+            "LeafInserted",
+            json!({
+                "index": index.to_string(),
+                "value": leaf.to_string(),
+                "new_root": root.to_string(),
+                "memo": memo,
+            }),
+        );
+        index
+    }
+
+    /// Applies `diff` to the account's note in transaction `digest`.
+    fn transact(&mut self, diff: [i128; 5], digest: &str) {
+        let update =
+            NoteUpdate::new(&self.state, diff, Fr::from(0u64), Fr::from(0u64), None).unwrap();
+        let before = self.state.account().clone();
+        let nullifier = before.nullifier(&self.hasher);
+        self.event(
+            digest,
+            "NullifierUsed",
+            json!({ "nullifier": nullifier.to_string() }),
+        );
+        let index = self.insert(update.circuit.after_leaf, update.memo.clone(), digest);
+        self.bundle
+            .record(&before, diff, index, digest, &self.hasher)
+            .unwrap();
+
+        let mut after = before;
+        after.wasm_set_index(index as usize);
+        after.set_history(update.after_counter, update.after_history);
+        let balance = after.balance();
+        after.set_balance(std::array::from_fn(|i| {
+            (i128::from(balance[i]) + diff[i]) as u64
+        }));
+        self.state.wasm_set_account(after);
+    }
+
+    fn snapshot(&self) -> ChainSnapshot {
+        let events: Vec<RawEvent> = serde_json::from_value(json!(self.events)).unwrap();
+        ChainSnapshot::decode(PACKAGE, &events).unwrap()
+    }
+
+    fn verify(&self, bundle: &AuditBundle) -> anyhow::Result<()> {
+        bundle.verify(&self.snapshot(), &self.hasher).map(|_| ())
+    }
+
+    /// The bundle with its JSON edited by `edit`.
+    fn edited(&self, edit: impl FnOnce(&mut Value)) -> AuditBundle {
+        let mut json = serde_json::to_value(&self.bundle).unwrap();
+        edit(&mut json);
+        serde_json::from_value(json).unwrap()
+    }
+}
+
+fn chain() -> Chain {
+    let mut chain = Chain::new();
+    chain.transact([100, 0, 0, 0, 0], "deposit");
+    chain.transact([-30, 0, 0, 0, 7], "swap");
+    chain.transact([0, 0, 0, 0, -7], "withdraw");
+    chain
+}
+
+#[test]
+fn complete_history_verifies() {
+    let chain = chain();
+    let summary = chain
+        .bundle
+        .verify(&chain.snapshot(), &chain.hasher)
+        .unwrap();
+    assert_eq!(summary.transitions, 3);
+    assert_eq!(summary.balance, [70, 0, 0, 0, 0]);
+    assert_eq!(summary.leaf_index, Some(3));
+    assert_eq!(summary.history_root, chain.state.account().history_root());
+
+    let json = serde_json::to_string(&chain.bundle).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AuditBundle>(&json).unwrap(),
+        chain.bundle
+    );
+}
+
+#[test]
+fn transitions_must_cite_their_transaction() {
+    let chain = chain();
+    let err = chain
+        .verify(&chain.edited(|b| b["transitions"][1]["digest"] = json!("withdraw")))
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not inserted by this transaction"),
+        "{err}"
+    );
+
+    // a leaf of another account cannot be claimed either
+    let err = chain
+        .verify(&chain.edited(|b| b["transitions"][0]["digest"] = json!("other")))
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not inserted by this transaction"),
+        "{err}"
+    );
+}
+
+#[test]
+fn forged_transitions_are_rejected() {
+    let chain = chain();
+    // amounts that hash to another diff
+    assert!(chain
+        .verify(&chain.edited(|b| b["transitions"][1]["diff"][0] = json!(-20)))
+        .is_err());
+    // a consistent transition that was never on chain
+    let mut forged = Chain::new();
+    forged.transact([100, 0, 0, 0, 0], "deposit");
+    forged.transact([900, 0, 0, 0, 0], "deposit2");
+    let err = chain.verify(&forged.bundle).unwrap_err();
+    assert!(err.to_string().contains("Transition 1"), "{err}");
+}
+
+#[test]
+fn omitted_transitions_are_rejected() {
+    let chain = chain();
+    let without = |i: usize| {
+        chain.edited(|b| {
+            b["transitions"].as_array_mut().unwrap().remove(i);
+        })
+    };
+    for i in 0..3 {
+        assert!(chain.verify(&without(i)).is_err(), "transition {i}");
+    }
+
+    // dropping the latest transitions leaves a spent note at the end
+    let err = chain
+        .verify(&chain.edited(|b| {
+            b["transitions"].as_array_mut().unwrap().truncate(1);
+        }))
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("spent by transaction swap"),
+        "{err}"
+    );
+    let empty = AuditBundle::new(chain.state.account());
+    let err = chain.verify(&empty).unwrap_err();
+    assert!(
+        err.to_string().contains("spent by transaction deposit"),
+        "{err}"
+    );
+    assert_eq!(
+        empty
+            .verify(&Chain::new().snapshot(), &chain.hasher)
+            .unwrap()
+            .transitions,
+        0
+    );
+}
+
+#[test]
+fn chains_start_at_the_genesis_nullifier() {
+    let chain = chain();
+    let account = chain.state.account();
+    let genesis = chain.bundle.transitions()[0].nullifier;
+    assert_eq!(
+        genesis,
+        Account::new(Fr::from(0xa11ceu64), Fr::from(42u64)).nullifier(&chain.hasher)
+    );
+    // watch-only accounts derive the same nullifiers
+    assert_eq!(
+        account.to_watch_only().nullifier(&chain.hasher),
+        account.nullifier(&chain.hasher)
+    );
+
+    // a transition whose nullifier was not derived from its before leaf
+    let err = chain
+        .verify(
+            &chain.edited(|b| b["transitions"][0]["nullifier"] = json!(fr_to_hex(&Fr::from(0u64)))),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid nullifier"), "{err}");
+}
@@ -0,0 +1,87 @@
+//! Password-encrypted account backups.
+
+use ark_bn254::Fr;
+use ark_serialize::CanonicalSerialize;
+use circuits_rust::wasm::{Account, KdfParams};
+
+/// Cheapest parameters Argon2 accepts, to keep the tests fast.
+const KDF: KdfParams = KdfParams {
+    m_cost: 8,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+/// Offset of `KdfParams::m_cost` in the header, after the magic and version.
+const M_COST: usize = 5;
+/// Offset of the salt, after the KDF parameters, level and asset size.
+const SALT: usize = 25;
+
+fn account() -> Account {
+    let mut account = Account::new(Fr::from(0xb0bu64), Fr::from(7u64));
+    account.set_balance([0, 0, u64::MAX, 0, 0]);
+    account.wasm_set_index(3);
+    account
+}
+
+fn bytes(account: &Account) -> Vec<u8> {
+    let mut bytes = vec![];
+    account.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn roundtrip() {
+    let account = account();
+    let backup = account.export_encrypted_with("hunter2", KDF).unwrap();
+    let imported = Account::import_encrypted(&backup, "hunter2").unwrap();
+    assert_eq!(bytes(&imported), bytes(&account));
+
+    // salt and nonce are fresh for every export
+    assert_ne!(
+        account.export_encrypted_with("hunter2", KDF).unwrap(),
+        backup
+    );
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let backup = account().export_encrypted_with("hunter2", KDF).unwrap();
+    let err = Account::import_encrypted(&backup, "hunter3").unwrap_err();
+    assert_eq!(err.to_string(), "Invalid password or corrupted backup");
+}
+
+#[test]
+fn tampered_backups_are_rejected() {
+    let backup = account().export_encrypted_with("hunter2", KDF).unwrap();
+
+    // the header is authenticated
+    let mut salt = backup.clone();
+    salt[SALT] ^= 1;
+    assert!(Account::import_encrypted(&salt, "hunter2").is_err());
+    let mut t_cost = backup.clone();
+    t_cost[M_COST + 4] = 2;
+    assert!(Account::import_encrypted(&t_cost, "hunter2").is_err());
+
+    let mut ciphertext = backup.clone();
+    *ciphertext.last_mut().unwrap() ^= 1;
+    assert!(Account::import_encrypted(&ciphertext, "hunter2").is_err());
+    assert!(Account::import_encrypted(&backup[..backup.len() - 1], "hunter2").is_err());
+    assert!(Account::import_encrypted(&backup[..SALT], "hunter2").is_err());
+}
+
+#[test]
+fn oversized_kdf_parameters_are_rejected() {
+    let mut backup = account().export_encrypted_with("hunter2", KDF).unwrap();
+    backup[M_COST..M_COST + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = Account::import_encrypted(&backup, "hunter2").unwrap_err();
+    assert!(err.to_string().contains("exceed"), "{err}");
+
+    let kdf = KdfParams {
+        p_cost: KdfParams::MAX.p_cost + 1,
+        ..KDF
+    };
+    assert!(account().export_encrypted_with("hunter2", kdf).is_err());
+    assert!(account()
+        .export_encrypted_with("hunter2", KdfParams::default())
+        .is_ok());
+}
@@ -0,0 +1,107 @@
+//! `CoinDiff` against the diffs `core.move` builds and the diff hashes of the
+//! conformance suite.
+
+use ark_bn254::Fr;
+use circuits_rust::{
+    coin_diff::{type_name, CoinDiff},
+    conformance,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    utils::fr_to_hex,
+    wasm::diff_hash,
+    ASSET_SIZE,
+};
+
+const SUI: &str = "0x2::sui::SUI";
+const USDC: &str = "0xdba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+const WAL: &str = "0x356a26eb9e012a68958082340d4c4116e7f55615cf27affcff209cf0ae544f59::wal::WAL";
+
+/// Pool with three of its five slots allowed.
+fn pool() -> CoinDiff {
+    CoinDiff::empty(ASSET_SIZE, &[SUI, USDC, WAL]).unwrap()
+}
+
+/// Checks the diff against the one given by slot and its hash against
+/// `wasm::diff_hash`.
+fn assert_diff(coin_diff: &CoinDiff, expected: [i128; ASSET_SIZE]) {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    assert_eq!(coin_diff.diffs().unwrap(), expected);
+    assert_eq!(
+        coin_diff.final_repr(&hasher),
+        diff_hash(&expected.map(Fr::from), &hasher)
+    );
+}
+
+#[test]
+fn deposit_and_withdraw() {
+    let mut deposit = pool();
+    deposit.add_coin(USDC, 1000).unwrap();
+    assert_diff(&deposit, [0, 1000, 0, 0, 0]);
+
+    let mut large = pool();
+    large.add_coin(SUI, u64::MAX).unwrap();
+    large.sub_coin(WAL, u64::MAX).unwrap();
+    assert_diff(&large, [u64::MAX.into(), 0, -i128::from(u64::MAX), 0, 0]);
+
+    let mut withdraw = pool();
+    withdraw.sub_coin(WAL, 7).unwrap();
+    assert_diff(&withdraw, [0, 0, -7, 0, 0]);
+}
+
+#[test]
+fn swap_matches_the_conformance_suite() {
+    // start_swap: sub_coin(origin), then add_coin(target, minimum_received)
+    let mut swap = pool();
+    swap.sub_coin(SUI, 500).unwrap();
+    swap.add_coin(WAL, 250).unwrap();
+    assert_diff(&swap, [-500, 0, 250, 0, 0]);
+
+    let suite = conformance::generate();
+    let vector = suite
+        .diff_hashes
+        .iter()
+        .find(|v| v.diff == ["-500", "0", "250", "0", "0"])
+        .unwrap();
+    assert_eq!(
+        swap.values().iter().map(fr_to_hex).collect::<Vec<_>>(),
+        vector.diff_fr
+    );
+    assert_eq!(
+        swap.final_repr(&PoseidonHash::new(poseidon_bn254())),
+        vector.diff_hash
+    );
+}
+
+#[test]
+fn empty_diff_hashes_every_slot() {
+    assert_diff(&pool(), [0; ASSET_SIZE]);
+    assert_eq!(pool().values().len(), ASSET_SIZE);
+}
+
+#[test]
+fn coin_types_are_normalized() {
+    assert_eq!(
+        type_name(SUI).unwrap(),
+        format!("{}2::sui::SUI", "0".repeat(63))
+    );
+    assert_eq!(
+        type_name("0x2::coin::Coin<0X2::sui::SUI>").unwrap(),
+        format!("{0}2::coin::Coin<{0}2::sui::SUI>", "0".repeat(63))
+    );
+    assert!(type_name("0xzz::sui::SUI").is_err());
+
+    let mut diff = pool();
+    diff.add_coin(&type_name(SUI).unwrap(), 1).unwrap();
+    assert_eq!(diff.index_of("0x02::sui::SUI").unwrap(), 0);
+    assert_diff(&diff, [1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn aborts_like_the_contract() {
+    let mut diff = pool();
+    assert!(diff.add_coin("0x2::other::OTHER", 1).is_err());
+    assert!(CoinDiff::empty(ASSET_SIZE, &[SUI, "0x02::sui::SUI"]).is_err());
+    assert!(CoinDiff::empty(ASSET_SIZE + 1, &[SUI])
+        .unwrap()
+        .diff()
+        .is_err());
+}
@@ -0,0 +1,66 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use circuits_rust::{
+    poseidon::{poseidon_bn254, PoseidonHash},
+    registry::{AssetInfo, AssetRegistry, PoolDescriptor},
+    utils::fr_to_hex,
+    wasm::{Account, State},
+    ASSET_SIZE,
+};
+
+/// Package of the devnet deployment.
+pub const PACKAGE: &str = "0x1ff54c1d795cfcee79686c0c5e994babcb47fa7facd1ce664ffa11c7ff17791d";
+
+/// Counter and history root of the notes `funded` builds.
+pub const COUNTER: u64 = 1;
+pub const HISTORY: u64 = 7;
+
+/// Registry of a pool allowing `assets`, coin type and decimals, in order.
+pub fn registry(assets: &[(&str, u8)]) -> AssetRegistry {
+    AssetRegistry::new(PoolDescriptor {
+        allowed_token_length: ASSET_SIZE,
+        allowed_tokens: assets
+            .iter()
+            .map(|(coin_type, decimals)| AssetInfo {
+                coin_type: coin_type.to_string(),
+                symbol: String::new(),
+                decimals: *decimals,
+            })
+            .collect(),
+    })
+    .unwrap()
+}
+
+/// Account of 0xa11ce with spending key 42, without a note yet.
+pub fn account() -> Account {
+    Account::new(Fr::from(0xa11ceu64), Fr::from(42u64))
+}
+
+/// State of `account` holding `balance` in its note at `index`, after
+/// `COUNTER` updates. The other leaves of the tree are 1, 2, ..., one of them
+/// after the note.
+pub fn funded(mut account: Account, balance: [u64; ASSET_SIZE], index: usize) -> State {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    account.set_balance(balance);
+    account.set_history(COUNTER, Fr::from(HISTORY));
+    account.wasm_set_index(index);
+
+    let leaves = (1..=index as u64)
+        .map(Fr::from)
+        .chain([account.leaf(&hasher), Fr::from(index as u64 + 1)]);
+    let mut state = State::wasm_new(account);
+    state
+        .wasm_set_leafs(leaves.map(|l| fr_to_hex(&l)).collect())
+        .unwrap();
+    state
+}
+
+pub fn is_satisfied(circuit: impl ConstraintSynthesizer<Fr>) -> bool {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit.generate_constraints(cs.clone()).unwrap();
+    cs.is_satisfied().unwrap()
+}
@@ -0,0 +1,45 @@
+//! The checked-in conformance suite and the Move test generated from it must
+//! match what this crate computes, so that Move is checked against the
+//! current reference.
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use circuits_rust::conformance::{generate, move_test, note_updates};
+
+#[test]
+fn checked_in_suite_is_up_to_date() {
+    let checked_in: serde_json::Value =
+        serde_json::from_str(include_str!("../conformance.json")).unwrap();
+    assert!(
+        checked_in == serde_json::to_value(generate()).unwrap(),
+        "conformance.json is stale, regenerate it with `cargo run --bin conformance conformance.json ../contracts/tests/conformance_test.move`"
+    );
+}
+
+#[test]
+fn checked_in_move_test_is_up_to_date() {
+    assert!(
+        include_str!("../../contracts/tests/conformance_test.move") == move_test(&generate()),
+        "conformance_test.move is stale, regenerate it with `cargo run --bin conformance conformance.json ../contracts/tests/conformance_test.move`"
+    );
+}
+
+#[test]
+fn amounts_are_decimal_strings() {
+    let suite = serde_json::to_value(generate()).unwrap();
+    // u64::MAX is not exact as a JSON number read by JavaScript
+    assert_eq!(suite["leaves"][2]["balance"][0], u64::MAX.to_string());
+    assert_eq!(suite["diff_hashes"][1]["diff"][0], "1000");
+}
+
+#[test]
+fn public_inputs_are_of_valid_note_updates() {
+    for (name, circuit) in note_updates() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(
+            cs.is_satisfied().unwrap(),
+            "{name} is not a valid note update"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Escrow of `address || diff` to an auditor key, natively and in circuit,
+//! and the signed amount encoding the diffs are opened with.
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{AdditiveGroup, Field, PrimeField};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_relations::r1cs::ConstraintSystem;
+use circuits_rust::{
+    curve::Curve,
+    escrow::{AuditorKey, Escrow, EscrowCiphertext, EscrowVar},
+    gadgets::{from_i128, to_i128, to_signed},
+    hash::TwoToOneHash,
+    poseidon::PoseidonHash,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const DIFF: [i128; 5] = [-30, 0, 7, -(u64::MAX as i128), u64::MAX as i128];
+
+fn message<F: PrimeField>() -> Vec<F> {
+    [vec![F::from(0xa11ceu64)], DIFF.map(F::from).to_vec()].concat()
+}
+
+fn roundtrip<E: Curve>() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let hasher = PoseidonHash::new(E::poseidon());
+    let key = AuditorKey::<E>::generate(&mut rng);
+    let escrow = Escrow::<E>::new(key.public_key(), &message(), &mut rng, &hasher);
+
+    let ciphertext = EscrowCiphertext::<E>::from_bytes(&escrow.ciphertext.to_bytes()).unwrap();
+    assert_eq!(ciphertext, escrow.ciphertext);
+    assert_eq!(
+        key.decrypt(&ciphertext, &hasher),
+        message::<E::ScalarField>()
+    );
+    let plaintext = key.open(&ciphertext, &hasher).unwrap();
+    assert_eq!(plaintext.address, E::ScalarField::from(0xa11ceu64));
+    assert_eq!(plaintext.diff, DIFF);
+
+    // another key opens nothing
+    let other = AuditorKey::<E>::generate(&mut rng);
+    assert!(other.open(&ciphertext, &hasher).is_err());
+    // fresh randomness for every escrow
+    let again = Escrow::<E>::new(key.public_key(), &message(), &mut rng, &hasher);
+    assert_ne!(again.ciphertext, escrow.ciphertext);
+}
+
+#[test]
+fn roundtrip_bn254() {
+    roundtrip::<Bn254>();
+}
+
+#[test]
+fn roundtrip_bls12_381() {
+    roundtrip::<Bls12_381>();
+}
+
+#[test]
+fn malformed_ciphertexts_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let hasher = PoseidonHash::new(Bn254::poseidon());
+    let key = AuditorKey::<Bn254>::generate(&mut rng);
+    let bytes = Escrow::<Bn254>::new(key.public_key(), &message(), &mut rng, &hasher)
+        .ciphertext
+        .to_bytes();
+
+    assert!(EscrowCiphertext::<Bn254>::from_bytes(&bytes[..63]).is_err());
+    assert!(EscrowCiphertext::<Bn254>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    let mut off_curve = bytes.clone();
+    off_curve[0] ^= 1;
+    assert!(EscrowCiphertext::<Bn254>::from_bytes(&off_curve).is_err());
+}
+
+#[test]
+fn circuit_enforces_the_encryption() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let hasher = PoseidonHash::new(Bn254::poseidon());
+    let key = AuditorKey::<Bn254>::generate(&mut rng);
+    let escrow = Escrow::<Bn254>::new(key.public_key(), &message(), &mut rng, &hasher);
+
+    let satisfied = |escrow: &Escrow<Bn254>, message: Vec<Fr>| {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let hasher_var =
+            <PoseidonHash<Fr> as TwoToOneHash<Fr>>::Var::new_constant(cs.clone(), hasher.clone())
+                .unwrap();
+        let message = Vec::<FpVar<Fr>>::new_witness(cs.clone(), || Ok(message)).unwrap();
+        EscrowVar::new(cs.clone(), escrow)
+            .unwrap()
+            .enforce_encryption(&message, &hasher_var)
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    };
+    assert!(satisfied(&escrow, message()));
+
+    let mut other_message = message::<Fr>();
+    other_message[1] += Fr::ONE;
+    assert!(!satisfied(&escrow, other_message));
+    let mut tampered = escrow.clone();
+    tampered.ciphertext.data[0] += Fr::ONE;
+    assert!(!satisfied(&tampered, message()));
+}
+
+#[test]
+fn signed_amounts_of_negative_diffs() {
+    let max = i128::from(u64::MAX);
+    assert_eq!(to_i128(&Fr::ZERO), Some(0));
+    assert_eq!(to_i128(&-Fr::ONE), Some(-1));
+    assert_eq!(to_signed(&-Fr::ONE), Some((true, 1)));
+    assert_eq!(to_i128(&Fr::from(-30i64)), Some(-30));
+    assert_eq!(to_i128(&Fr::from(-max)), Some(-max));
+    assert_eq!(to_signed(&Fr::from(-max)), Some((true, u64::MAX)));
+    assert_eq!(to_i128(&Fr::from(max)), Some(max));
+
+    // magnitudes of 2^64 and above are not amounts
+    assert_eq!(to_i128(&Fr::from(max + 1)), None);
+    assert_eq!(to_i128(&Fr::from(-max - 1)), None);
+    // nor is (p + 1) / 2, far from both ends of the field
+    assert_eq!(to_i128(&Fr::from(2u64).inverse().unwrap()), None);
+
+    for amount in [0, 1, -1, -30, max, -max] {
+        assert_eq!(to_i128(&from_i128::<Fr>(amount).unwrap()), Some(amount));
+    }
+    assert_eq!(from_i128::<Fr>(max + 1), None);
+    assert_eq!(from_i128::<Fr>(-max - 1), None);
+}
@@ -0,0 +1,184 @@
+//! Boundaries of the range checks, comparisons and signed amounts of
+//! `gadgets`.
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, Field};
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    fields::fp::FpVar,
+    prelude::{Boolean, EqGadget},
+    R1CSVar,
+};
+use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+use circuits_rust::gadgets::{greater_or_equal, less_than, select_leaf, SignedVar, UInt64Var};
+
+const MAX: u64 = u64::MAX;
+
+/// 2^64 as a field element.
+fn two_to_64() -> Fr {
+    Fr::from(MAX) + Fr::ONE
+}
+
+/// Whether `f` synthesizes satisfied constraints over fresh witnesses.
+fn satisfied(f: impl FnOnce(ConstraintSystemRef<Fr>)) -> bool {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    f(cs.clone());
+    cs.is_satisfied().unwrap()
+}
+
+fn witness(cs: &ConstraintSystemRef<Fr>, value: Fr) -> FpVar<Fr> {
+    FpVar::new_witness(cs.clone(), || Ok(value)).unwrap()
+}
+
+fn amount(value: Fr) -> bool {
+    satisfied(|cs| {
+        UInt64Var::from_fp(witness(&cs, value)).unwrap();
+    })
+}
+
+#[test]
+fn amounts_end_at_2_64_minus_1() {
+    assert!(amount(Fr::ZERO));
+    assert!(amount(Fr::from(MAX)));
+    assert!(!amount(two_to_64()));
+    // negative diffs wrap to just below the modulus
+    assert!(!amount(-Fr::ONE));
+    assert!(satisfied(|cs| {
+        UInt64Var::new_witness(cs, || Ok(MAX)).unwrap();
+    }));
+}
+
+/// `greater_or_equal(a, b)` and `less_than(a, b)` of witnesses, enforcing
+/// that they disagree.
+fn compare(a: u64, b: u64) -> bool {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let (a, b) = (witness(&cs, Fr::from(a)), witness(&cs, Fr::from(b)));
+    let ge = greater_or_equal(&a, &b, 64).unwrap();
+    less_than(&a, &b, 64).unwrap().enforce_equal(&!&ge).unwrap();
+    assert!(cs.is_satisfied().unwrap());
+    ge.value().unwrap()
+}
+
+#[test]
+fn comparisons_at_the_boundaries() {
+    for a in [0, 1, 7, MAX - 1, MAX] {
+        assert!(compare(a, a), "{a} >= {a}");
+    }
+    assert!(compare(8, 7));
+    assert!(!compare(7, 8));
+    assert!(compare(MAX, 0));
+    assert!(!compare(0, MAX));
+    assert!(!compare(MAX - 1, MAX));
+}
+
+#[test]
+fn comparison_results_cannot_be_flipped() {
+    // a >= b enforced as false for a = b
+    assert!(!satisfied(|cs| {
+        let a = witness(&cs, Fr::from(5u64));
+        greater_or_equal(&a, &a, 64)
+            .unwrap()
+            .enforce_equal(&Boolean::FALSE)
+            .unwrap();
+    }));
+}
+
+#[test]
+fn uint64_comparisons() {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let small = UInt64Var::new_witness(cs.clone(), || Ok(3)).unwrap();
+    let large = UInt64Var::new_witness(cs.clone(), || Ok(MAX)).unwrap();
+    assert!(small.is_lt(&large).unwrap().value().unwrap());
+    assert!(!large.is_lt(&small).unwrap().value().unwrap());
+    assert!(large.is_ge(&large).unwrap().value().unwrap());
+    assert!(!small.is_lt(&small).unwrap().value().unwrap());
+    assert!(!small
+        .is_ge(&UInt64Var::constant(4))
+        .unwrap()
+        .value()
+        .unwrap());
+    assert!(cs.is_satisfied().unwrap());
+}
+
+/// Whether `value` decomposes as the sign and magnitude given, under the
+/// constraint `decompose` enforces.
+fn decomposes_as(value: Fr, is_negative: bool, magnitude: u64) -> bool {
+    satisfied(|cs| {
+        let signed = SignedVar {
+            is_negative: Boolean::new_witness(cs.clone(), || Ok(is_negative)).unwrap(),
+            magnitude: UInt64Var::new_witness(cs.clone(), || Ok(magnitude)).unwrap(),
+        };
+        signed
+            .to_fp()
+            .unwrap()
+            .enforce_equal(&witness(&cs, value))
+            .unwrap();
+    })
+}
+
+#[test]
+fn decompose_signed_amounts() {
+    let decompose = |value: Fr| {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let signed = SignedVar::decompose(&witness(&cs, value)).unwrap();
+        (
+            signed.is_negative.value().unwrap(),
+            signed.magnitude.as_fp().value().unwrap(),
+            cs.is_satisfied().unwrap(),
+        )
+    };
+    assert_eq!(decompose(-Fr::ONE), (true, Fr::ONE, true));
+    assert_eq!(decompose(Fr::ZERO), (false, Fr::ZERO, true));
+    assert_eq!(decompose(Fr::from(MAX)), (false, Fr::from(MAX), true));
+    assert_eq!(decompose(-Fr::from(MAX)), (true, Fr::from(MAX), true));
+    // magnitudes of 2^64 and above have no decomposition
+    assert!(!decompose(two_to_64()).2);
+    assert!(!decompose(-two_to_64()).2);
+
+    // p - 1 is only -1
+    assert!(decomposes_as(-Fr::ONE, true, 1));
+    assert!(!decomposes_as(-Fr::ONE, false, 1));
+    // zero is zero with either sign bit
+    assert!(decomposes_as(Fr::ZERO, false, 0));
+    assert!(decomposes_as(Fr::ZERO, true, 0));
+    assert!(!decomposes_as(Fr::ZERO, true, 1));
+}
+
+#[test]
+fn add_signed_stays_an_amount() {
+    let add = |balance: u64, diff: Fr| {
+        satisfied(|cs| {
+            let balance = UInt64Var::new_witness(cs.clone(), || Ok(balance)).unwrap();
+            let diff = SignedVar::decompose(&witness(&cs, diff)).unwrap();
+            balance.add_signed(&diff).unwrap();
+        })
+    };
+    assert!(add(5, -Fr::from(5u64)));
+    assert!(add(0, Fr::from(MAX)));
+    assert!(add(MAX, -Fr::from(MAX)));
+    assert!(!add(5, -Fr::from(6u64)));
+    assert!(!add(MAX, Fr::ONE));
+    assert!(!add(1, Fr::from(MAX)));
+}
+
+#[test]
+fn select_leaf_by_position() {
+    let leaves = (10..18u64).map(Fr::from).collect::<Vec<_>>();
+    for index in 0..leaves.len() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let position = (0..3)
+            .map(|bit| Boolean::new_witness(cs.clone(), || Ok(index >> bit & 1 == 1)).unwrap())
+            .collect::<Vec<_>>();
+        let leaf_vars = leaves.iter().map(|l| witness(&cs, *l)).collect::<Vec<_>>();
+        let selected = select_leaf(&position, &leaf_vars).unwrap();
+        assert_eq!(selected.value().unwrap(), leaves[index], "index {index}");
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
+
+#[test]
+#[should_panic(expected = "One leaf for every position")]
+fn select_leaf_needs_a_leaf_for_every_position() {
+    let leaves = vec![FpVar::Constant(Fr::ONE); 3];
+    let _ = select_leaf(&[Boolean::TRUE, Boolean::FALSE], &leaves);
+}
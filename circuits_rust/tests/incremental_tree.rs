@@ -0,0 +1,108 @@
+//! `IncrementalTree` models `merkle.move`, it must agree with the
+//! `SparseMerkleTree` the proofs are built from.
+
+use std::collections::BTreeMap;
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, PrimeField};
+use circuits_rust::{
+    conformance,
+    merkle_tree::{IncrementalTree, SparseMerkleTree},
+    poseidon::{poseidon_bn254, PoseidonHash},
+    utils::fr_from_hex,
+    LEVEL,
+};
+use proptest::prelude::*;
+
+const TEST_LEVEL: usize = 6;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn matches_sparse_tree(
+        leaves in prop::collection::vec(any::<[u8; 32]>(), 0..=1 << TEST_LEVEL),
+        default_leaf in any::<u64>(),
+    ) {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        let default_leaf = Fr::from(default_leaf);
+        let mut incremental =
+            IncrementalTree::new(TEST_LEVEL as u8, 4, default_leaf, &hasher).unwrap();
+        let mut sparse =
+            SparseMerkleTree::<TEST_LEVEL>::new(&BTreeMap::new(), &hasher, &default_leaf).unwrap();
+        prop_assert_eq!(incremental.root(), sparse.root());
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let leaf = Fr::from_le_bytes_mod_order(leaf);
+            let previous = incremental.root();
+
+            let (index, root) = incremental.insert(leaf, &hasher).unwrap();
+            sparse.insert_batch(&BTreeMap::from([(i as u32, leaf)]), &hasher).unwrap();
+
+            prop_assert_eq!(index, i as u64);
+            prop_assert_eq!(root, sparse.root());
+            prop_assert_eq!(incremental.root(), root);
+            prop_assert!(incremental.is_valid_root(&root));
+            // the window only holds the latest root
+            prop_assert_eq!(incremental.valid_roots(), &[root][..]);
+            prop_assert!(previous == root || !incremental.is_valid_root(&previous));
+        }
+        prop_assert_eq!(incremental.size(), leaves.len() as u64);
+    }
+}
+
+#[test]
+fn rejects_inserts_past_capacity() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let mut tree = IncrementalTree::new(2, 1, Fr::ZERO, &hasher).unwrap();
+    for i in 0..4u64 {
+        assert_eq!(tree.insert(Fr::from(i), &hasher).unwrap().0, i);
+    }
+    assert!(tree.insert(Fr::from(4u64), &hasher).is_err());
+    assert_eq!(tree.size(), 4);
+}
+
+#[test]
+fn rejects_invalid_parameters() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    assert!(IncrementalTree::new(1, 1, Fr::ZERO, &hasher).is_err());
+    assert!(IncrementalTree::new(2, 0, Fr::ZERO, &hasher).is_err());
+}
+
+#[test]
+fn matches_conformance_roots() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let merkle = conformance::generate().merkle;
+    let mut tree = IncrementalTree::new(LEVEL as u8, 100, merkle.default_leaf, &hasher).unwrap();
+
+    let roots = merkle
+        .roots
+        .iter()
+        .map(|r| fr_from_hex(r).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tree.root(), roots[0]);
+    for (leaf, root) in merkle.leaves.iter().zip(&roots[1..]) {
+        tree.insert(fr_from_hex(leaf).unwrap(), &hasher).unwrap();
+        assert_eq!(tree.root(), *root);
+    }
+}
+
+#[test]
+fn sparse_tree_holds_a_full_level() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let leaves = (1..=4u64).map(Fr::from).collect::<Vec<_>>();
+    let sparse = SparseMerkleTree::<2>::new_sequential(&leaves, &hasher, &Fr::ZERO).unwrap();
+    let mut incremental = IncrementalTree::new(2, 1, Fr::ZERO, &hasher).unwrap();
+    for leaf in &leaves {
+        incremental.insert(*leaf, &hasher).unwrap();
+    }
+    assert_eq!(sparse.root(), incremental.root());
+}
+
+#[test]
+#[should_panic(expected = "Too many leaves")]
+fn sparse_tree_rejects_leaves_past_capacity() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let leaves = (1..=5u64).map(Fr::from).collect::<Vec<_>>();
+    let _ = SparseMerkleTree::<2>::new_sequential(&leaves, &hasher, &Fr::ZERO);
+}
@@ -0,0 +1,270 @@
+//! Intents against the diffs and arguments of the `core` entry points they
+//! stand for.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use circuits_rust::{
+    circuit::SINGLE_NOTE,
+    intent::{Intent, MoveArg, MoveCall},
+    poseidon::{poseidon_bn254, PoseidonHash},
+    registry::AssetRegistry,
+    wasm::{NoteUpdate, State},
+    ASSET_SIZE,
+};
+use common::{account, is_satisfied, PACKAGE};
+use serde_json::json;
+
+const SHROUD: &str = "0xeab4fea514cf302ba359351e7778ffac0188b2d0a3026afcf1fabade6fcc4faa";
+const USDC: &str = "0x1::usdc::USDC";
+const WAL: &str = "0x1::wal::WAL";
+
+fn registry() -> AssetRegistry {
+    common::registry(&[(USDC, 6), (WAL, 9)])
+}
+
+/// State of an account with its note at index 0 of a two leaf tree.
+fn state(balance: [u64; ASSET_SIZE]) -> State {
+    common::funded(account(), balance, 0)
+}
+
+fn note_update(intent: &Intent, state: &State) -> NoteUpdate {
+    let update = intent.note_update(&registry(), state, None).unwrap();
+    assert!(is_satisfied(update.circuit.clone()));
+    update
+}
+
+fn coin_type(coin_type: &str) -> String {
+    format!("0x{}", registry().asset(coin_type).unwrap().coin_type)
+}
+
+/// Parameters of `core::{function}` as `(name, type)`, in the order the
+/// contract declares them, without the `TxContext`.
+fn signature(function: &str) -> Vec<(String, String)> {
+    let source = include_str!("../../contracts/sources/core.move");
+    let start = [
+        format!("public fun {function}("),
+        format!("public fun {function}<"),
+    ]
+    .iter()
+    .find_map(|decl| source.find(decl.as_str()))
+    .unwrap_or_else(|| panic!("core::{function} not found"));
+    let params = &source[start..];
+    let params = &params[params.find('(').unwrap() + 1..params.find(')').unwrap()];
+    params
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (name, ty) = p.split_once(':').unwrap();
+            (name.trim().to_string(), ty.trim().to_string())
+        })
+        .filter(|(_, ty)| ty != "&mut TxContext")
+        .collect()
+}
+
+/// Checks `call` against the declaration of the entry point it targets, one
+/// argument of the parameter's type per parameter, and the values of `update`
+/// under the names the contract gives them.
+fn assert_signature(call: &MoveCall, update: &NoteUpdate, proof: &[u8]) {
+    let function = call.target.rsplit("::").next().unwrap();
+    let params = signature(function);
+    assert_eq!(call.arguments.len(), params.len(), "core::{function}");
+
+    let c = &update.circuit;
+    for ((name, ty), arg) in params.iter().zip(&call.arguments) {
+        let typed = match arg {
+            MoveArg::Object { .. } => ty == "&mut Shroud" || ty == "&Shroud",
+            MoveArg::Result { .. } => ty == "PublicOperation",
+            MoveArg::Coin { .. } => ty.starts_with("Coin<"),
+            MoveArg::U64 { .. } => ty == "u64",
+            MoveArg::U256 { .. } => ty == "u256",
+            MoveArg::Bytes { .. } => ty == "vector<u8>",
+        };
+        assert!(typed, "core::{function} takes {name}: {ty}, given {arg:?}");
+
+        let expected = match name.as_str() {
+            "current_root" => MoveArg::U256 {
+                value: c.merkle_root,
+            },
+            "old_leaf_nullifier" => MoveArg::U256 { value: c.nullifier },
+            "new_leaf" => MoveArg::U256 {
+                value: c.after_leaf,
+            },
+            "note_type" => MoveArg::U64 { value: SINGLE_NOTE },
+            "proof" => MoveArg::Bytes {
+                value: hex::encode(proof),
+            },
+            "memo" => MoveArg::Bytes {
+                value: hex::encode(&update.memo),
+            },
+            "escrow" => MoveArg::Bytes {
+                value: hex::encode(&update.escrow),
+            },
+            _ => continue,
+        };
+        assert_eq!(*arg, expected, "core::{function} {name}");
+    }
+}
+
+#[test]
+fn deposit() {
+    let state = state([0; ASSET_SIZE]);
+    let intent = Intent::Deposit {
+        coin_type: WAL.to_string(),
+        amount: 5,
+    };
+    assert!(intent.is_public());
+    let update = note_update(&intent, &state);
+    assert_eq!(update.circuit.public_address, state.account().address_fr());
+    assert_eq!(
+        intent.coin_diff(&registry()).unwrap().diffs().unwrap(),
+        [0, 5, 0, 0, 0]
+    );
+
+    let calls = intent
+        .move_calls(&registry(), PACKAGE, SHROUD, &update, &[1, 2])
+        .unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].target, format!("{PACKAGE}::core::deposit"));
+    assert_eq!(calls[0].type_arguments, [coin_type(WAL)]);
+    assert_signature(&calls[0], &update, &[1, 2]);
+    assert_eq!(
+        calls[0].arguments[..2],
+        [
+            MoveArg::Object {
+                id: SHROUD.to_string()
+            },
+            MoveArg::Coin {
+                coin_type: coin_type(WAL),
+                amount: 5
+            },
+        ]
+    );
+}
+
+#[test]
+fn withdraw() {
+    let state = state([10, 0, 0, 0, 0]);
+    let intent = Intent::Withdraw {
+        coin_type: USDC.to_string(),
+        amount: u64::MAX,
+    };
+    assert!(intent.note_update(&registry(), &state, None).is_err());
+
+    let intent = Intent::Withdraw {
+        coin_type: USDC.to_string(),
+        amount: 10,
+    };
+    let update = note_update(&intent, &state);
+    let calls = intent
+        .move_calls(&registry(), PACKAGE, SHROUD, &update, &[])
+        .unwrap();
+    assert_eq!(calls[0].target, format!("{PACKAGE}::core::withdraw"));
+    assert_signature(&calls[0], &update, &[]);
+    assert_eq!(calls[0].arguments[1], MoveArg::U64 { value: 10 });
+}
+
+#[test]
+fn swap_debits_from_and_credits_min_out() {
+    let state = state([10, 0, 0, 0, 0]);
+    let intent = Intent::Swap {
+        from: USDC.to_string(),
+        to: WAL.to_string(),
+        amount: 4,
+        min_out: 3_000,
+    };
+    assert!(!intent.is_public());
+    assert_eq!(
+        intent.coin_diff(&registry()).unwrap().diffs().unwrap(),
+        [-4, 3_000, 0, 0, 0]
+    );
+    let update = note_update(&intent, &state);
+    assert_eq!(update.circuit.public_address, Fr::ZERO);
+
+    let calls = intent
+        .move_calls(&registry(), PACKAGE, SHROUD, &update, &[])
+        .unwrap();
+    assert_eq!(calls[0].target, format!("{PACKAGE}::core::start_swap"));
+    assert_eq!(calls[0].type_arguments, [coin_type(USDC), coin_type(WAL)]);
+    assert_signature(&calls[0], &update, &[]);
+    assert_eq!(
+        calls[0].arguments[1..3],
+        [MoveArg::U64 { value: 4 }, MoveArg::U64 { value: 3_000 }]
+    );
+
+    let to_itself = Intent::Swap {
+        from: USDC.to_string(),
+        to: "0x01::usdc::USDC".to_string(),
+        amount: 1,
+        min_out: 1,
+    };
+    assert!(to_itself.coin_diff(&registry()).is_err());
+}
+
+#[test]
+fn refresh_is_an_empty_public_operation() {
+    let state = state([3, 4, 0, 0, 0]);
+    let update = note_update(&Intent::Refresh, &state);
+    assert_eq!(
+        update.circuit.diff_hash,
+        registry()
+            .coin_diff()
+            .final_repr(&PoseidonHash::new(poseidon_bn254()))
+    );
+
+    let calls = Intent::Refresh
+        .move_calls(&registry(), PACKAGE, SHROUD, &update, &[])
+        .unwrap();
+    let targets = calls.iter().map(|c| c.target.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        targets,
+        [
+            format!("{PACKAGE}::core::start_public_operation"),
+            format!("{PACKAGE}::core::finish_public_operation"),
+        ]
+    );
+    for call in &calls {
+        assert_signature(call, &update, &[]);
+    }
+    assert_eq!(calls[1].arguments[1], MoveArg::Result { index: 0 });
+}
+
+#[test]
+fn json_shapes() {
+    let intent: Intent = serde_json::from_value(json!({
+        "type": "swap",
+        "from": USDC,
+        "to": WAL,
+        "amount": "18446744073709551615",
+        "min_out": "1",
+    }))
+    .unwrap();
+    assert_eq!(
+        intent,
+        Intent::Swap {
+            from: USDC.to_string(),
+            to: WAL.to_string(),
+            amount: u64::MAX,
+            min_out: 1,
+        }
+    );
+    assert_eq!(
+        serde_json::from_value::<Intent>(json!({ "type": "refresh" })).unwrap(),
+        Intent::Refresh
+    );
+
+    assert_eq!(
+        serde_json::to_value(MoveArg::Coin {
+            coin_type: USDC.to_string(),
+            amount: u64::MAX,
+        })
+        .unwrap(),
+        json!({ "kind": "coin", "coin_type": USDC, "amount": "18446744073709551615" })
+    );
+    assert_eq!(
+        serde_json::to_value(MoveArg::U256 { value: Fr::ZERO }).unwrap(),
+        json!({ "kind": "u256", "value": "0".repeat(64) })
+    );
+}
@@ -0,0 +1,267 @@
+//! Adversarial witnesses for `MainCircuit`: each test starts from a valid note
+//! update, applies one targeted mutation and checks that the constraint system
+//! rejects it, reporting the first unsatisfied constraint.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, Field};
+use ark_relations::r1cs::{ConstraintLayer, ConstraintSynthesizer, ConstraintSystem};
+use circuits_rust::{
+    hash::Domain,
+    merkle_tree::SparseMerkleTree,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    wasm::{diff_hash, history_step, viewing_key, Account, NoteUpdate, State},
+    Circuit, ASSET_SIZE, LEVEL,
+};
+use common::{account, COUNTER, HISTORY};
+use tracing_subscriber::layer::SubscriberExt;
+
+const BEFORE: [u64; ASSET_SIZE] = [100, 5, 0, 0, 0];
+const INDEX: usize = 2;
+
+/// Note at `INDEX` of a small tree, spent by a valid witness that `update`
+/// builds for any diff.
+struct Fixture {
+    hasher: PoseidonHash,
+    account: Account,
+    spending_key: Fr,
+    history: Fr,
+    tree: SparseMerkleTree<LEVEL>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        let state = common::funded(account(), BEFORE, INDEX);
+        let tree = SparseMerkleTree::new_sequential(state.leafs(), &hasher, &Fr::ZERO).unwrap();
+
+        Self {
+            hasher,
+            account: state.account().clone(),
+            spending_key: Fr::from(42u64),
+            history: Fr::from(HISTORY),
+            tree,
+        }
+    }
+
+    /// Leaf over field balances, which may be out of range.
+    fn leaf(&self, balance: &[Fr; ASSET_SIZE], counter: u64, history: &Fr) -> Fr {
+        let h = &self.hasher;
+        let balance_hash = balance.iter().fold(self.account.prehash(h), |acc, b| {
+            h.hash_in(Domain::Leaf, &acc, b)
+        });
+        h.hash_in(
+            Domain::Leaf,
+            &h.hash_in(Domain::Leaf, &balance_hash, &Fr::from(counter)),
+            history,
+        )
+    }
+
+    /// Valid witness of applying `diff`, its after balances are computed in
+    /// the field and so wrap around if they go negative.
+    fn update(&self, diff: [i128; ASSET_SIZE]) -> Circuit {
+        let before = BEFORE.map(Fr::from);
+        let diff = diff.map(Fr::from);
+        let after = std::array::from_fn(|i| before[i] + diff[i]);
+        let diff_hash = diff_hash(&diff, &self.hasher);
+        let after_history = history_step(&self.history, &diff_hash, COUNTER + 1, &self.hasher);
+        let before_leaf = self.account.leaf(&self.hasher);
+
+        Circuit {
+            spending_key: self.spending_key,
+            address: self.account.address_fr(),
+            public_address: Fr::ZERO,
+            before,
+            diff,
+            after,
+            history: self.history,
+            counter: Fr::from(COUNTER),
+            merkle_root: self.tree.root(),
+            merkle_path: self.tree.generate_membership_proof(INDEX as u64),
+            diff_hash,
+            nullifier: self.account.nullifier_of(&before_leaf, &self.hasher),
+            after_leaf: self.leaf(&after, COUNTER + 1, &after_history),
+            hasher: self.hasher.clone(),
+            aux: Fr::ZERO,
+            escrow: None,
+        }
+    }
+
+    fn valid(&self) -> Circuit {
+        self.update([-30, 5, 0, 0, 0])
+    }
+}
+
+/// First unsatisfied constraint of `circuit`, with its trace, or `None` if
+/// the witness is accepted.
+fn unsatisfied(circuit: Circuit) -> Option<String> {
+    let subscriber = tracing_subscriber::Registry::default().with(ConstraintLayer::default());
+    tracing::subscriber::with_default(subscriber, || {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.which_is_unsatisfied().unwrap()
+    })
+}
+
+/// Asserts that `circuit` is rejected by the `check` namespace of
+/// `MainCircuit::synthesize` and prints the failing constraint.
+fn assert_rejected(name: &str, circuit: Circuit, check: &str) {
+    let failed = unsatisfied(circuit).unwrap_or_else(|| panic!("{name}: witness accepted"));
+    println!("{name}: rejected by\n{failed}");
+    assert!(
+        failed.contains(&format!("circuits_rust::circuit::{check}")),
+        "{name}: rejected outside of the {check} check"
+    );
+}
+
+#[test]
+fn valid_witness_is_accepted() {
+    let fixture = Fixture::new();
+    assert_eq!(unsatisfied(fixture.valid()), None);
+
+    // public operation bound to the owner
+    let mut circuit = fixture.valid();
+    circuit.public_address = circuit.address;
+    assert_eq!(unsatisfied(circuit), None);
+}
+
+#[test]
+fn negative_after_balance() {
+    let fixture = Fixture::new();
+    assert_rejected(
+        "negative after balance",
+        fixture.update([-101, 0, 0, 0, 0]),
+        "balance",
+    );
+}
+
+#[test]
+fn diffs_span_the_full_amount_range() {
+    let fixture = Fixture::new();
+    let max = i128::from(u64::MAX);
+    assert_eq!(unsatisfied(fixture.update([max - 100, 0, 0, 0, 0])), None);
+    assert_rejected(
+        "after balance above u64::MAX",
+        fixture.update([max - 99, 0, 0, 0, 0]),
+        "balance",
+    );
+}
+
+#[test]
+fn wrong_nullifier() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    circuit.nullifier += Fr::ONE;
+    assert_rejected("wrong nullifier", circuit, "nullifier");
+}
+
+#[test]
+fn mismatched_public_address() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    circuit.public_address = circuit.address + Fr::ONE;
+    assert_rejected("mismatched public address", circuit, "public_address");
+}
+
+#[test]
+fn path_for_a_different_leaf() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    // the neighbour's path holds the same pairs, so it is also a path of this
+    // leaf, take one from the other subtree
+    circuit.merkle_path = fixture.tree.generate_membership_proof(0);
+    assert_rejected("path for a different leaf", circuit, "spend");
+}
+
+#[test]
+fn nullifier_of_the_spending_key() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    circuit.nullifier = fixture.hasher.hash_in(
+        Domain::Nullifier,
+        &fixture.account.leaf(&fixture.hasher),
+        &fixture.spending_key,
+    );
+    assert_rejected("nullifier of the spending key", circuit, "nullifier");
+}
+
+#[test]
+fn zero_nullifier_for_a_non_empty_leaf() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    assert_ne!(fixture.account.leaf(&fixture.hasher), Fr::ZERO);
+    circuit.nullifier = Fr::ZERO;
+    assert_rejected("zero nullifier for a non-empty leaf", circuit, "nullifier");
+}
+
+#[test]
+fn first_note_spends_the_genesis_nullifier() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let account = account();
+    let state = State::wasm_new(account.clone());
+    let circuit = NoteUpdate::new(&state, [100, 0, 0, 0, 0], Fr::ZERO, Fr::ZERO, None)
+        .unwrap()
+        .circuit;
+    // the genesis nullifier is that of the empty leaf, unique to the account
+    assert_eq!(circuit.nullifier, account.nullifier(&hasher));
+    assert_ne!(
+        circuit.nullifier,
+        Account::new(Fr::from(0xa11ceu64), Fr::from(43u64)).nullifier(&hasher)
+    );
+    assert_eq!(unsatisfied(circuit.clone()), None);
+
+    let mut zero = circuit;
+    zero.nullifier = Fr::ZERO;
+    assert_rejected("zero nullifier for the empty leaf", zero, "nullifier");
+}
+
+#[test]
+fn viewing_key_and_history_derivations() {
+    let fixture = Fixture::new();
+    let h = &fixture.hasher;
+    // viewing_key = H_view(spending_key, 0)
+    assert_eq!(
+        fixture.account.viewing_key(),
+        h.hash_in(Domain::ViewingKey, &fixture.spending_key, &Fr::ZERO)
+    );
+    assert_eq!(
+        fixture.account.viewing_key(),
+        viewing_key(&fixture.spending_key, h)
+    );
+    // the circuit derives it from the spending key, another key computes
+    // other leaves
+    let mut circuit = fixture.valid();
+    circuit.spending_key += Fr::ONE;
+    assert_rejected("spending key of another account", circuit, "after_leaf");
+
+    // after_history = H_history(H_history(history, diff_hash), counter + 1)
+    let d = diff_hash(&[-30, 5, 0, 0, 0].map(Fr::from), h);
+    let expected = h.hash_in(
+        Domain::History,
+        &h.hash_in(Domain::History, &fixture.history, &d),
+        &Fr::from(COUNTER + 1),
+    );
+    assert_eq!(history_step(&fixture.history, &d, COUNTER + 1, h), expected);
+    assert_eq!(fixture.account.next_history_root(&d, h), expected);
+    // the history commits to the order of the diffs
+    let e = diff_hash(&[1, 0, 0, 0, 0].map(Fr::from), h);
+    assert_ne!(
+        history_step(&history_step(&Fr::ZERO, &d, 1, h), &e, 2, h),
+        history_step(&history_step(&Fr::ZERO, &e, 1, h), &d, 2, h)
+    );
+
+    // an after leaf that does not step the counter is rejected
+    let mut circuit = fixture.valid();
+    let stale = history_step(&fixture.history, &d, COUNTER, h);
+    circuit.after_leaf = fixture.leaf(&circuit.after, COUNTER + 1, &stale);
+    assert_rejected("history of a stale counter", circuit, "after_leaf");
+}
+
+#[test]
+fn diff_not_hashing_to_diff_hash() {
+    let fixture = Fixture::new();
+    let mut circuit = fixture.valid();
+    circuit.diff_hash = fixture.update([-31, 5, 0, 0, 0]).diff_hash;
+    assert_rejected("diff not hashing to diff_hash", circuit, "diff_hash");
+}
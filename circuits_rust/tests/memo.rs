@@ -0,0 +1,97 @@
+//! Note memos: encryption to the account and recovery by trial decryption.
+
+use ark_bn254::Fr;
+use circuits_rust::{
+    memo::{MemoScanner, NoteMemo},
+    poseidon::{poseidon_bn254, PoseidonHash},
+    wasm::Account,
+};
+
+fn account(spending_key: u64) -> Account {
+    Account::new(Fr::from(0xb0bu64), Fr::from(spending_key))
+}
+
+fn memo(balance: u64, counter: u64) -> NoteMemo {
+    NoteMemo {
+        balance: [balance, 0, 0, 0, u64::MAX],
+        index_hint: 4,
+        counter,
+        history_root: Fr::from(counter + 100),
+    }
+}
+
+/// Leaf `memo` commits to for `account`.
+fn leaf(account: &Account, memo: &NoteMemo, hasher: &PoseidonHash) -> Fr {
+    account.leaf_of(&memo.balance, memo.counter, &memo.history_root, hasher)
+}
+
+#[test]
+fn roundtrip() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let account = account(7);
+    let memo = memo(5, 1);
+    let data = account.encrypt_memo(&memo, &hasher).unwrap();
+    assert_eq!(account.decrypt_memo(&data, &hasher).unwrap(), memo);
+    // the memo key derives from the viewing key
+    assert_eq!(
+        account
+            .to_watch_only()
+            .decrypt_memo(&data, &hasher)
+            .unwrap(),
+        memo
+    );
+    assert_ne!(account.encrypt_memo(&memo, &hasher).unwrap(), data);
+}
+
+#[test]
+fn foreign_and_malformed_memos_are_rejected() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let data = account(7).encrypt_memo(&memo(5, 1), &hasher).unwrap();
+    assert!(account(8).decrypt_memo(&data, &hasher).is_err());
+
+    let mut version = data.clone();
+    version[0] += 1;
+    assert!(account(7).decrypt_memo(&version, &hasher).is_err());
+    let mut ciphertext = data.clone();
+    *ciphertext.last_mut().unwrap() ^= 1;
+    assert!(account(7).decrypt_memo(&ciphertext, &hasher).is_err());
+    assert!(account(7).decrypt_memo(&data[..25], &hasher).is_err());
+    assert!(account(7).decrypt_memo(&[], &hasher).is_err());
+}
+
+#[test]
+fn scan_keeps_the_latest_note_matching_its_leaf() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let owner = account(7);
+    let (first, second) = (memo(5, 1), memo(3, 2));
+    let first_data = owner.encrypt_memo(&first, &hasher).unwrap();
+    let second_data = owner.encrypt_memo(&second, &hasher).unwrap();
+    let foreign = account(8).encrypt_memo(&memo(9, 1), &hasher).unwrap();
+
+    let mut scanner = MemoScanner::new(owner.to_watch_only(), hasher.clone());
+    assert!(scanner
+        .scan(2, &leaf(&owner, &first, &hasher), &first_data)
+        .is_some());
+    assert!(scanner
+        .scan(3, &leaf(&account(8), &memo(9, 1), &hasher), &foreign)
+        .is_none());
+    assert!(scanner.scan(4, &Fr::from(1u64), &[]).is_none());
+    // a memo moved to another leaf, e.g. by the sender, is not recovered
+    assert!(scanner
+        .scan(5, &leaf(&owner, &second, &hasher), &first_data)
+        .is_none());
+
+    let note = scanner
+        .scan(6, &leaf(&owner, &second, &hasher), &second_data)
+        .unwrap();
+    assert_eq!((note.index, note.memo), (6, second));
+    // notes scanned out of order do not replace a later one
+    scanner.scan(2, &leaf(&owner, &first, &hasher), &first_data);
+    assert_eq!(scanner.latest(), Some(&note));
+
+    let mut restored = owner.clone();
+    restored.restore(&note);
+    assert_eq!(restored.balance(), second.balance);
+    assert_eq!(restored.index(), Some(6));
+    assert_eq!(restored.leaf(&hasher), note.leaf);
+}
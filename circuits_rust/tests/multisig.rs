@@ -0,0 +1,151 @@
+//! Multisig notes: threshold signatures over the update intent, and the note
+//! type bound into their leaves.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, Field};
+use circuits_rust::{
+    circuit::{MULTISIG_NOTE, SINGLE_NOTE},
+    multisig::{intent_hash, MultisigPolicy, Signature, SignerKey},
+    poseidon::{poseidon_bn254, PoseidonHash},
+    wasm::{Account, NoteUpdate, State},
+    Circuit, Multisig, SIGNERS,
+};
+use common::is_satisfied;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// 2 of 3 multisig note holding 100 of the first asset at leaf 1.
+struct Fixture {
+    hasher: PoseidonHash,
+    keys: Vec<SignerKey>,
+    policy: MultisigPolicy,
+    state: State,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let hasher = PoseidonHash::new(poseidon_bn254());
+        let mut rng = StdRng::seed_from_u64(0);
+        let keys: Vec<_> = (0..SIGNERS)
+            .map(|_| SignerKey::generate(&mut rng))
+            .collect();
+        let policy =
+            MultisigPolicy::new(2, keys.iter().map(SignerKey::public_key).collect()).unwrap();
+
+        let account = Account::multisig(&policy, Fr::from(42u64));
+        let state = common::funded(account, [100, 0, 0, 0, 0], 1);
+
+        Self {
+            hasher,
+            keys,
+            policy,
+            state,
+        }
+    }
+
+    /// Private transfer out of the note, with no public address.
+    fn update(&self) -> Circuit {
+        NoteUpdate::new(&self.state, [-30, 0, 0, 0, 0], Fr::ZERO, Fr::ZERO, None)
+            .unwrap()
+            .circuit
+    }
+
+    fn intent(circuit: &Circuit) -> Fr {
+        intent_hash(
+            &circuit.nullifier,
+            &circuit.diff_hash,
+            &circuit.public_address,
+            &circuit.aux,
+            &circuit.hasher,
+        )
+    }
+
+    /// Signatures of the signers at `signed` over `intent`.
+    fn sign(&self, intent: &Fr, signed: &[usize]) -> [Option<Signature>; SIGNERS] {
+        std::array::from_fn(|i| {
+            signed
+                .contains(&i)
+                .then(|| self.keys[i].sign(intent, &self.hasher))
+        })
+    }
+
+    fn multisig(&self, signatures: [Option<Signature>; SIGNERS]) -> Multisig {
+        Multisig::new(self.update(), &self.policy, signatures).unwrap()
+    }
+}
+
+#[test]
+fn threshold_of_signatures_is_accepted() {
+    let fixture = Fixture::new();
+    let intent = Fixture::intent(&fixture.update());
+    assert!(is_satisfied(
+        fixture.multisig(fixture.sign(&intent, &[0, 2]))
+    ));
+    assert!(is_satisfied(
+        fixture.multisig(fixture.sign(&intent, &[0, 1, 2]))
+    ));
+}
+
+#[test]
+fn below_threshold_is_rejected() {
+    let fixture = Fixture::new();
+    let intent = Fixture::intent(&fixture.update());
+    assert!(!is_satisfied(fixture.multisig(fixture.sign(&intent, &[1]))));
+    assert!(!is_satisfied(fixture.multisig(fixture.sign(&intent, &[]))));
+}
+
+#[test]
+fn duplicate_signers_are_rejected() {
+    let fixture = Fixture::new();
+    let intent = Fixture::intent(&fixture.update());
+    // one signer's signature in two slots
+    let mut signatures = fixture.sign(&intent, &[0]);
+    signatures[1] = signatures[0];
+    assert!(!is_satisfied(fixture.multisig(signatures)));
+
+    // nor can a policy list a signer twice to meet its threshold alone
+    let key = fixture.keys[0].public_key();
+    assert!(MultisigPolicy::new(2, vec![key, key, fixture.keys[1].public_key()]).is_err());
+}
+
+#[test]
+fn signatures_over_another_intent_are_rejected() {
+    let fixture = Fixture::new();
+    let circuit = fixture.update();
+    // the same update bound to a public address
+    let other = Fixture::intent(&Circuit {
+        public_address: Fr::ONE,
+        ..circuit.clone()
+    });
+    assert!(!is_satisfied(
+        fixture.multisig(fixture.sign(&other, &[0, 1]))
+    ));
+
+    // or a valid signature next to one over another intent
+    let mut signatures = fixture.sign(&Fixture::intent(&circuit), &[0]);
+    signatures[1] = Some(fixture.keys[1].sign(&other, &fixture.hasher));
+    assert!(!is_satisfied(fixture.multisig(signatures)));
+}
+
+#[test]
+fn multisig_notes_cannot_be_spent_by_the_main_circuit() {
+    let fixture = Fixture::new();
+    let account = fixture.state.account();
+    assert_eq!(account.note_type(), MULTISIG_NOTE);
+    assert_eq!(account.address_fr(), fixture.policy.owner(&fixture.hasher));
+
+    // the holder of the spending key alone proves a swap out of the note
+    let circuit = fixture.update();
+    assert_eq!(circuit.public_address, Fr::ZERO);
+    assert!(!is_satisfied(circuit));
+
+    // a single owner account of the same owner and key commits to other leaves
+    let single = Account::new(account.address_fr(), Fr::from(42u64));
+    assert_eq!(single.note_type(), SINGLE_NOTE);
+    assert_eq!(single.viewing_key(), account.viewing_key());
+    assert_ne!(
+        single.prehash(&fixture.hasher),
+        account.prehash(&fixture.hasher)
+    );
+}
@@ -0,0 +1,118 @@
+//! Groth16 setup, proving and verification of `MainCircuit` over both curves.
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_crypto_primitives::snark::SNARK;
+use ark_ff::{AdditiveGroup, Field};
+use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
+use circuits_rust::{
+    circuit::SINGLE_NOTE,
+    curve::Curve,
+    hash::Domain,
+    merkle_tree::SparseMerkleTree,
+    poseidon::{PoseidonHash, DIFF_HASH_DOMAIN},
+    prover, Circuit, ASSET_SIZE, LEVEL,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// First deposit of 100 of the first asset into a fresh account, built from
+/// the hash definitions so that it works over the scalar field of any curve.
+fn first_deposit<E: Curve>() -> Circuit<E> {
+    let hasher = PoseidonHash::new(E::poseidon());
+    let h = |domain, l: &E::ScalarField, r: &E::ScalarField| hasher.hash_in(domain, l, r);
+    let fold = |domain, init, values: &[E::ScalarField]| {
+        values.iter().fold(init, |acc, v| h(domain, &acc, v))
+    };
+
+    let spending_key = E::ScalarField::from(7u64);
+    let address = E::ScalarField::from(0xa11ceu64);
+    let viewing_key = h(Domain::ViewingKey, &spending_key, &E::ScalarField::ZERO);
+    let prehash = h(
+        Domain::Leaf,
+        &h(Domain::Leaf, &address, &viewing_key),
+        &E::ScalarField::from(SINGLE_NOTE),
+    );
+    let leaf = |balance: &[E::ScalarField], counter, history| {
+        let balance_hash = fold(Domain::Leaf, prehash, balance);
+        h(
+            Domain::Leaf,
+            &h(Domain::Leaf, &balance_hash, &counter),
+            &history,
+        )
+    };
+
+    let before = [E::ScalarField::ZERO; ASSET_SIZE];
+    let mut diff = [E::ScalarField::ZERO; ASSET_SIZE];
+    diff[0] = E::ScalarField::from(100u64);
+    let diff_hash = fold(Domain::Node, E::ScalarField::from(DIFF_HASH_DOMAIN), &diff);
+    let after_history = h(
+        Domain::History,
+        &h(Domain::History, &E::ScalarField::ZERO, &diff_hash),
+        &E::ScalarField::ONE,
+    );
+    let empty_leaf = leaf(&before, E::ScalarField::ZERO, E::ScalarField::ZERO);
+
+    // a tree with a leaf of another account, the empty leaf is spent
+    // without a membership proof
+    let tree = SparseMerkleTree::<LEVEL, E::ScalarField>::new_sequential(
+        &[E::ScalarField::ONE],
+        &hasher,
+        &E::ScalarField::ZERO,
+    )
+    .unwrap();
+
+    Circuit::<E> {
+        spending_key,
+        before,
+        diff,
+        after: diff,
+        history: E::ScalarField::ZERO,
+        counter: E::ScalarField::ZERO,
+        merkle_root: tree.root(),
+        merkle_path: tree.generate_membership_proof(0),
+        diff_hash,
+        nullifier: h(Domain::Nullifier, &empty_leaf, &viewing_key),
+        after_leaf: leaf(&diff, E::ScalarField::ONE, after_history),
+        address,
+        public_address: address,
+        aux: E::ScalarField::ZERO,
+        ..Circuit::<E>::empty(hasher.clone())
+    }
+}
+
+fn prove_and_verify<E: Curve>() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let circuit = first_deposit::<E>();
+    let (pk, vk) = prover::setup::<E, _>(
+        Circuit::<E>::empty(PoseidonHash::new(E::poseidon())),
+        &mut rng,
+    )
+    .unwrap();
+    let proof = prover::prove::<E, _>(&pk, circuit.clone(), &mut rng).unwrap();
+
+    let vk = VerifyingKey::<E>::deserialize_compressed(&vk[..]).unwrap();
+    let proof = Proof::<E>::deserialize_compressed(&proof[..]).unwrap();
+    let inputs = circuit.public_inputs();
+    assert!(Groth16::<E>::verify(&vk, &inputs, &proof).unwrap());
+
+    // another after leaf
+    let mut forged = inputs.clone();
+    forged[3] += E::ScalarField::ONE;
+    assert!(!Groth16::<E>::verify(&vk, &forged, &proof).unwrap());
+
+    // nor can an unsatisfied witness be proven
+    let mut wrong = circuit;
+    wrong.after[0] += E::ScalarField::ONE;
+    assert!(prover::prove::<E, _>(&pk, wrong, &mut rng).is_err());
+}
+
+#[test]
+fn bn254_proofs_verify() {
+    prove_and_verify::<Bn254>();
+}
+
+#[test]
+fn bls12_381_proofs_verify() {
+    prove_and_verify::<Bls12_381>();
+}
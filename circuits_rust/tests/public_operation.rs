@@ -0,0 +1,95 @@
+//! Multi-asset public operations: one note update depositing and withdrawing
+//! several coin types, hashing to the diff `core::finish_public_operation`
+//! computes.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use circuits_rust::{
+    poseidon::{poseidon_bn254, PoseidonHash},
+    registry::AssetRegistry,
+    utils::fr_to_hex,
+    wasm::{NoteUpdate, State},
+    ASSET_SIZE,
+};
+use common::{account, is_satisfied, COUNTER};
+
+const USDC: &str = "0x1::usdc::USDC";
+const USDT: &str = "0x1::usdt::USDT";
+const HASUI: &str = "0x1::hasui::HASUI";
+const SUINS: &str = "0x1::suins::SUINS";
+const WAL: &str = "0x1::wal::WAL";
+
+fn registry() -> AssetRegistry {
+    common::registry(&[USDC, USDT, HASUI, SUINS, WAL].map(|c| (c, 6)))
+}
+
+/// State of `account` with its note at index 1 of a three leaf tree.
+fn funded(balance: [u64; ASSET_SIZE]) -> State {
+    common::funded(account(), balance, 1)
+}
+
+#[test]
+fn deposit_and_withdraw_several_assets() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let registry = registry();
+    let state = funded([100, 5, 0, 0, 9]);
+    let coin_diff = registry
+        .operation_diff(&[(USDT, 20), (USDC, -100), (HASUI, 3), (WAL, -4)])
+        .unwrap();
+    assert_eq!(coin_diff.diffs().unwrap(), [-100, 20, 3, 0, -4]);
+
+    let update = NoteUpdate::public_operation(&state, &coin_diff, None).unwrap();
+    assert!(is_satisfied(update.circuit.clone()));
+
+    let circuit = &update.circuit;
+    let account = state.account();
+    assert_eq!(circuit.public_address, account.address_fr());
+    assert_eq!(circuit.aux, Fr::ZERO);
+    assert_eq!(circuit.diff_hash, coin_diff.final_repr(&hasher));
+    assert_eq!(
+        circuit.after_leaf,
+        account.leaf_of(
+            &[0, 25, 3, 0, 5],
+            COUNTER + 1,
+            &update.after_history,
+            &hasher
+        )
+    );
+}
+
+#[test]
+fn first_note_credits_several_assets() {
+    let registry = registry();
+    let mut state = State::wasm_new(account());
+    state
+        .wasm_set_leafs(vec![fr_to_hex(&Fr::from(1u64))])
+        .unwrap();
+    let coin_diff = registry
+        .operation_diff(&[(SUINS, 1_000_000), (USDC, 2), (SUINS, 1)])
+        .unwrap();
+
+    let update = NoteUpdate::public_operation(&state, &coin_diff, None).unwrap();
+    assert!(is_satisfied(update.circuit.clone()));
+    assert_eq!(
+        update.circuit.nullifier,
+        state.account().nullifier(&update.circuit.hasher)
+    );
+    assert_eq!(update.after_counter, 1);
+}
+
+#[test]
+fn overdrawn_operations_are_rejected() {
+    let registry = registry();
+    let state = funded([100, 0, 0, 0, 0]);
+    let coin_diff = registry
+        .operation_diff(&[(USDC, -60), (USDC, -41)])
+        .unwrap();
+    assert!(NoteUpdate::public_operation(&state, &coin_diff, None).is_err());
+
+    let max = i128::from(u64::MAX);
+    assert!(registry.operation_diff(&[(USDC, max + 1)]).is_err());
+    assert!(registry.operation_diff(&[(USDC, max), (USDC, 1)]).is_err());
+    assert!(registry.operation_diff(&[("0x1::sui::SUI", 1)]).is_err());
+}
@@ -0,0 +1,150 @@
+//! `AssetRegistry` slots, amounts and diffs for a pool descriptor.
+
+mod common;
+
+use ark_bn254::Fr;
+use circuits_rust::{
+    poseidon::{poseidon_bn254, PoseidonHash},
+    registry::AssetRegistry,
+    wasm::{diff_hash, Account},
+};
+use common::PACKAGE;
+
+fn registry() -> AssetRegistry {
+    AssetRegistry::from_json(&format!(
+        r#"{{
+            "allowed_token_length": 5,
+            "allowed_tokens": [
+                {{ "coin_type": "{PACKAGE}::usdc::USDC", "symbol": "USDC", "decimals": 6 }},
+                {{ "coin_type": "0x2::sui::SUI", "symbol": "SUI", "decimals": 9 }},
+                {{ "coin_type": "{PACKAGE}::wal::WAL", "decimals": 0 }}
+            ]
+        }}"#
+    ))
+    .unwrap()
+}
+
+fn usdc() -> String {
+    format!("{PACKAGE}::usdc::USDC")
+}
+
+#[test]
+fn slots_follow_allowed_tokens() {
+    let registry = registry();
+    assert_eq!(registry.slot(&usdc()).unwrap(), 0);
+    assert_eq!(registry.slot("0x0002::sui::SUI").unwrap(), 1);
+    assert_eq!(
+        registry
+            .asset(&format!("{PACKAGE}::wal::WAL"))
+            .unwrap()
+            .symbol,
+        ""
+    );
+    assert!(registry.slot("0x2::coin::COIN").is_err());
+}
+
+#[test]
+fn amounts_round_trip() {
+    let registry = registry();
+    let sui = "0x2::sui::SUI";
+    assert_eq!(registry.parse_amount(sui, "1.5").unwrap(), 1_500_000_000);
+    assert_eq!(registry.parse_amount(sui, ".000000001").unwrap(), 1);
+    assert_eq!(registry.parse_amount(&usdc(), "42").unwrap(), 42_000_000);
+    assert_eq!(registry.parse_signed(&usdc(), "-0.25").unwrap(), -250_000);
+    assert_eq!(registry.parse_signed(&usdc(), "-0").unwrap(), 0);
+
+    assert_eq!(registry.format_amount(sui, 1_500_000_000).unwrap(), "1.5");
+    assert_eq!(registry.format_amount(&usdc(), 42_000_000).unwrap(), "42");
+    assert_eq!(registry.format_amount(&usdc(), 1).unwrap(), "0.000001");
+    assert_eq!(
+        registry.format_amount(sui, u64::MAX).unwrap(),
+        "18446744073.709551615"
+    );
+    assert_eq!(
+        registry.parse_amount(sui, "18446744073.709551615").unwrap(),
+        u64::MAX
+    );
+
+    for invalid in [
+        "",
+        ".",
+        "1.2.3",
+        "1e6",
+        "0.0000001",
+        "18446744073709.551616",
+    ] {
+        assert!(
+            registry.parse_amount(&usdc(), invalid).is_err(),
+            "{invalid} parsed"
+        );
+    }
+    assert!(registry.parse_amount(&usdc(), "-1").is_err());
+    assert_eq!(
+        registry
+            .parse_signed(sui, "-18446744073.709551615")
+            .unwrap(),
+        -i128::from(u64::MAX)
+    );
+    assert!(registry
+        .parse_signed(sui, "-18446744073.709551616")
+        .is_err());
+}
+
+#[test]
+fn diffs_by_coin_type() {
+    let registry = registry();
+    let sui = "0x2::sui::SUI";
+    let diff = registry
+        .diff(&[
+            (sui, -500),
+            (&format!("{PACKAGE}::wal::WAL"), 250),
+            (sui, 100),
+        ])
+        .unwrap();
+    assert_eq!(diff, [0, -400, 250, 0, 0]);
+    let max = i128::from(u64::MAX);
+    assert_eq!(registry.diff(&[(sui, max)]).unwrap(), [0, max, 0, 0, 0]);
+    assert!(registry.diff(&[(sui, max), (sui, 1)]).is_err());
+
+    let mut coin_diff = registry.coin_diff();
+    coin_diff.sub_coin(sui, 400).unwrap();
+    coin_diff
+        .add_coin(&format!("{PACKAGE}::wal::WAL"), 250)
+        .unwrap();
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    assert_eq!(
+        coin_diff.final_repr(&hasher),
+        diff_hash(&diff.map(Fr::from), &hasher)
+    );
+}
+
+#[test]
+fn balances_by_coin_type() {
+    let registry = registry();
+    let mut account = Account::new(Fr::from(1u64), Fr::from(2u64));
+    account
+        .set_balance_of(&registry, "0x2::sui::SUI", 7)
+        .unwrap();
+    assert_eq!(account.balance(), [0, 7, 0, 0, 0]);
+    assert_eq!(account.balance_of(&registry, "0x02::sui::SUI").unwrap(), 7);
+    assert!(account.balance_of(&registry, "0x2::coin::COIN").is_err());
+}
+
+#[test]
+fn invalid_descriptors() {
+    let descriptor = |length: usize, tokens: &str| {
+        AssetRegistry::from_json(&format!(
+            r#"{{ "allowed_token_length": {length}, "allowed_tokens": [{tokens}] }}"#
+        ))
+    };
+    let sui = r#"{ "coin_type": "0x2::sui::SUI", "decimals": 9 }"#;
+    assert!(descriptor(5, sui).is_ok());
+    assert!(descriptor(4, sui).is_err());
+    assert!(descriptor(5, &[sui; 6].join(",")).is_err());
+    assert!(descriptor(
+        5,
+        &format!(r#"{sui}, {{ "coin_type": "0x02::sui::SUI", "decimals": 9 }}"#)
+    )
+    .is_err());
+    assert!(descriptor(5, r#"{ "coin_type": "0x2::sui::SUI", "decimals": 20 }"#).is_err());
+}
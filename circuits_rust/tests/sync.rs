@@ -0,0 +1,235 @@
+//! Decoding of `core` events and incremental rebuilding of the tree from
+//! pages of them.
+
+mod common;
+
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use circuits_rust::{
+    merkle_tree::SparseMerkleTree,
+    poseidon::{poseidon_bn254, PoseidonHash},
+    sync::{EventPage, LeafInserted, RawEvent, ShroudEvent, Synchronizer, Withdrawn},
+    wasm::{Account, State},
+    LEVEL,
+};
+use common::PACKAGE;
+use serde_json::{json, Value};
+
+fn raw(seq: u64, name: &str, parsed_json: Value) -> Value {
+    json!({
+        "id": { "txDigest": format!("digest{seq}"), "eventSeq": seq.to_string() },
+        "type": format!("{PACKAGE}::core::{name}"),
+        "parsedJson": parsed_json,
+    })
+}
+
+fn decode(event: Value) -> anyhow::Result<Option<ShroudEvent>> {
+    ShroudEvent::decode(PACKAGE, &serde_json::from_value::<RawEvent>(event)?)
+}
+
+/// `LeafInserted` events of `leafs` appended to `before`, with the roots
+/// `merkle::insert` computes.
+fn inserted(before: &[Fr], leafs: &[Fr]) -> Vec<Value> {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let mut tree = before.to_vec();
+    leafs
+        .iter()
+        .map(|leaf| {
+            tree.push(*leaf);
+            let root = SparseMerkleTree::<LEVEL>::new_sequential(&tree, &hasher, &Fr::ZERO)
+                .unwrap()
+                .root();
+            raw(
+                tree.len() as u64,
+                "LeafInserted",
+                json!({
+                    "index": (tree.len() - 1).to_string(),
+                    "value": leaf.to_string(),
+                    "new_root": root.to_string(),
+                    "memo": [],
+                }),
+            )
+        })
+        .collect()
+}
+
+fn page(data: Vec<Value>, next: Option<u64>) -> EventPage {
+    serde_json::from_value(json!({
+        "data": data,
+        "nextCursor": next.map(|seq| json!({ "txDigest": format!("digest{seq}"), "eventSeq": seq.to_string() })),
+        "hasNextPage": false,
+    }))
+    .unwrap()
+}
+
+fn state() -> State {
+    State::wasm_new(Account::new(Fr::from(0xb0bu64), Fr::from(7u64)))
+}
+
+#[test]
+fn decodes_core_events() {
+    assert_eq!(
+        decode(raw(
+            0,
+            "LeafInserted",
+            json!({ "index": "3", "value": "5", "new_root": "0", "memo": [1, 255] }),
+        ))
+        .unwrap(),
+        Some(ShroudEvent::LeafInserted(LeafInserted {
+            index: 3,
+            value: Fr::from(5u64),
+            new_root: Fr::from(0u64),
+            memo: vec![1, 255],
+        }))
+    );
+
+    // Sui pads addresses in type names, callers may not
+    let withdrawn = json!({
+        "id": { "txDigest": "digest1", "eventSeq": "1" },
+        "type": format!("0x{:0>64}::core::Withdrawn<0x2::sui::SUI>", "a"),
+        "parsedJson": { "account": "0xb0b", "amount": "18446744073709551615" },
+    });
+    assert_eq!(
+        ShroudEvent::decode("0xA", &serde_json::from_value(withdrawn).unwrap()).unwrap(),
+        Some(ShroudEvent::Withdrawn(Withdrawn {
+            coin_type: "0x2::sui::SUI".to_string(),
+            account: "0xb0b".to_string(),
+            amount: u64::MAX,
+        }))
+    );
+}
+
+#[test]
+fn skips_foreign_events_and_rejects_malformed_ones() {
+    let mut foreign = raw(0, "NullifierUsed", json!({ "nullifier": "1" }));
+    foreign["type"] = json!("0x2::core::NullifierUsed");
+    assert_eq!(decode(foreign).unwrap(), None);
+    assert_eq!(decode(raw(0, "Unknown", json!({}))).unwrap(), None);
+    // generic events without their type arguments are not ours either
+    assert_eq!(
+        decode(raw(
+            0,
+            "Deposited",
+            json!({ "account": "0x1", "amount": "1" })
+        ))
+        .unwrap(),
+        None
+    );
+
+    let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+    assert!(decode(raw(0, "NullifierUsed", json!({ "nullifier": modulus }))).is_err());
+    assert!(decode(raw(0, "NullifierUsed", json!({ "nullifier": "0x1" }))).is_err());
+    assert!(decode(raw(
+        0,
+        "LeafInserted",
+        json!({ "index": "-1", "value": "1", "new_root": "1", "memo": [] }),
+    ))
+    .is_err());
+    assert!(decode(raw(
+        0,
+        "Escrowed",
+        json!({ "index": "0", "ciphertext": [256] })
+    ))
+    .is_err());
+}
+
+#[test]
+fn applies_pages_incrementally() {
+    let leafs = [1u64, 2, 3].map(Fr::from);
+    let mut state = state();
+    let mut sync = Synchronizer::new(PACKAGE, &state).unwrap();
+
+    let report = sync
+        .apply_page(&mut state, &page(inserted(&[], &leafs[..2]), Some(2)))
+        .unwrap();
+    assert_eq!(report.leafs_added, 2);
+    assert_eq!(state.leafs(), &leafs[..2]);
+    assert_eq!(state.cursor().unwrap().event_seq, "2");
+    assert_eq!(state.account().synced_leafs(), 2);
+
+    // a page applied again only checks the leaves it already has
+    let events = [
+        inserted(&[], &leafs[..2]),
+        inserted(&leafs[..2], &leafs[2..]),
+    ]
+    .concat();
+    let report = sync.apply_page(&mut state, &page(events, None)).unwrap();
+    assert_eq!(report.leafs_added, 1);
+    assert_eq!(state.leafs(), &leafs);
+    // without a next cursor, the last event is the cursor
+    assert_eq!(state.cursor().unwrap().event_seq, "3");
+
+    // a synchronizer built from the synced state continues from it
+    let mut resumed = Synchronizer::new(PACKAGE, &state).unwrap();
+    let report = resumed
+        .apply_page(&mut state, &page(inserted(&leafs, &[Fr::from(4u64)]), None))
+        .unwrap();
+    assert_eq!(report.leafs_added, 1);
+}
+
+#[test]
+fn finds_the_account_note_and_its_spend() {
+    let hasher = PoseidonHash::new(poseidon_bn254());
+    let mut state = state();
+    let own = state.account().leaf(&hasher);
+    let nullifier = state.account().nullifier_of(&own, &hasher);
+    let mut sync = Synchronizer::new(PACKAGE, &state).unwrap();
+
+    let events = [
+        inserted(&[], &[Fr::from(1u64), own]),
+        vec![raw(
+            9,
+            "NullifierUsed",
+            json!({ "nullifier": nullifier.to_string() }),
+        )],
+    ]
+    .concat();
+    let report = sync.apply_page(&mut state, &page(events, None)).unwrap();
+    assert_eq!(report.own_index, Some(1));
+    assert_eq!(state.account().index(), Some(1));
+    assert!(report.own_nullifier_spent);
+    assert_eq!(report.events.len(), 3);
+
+    // the spend stays reported on the pages after it
+    let report = sync
+        .apply_page(
+            &mut state,
+            &page(inserted(&[Fr::from(1u64), own], &[Fr::from(2u64)]), None),
+        )
+        .unwrap();
+    assert_eq!(report.leafs_added, 1);
+    assert!(report.own_nullifier_spent);
+}
+
+#[test]
+fn rejects_wrong_roots_and_gaps_without_advancing() {
+    let leafs = [1u64, 2].map(Fr::from);
+    let mut state = state();
+    let mut sync = Synchronizer::new(PACKAGE, &state).unwrap();
+
+    let mut events = inserted(&[], &leafs);
+    events[1]["parsedJson"]["new_root"] = json!("1");
+    let err = sync
+        .apply_page(&mut state, &page(events, Some(2)))
+        .unwrap_err();
+    assert!(
+        err.to_string().starts_with("Root mismatch at leaf 1"),
+        "{err}"
+    );
+    // the verified leaf is kept, the cursor is not advanced
+    assert_eq!(state.leafs(), &leafs[..1]);
+    assert_eq!(state.cursor(), None);
+
+    // the page can be applied again once corrected
+    sync.apply_page(&mut state, &page(inserted(&[], &leafs), Some(2)))
+        .unwrap();
+    assert_eq!(state.leafs(), &leafs);
+
+    let gap = inserted(&[leafs[0], leafs[1], Fr::from(3u64)], &[Fr::from(4u64)]);
+    assert!(sync.apply_page(&mut state, &page(gap, None)).is_err());
+
+    let mut conflict = inserted(&[], &leafs);
+    conflict[0]["parsedJson"]["value"] = json!("7");
+    assert!(sync.apply_page(&mut state, &page(conflict, None)).is_err());
+    assert_eq!(state.leafs(), &leafs);
+}
@@ -5,6 +5,9 @@ use std::type_name::TypeName;
 use sui::poseidon::poseidon_bn254;
 use sui::vec_map::{Self, VecMap};
 
+// "diff", first input of the diff hash chain, separating it from tree nodes
+const DIFF_HASH_DOMAIN: u256 = 0x64696666;
+
 public struct CoinDiff has copy, drop {
     total: u64,
     map: VecMap<TypeName, FR>,
@@ -33,8 +36,8 @@ public fun final_repr(coin_diff: &CoinDiff): u256 {
     while (values.length() < coin_diff.total) {
         values.push_back(fr::zero());
     };
-    // coin_diff_hash = H(H(0, coin_diff[0]), coin_diff[1]), ...
-    values.fold!(0, |acc, v| {
+    // coin_diff_hash = H(H("diff", coin_diff[0]), coin_diff[1]), ...
+    values.fold!(DIFF_HASH_DOMAIN, |acc, v| {
         let mut to_hash = vector::empty();
         to_hash.push_back(acc);
         to_hash.push_back(v.repr());
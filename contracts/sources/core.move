@@ -1,7 +1,7 @@
 /// Module: shroud
 module shroud::core;
 
-use shroud::coin_diff;
+use shroud::coin_diff::{Self, CoinDiff};
 use shroud::fr;
 use shroud::merkle::{Self, MerkleTree};
 use std::type_name::{TypeName, get};
@@ -13,6 +13,11 @@ use sui::groth16;
 use sui::object_bag::{Self, ObjectBag};
 use sui::table::{Self, Table};
 
+#[test_only]
+use sui::test_scenario::{Scenario, return_shared};
+#[test_only]
+use std::unit_test::{assert_eq, destroy};
+
 const ETREE_NOT_EMPTY: u64 = 0x1;
 const ETOKEN_NOT_ALLOWED: u64 = 0x2;
 const EOLD_NULLIFIER_EXISTS: u64 = 0x3;
@@ -20,6 +25,13 @@ const EINVALID_ROOT: u64 = 0x4;
 const EINSUFFICIENT_RECEIVED: u64 = 0x5;
 const ETOKEN_ALREADY_ALLOWED: u64 = 0x6;
 const EINVALID_PROOF: u64 = 0x7;
+const EWRONG_SHROUD: u64 = 0x8;
+const EUNKNOWN_NOTE_TYPE: u64 = 0x9;
+
+// verifying key slots in `Shroud.keys`, indexed by the note type every leaf
+// commits to, so a note is only spent through the circuit of its type
+const SINGLE_KEY: u64 = 0;
+const MULTISIG_KEY: u64 = 1;
 
 public struct Shroud has key, store {
     id: UID,
@@ -56,31 +68,42 @@ public struct NullifierUsed has copy, drop {
     nullifier: u256,
 }
 
+// auditor ciphertext of (address, diff) for leaf `index`, only on deployments
+// whose circuit was set up with an auditor key
+public struct Escrowed has copy, drop {
+    index: u64,
+    ciphertext: vector<u8>,
+}
+
 public struct LeafInserted has copy, drop {
     index: u64,
     value: u256,
     new_root: u256,
+    // encrypted note (balances, index hint), only readable by the owner. not
+    // bound by the proof, the sender can replace it: a recovery hint only
+    memo: vector<u8>,
 }
 
 // --- Helper Functions ---
 
-fun get_vk(shroud: &Shroud): groth16::PreparedVerifyingKey {
-    let vk_bytes: &vector<u8> = shroud.keys.borrow(0);
+fun get_vk(shroud: &Shroud, key: u64): groth16::PreparedVerifyingKey {
+    let vk_bytes: &vector<u8> = shroud.keys.borrow(key);
     let pvk = groth16::prepare_verifying_key(&groth16::bn254(), vk_bytes);
     pvk
 }
 
-fun set_vk(shroud: &mut Shroud, vk_bytes: vector<u8>) {
-    if (shroud.keys.length() == 0) {
-        shroud.keys.add(0, vk_bytes);
+fun set_vk(shroud: &mut Shroud, key: u64, vk_bytes: vector<u8>) {
+    if (!shroud.keys.contains(key)) {
+        shroud.keys.add(key, vk_bytes);
     } else {
-        let bytes: &mut vector<u8> = shroud.keys.borrow_mut(0);
+        let bytes: &mut vector<u8> = shroud.keys.borrow_mut(key);
         *bytes = vk_bytes;
     }
 }
 
 fun verify_proof(
     shroud: &Shroud,
+    note_type: u64,
     current_root: u256,
     diff_hash: u256,
     old_leaf_nullifier: u256,
@@ -88,9 +111,42 @@ fun verify_proof(
     proof: vector<u8>,
     address: u256,
     aux: u256,
+    escrow: vector<u8>,
 ) {
-    let vk = get_vk(shroud);
     let proof_points = groth16::proof_points_from_bytes(proof);
+    let public_inputs = groth16::public_proof_inputs_from_bytes(
+        public_inputs_bytes(
+            current_root,
+            diff_hash,
+            old_leaf_nullifier,
+            new_leaf,
+            address,
+            aux,
+            escrow,
+        ),
+    );
+    // both circuits share the public inputs, the note type selects the key
+    assert!(shroud.keys.contains(note_type), EUNKNOWN_NOTE_TYPE);
+    let is_valid = groth16::verify_groth16_proof(
+        &groth16::bn254(),
+        &get_vk(shroud, note_type),
+        &public_inputs,
+        &proof_points,
+    );
+    assert!(is_valid, EINVALID_PROOF);
+}
+
+// Public inputs in the order the circuits allocate them, checked against
+// `conformance.json` by tests/conformance_test.move
+public(package) fun public_inputs_bytes(
+    current_root: u256,
+    diff_hash: u256,
+    old_leaf_nullifier: u256,
+    new_leaf: u256,
+    address: u256,
+    aux: u256,
+    escrow: vector<u8>,
+): vector<u8> {
     let mut public_inputs_bytes: vector<u8> = vector::empty();
     // 1: merkle root
     public_inputs_bytes.append(to_bytes(&current_root));
@@ -104,22 +160,23 @@ fun verify_proof(
     public_inputs_bytes.append(to_bytes(&address));
     // 6: aux
     public_inputs_bytes.append(to_bytes(&aux));
-    let public_inputs = groth16::public_proof_inputs_from_bytes(public_inputs_bytes);
-    let is_valid = groth16::verify_groth16_proof(
-        &groth16::bn254(),
-        &vk,
-        &public_inputs,
-        &proof_points,
-    );
-    assert!(is_valid, EINVALID_PROOF);
+    // 7..: escrow ciphertext, already encoded as 32-byte public inputs
+    public_inputs_bytes.append(escrow);
+    public_inputs_bytes
 }
 
-fun init(ctx: &mut TxContext) {
+fun emit_escrow(index: u64, ciphertext: vector<u8>) {
+    if (!ciphertext.is_empty()) {
+        emit(Escrowed { index, ciphertext });
+    }
+}
+
+fun new(ctx: &mut TxContext): Shroud {
     let level = 20;
     let valid_size = 20;
     let default_leaf = 0;
     let allowed_token_length = 5;
-    let shroud = Shroud {
+    Shroud {
         id: object::new(ctx),
         tree: merkle::new(level, valid_size, default_leaf, ctx),
         allowed_token_length: allowed_token_length,
@@ -127,15 +184,26 @@ fun init(ctx: &mut TxContext) {
         balances: object_bag::new(ctx),
         allowed_tokens: vector::empty(),
         keys: bag::new(ctx),
-    };
-    transfer::share_object(shroud);
+    }
+}
+
+fun init(ctx: &mut TxContext) {
+    transfer::share_object(new(ctx));
     transfer::transfer(ShroudAdmin { id: object::new(ctx) }, ctx.sender());
 }
 
 // --- FUNCTIONS ---
 
 public fun initialize_prover(_: &mut ShroudAdmin, shroud: &mut Shroud, vk_bytes: vector<u8>) {
-    set_vk(shroud, vk_bytes);
+    set_vk(shroud, SINGLE_KEY, vk_bytes);
+}
+
+public fun initialize_multisig_prover(
+    _: &mut ShroudAdmin,
+    shroud: &mut Shroud,
+    vk_bytes: vector<u8>,
+) {
+    set_vk(shroud, MULTISIG_KEY, vk_bytes);
 }
 
 public fun allow_token<T>(_: &mut ShroudAdmin, shroud: &mut Shroud, ctx: &mut TxContext) {
@@ -153,7 +221,10 @@ public fun deposit<T>(
     current_root: u256,
     old_leaf_nullifier: u256,
     new_leaf: u256,
+    note_type: u64,
     proof: vector<u8>,
+    memo: vector<u8>,
+    escrow: vector<u8>,
     ctx: &mut TxContext,
 ) {
     let tn = get<T>();
@@ -175,6 +246,7 @@ public fun deposit<T>(
     // public address
     verify_proof(
         shroud,
+        note_type,
         current_root,
         diff_hash,
         old_leaf_nullifier,
@@ -182,20 +254,20 @@ public fun deposit<T>(
         proof,
         fr::from_address_bytes(ctx.sender()).repr(),
         0,
+        escrow,
     );
 
     // check if root valid
     assert!(shroud.tree.is_valid_root(current_root), EINVALID_ROOT);
 
-    // check if old nullifier exists
-    if (old_leaf_nullifier != 0) {
-        assert!(!shroud.nullifiers.contains(old_leaf_nullifier), EOLD_NULLIFIER_EXISTS);
-        // add old nullifier to nullifiers table
-        shroud.nullifiers.add(old_leaf_nullifier, true);
-        emit(NullifierUsed {
-            nullifier: old_leaf_nullifier,
-        });
-    };
+    // check if old nullifier exists, the first note of an account spends
+    // the nullifier of its empty leaf so an account has a single chain
+    assert!(!shroud.nullifiers.contains(old_leaf_nullifier), EOLD_NULLIFIER_EXISTS);
+    // add old nullifier to nullifiers table
+    shroud.nullifiers.add(old_leaf_nullifier, true);
+    emit(NullifierUsed {
+        nullifier: old_leaf_nullifier,
+    });
 
     let (index, root) = shroud.tree.insert(new_leaf);
 
@@ -208,7 +280,9 @@ public fun deposit<T>(
         index: index,
         value: new_leaf,
         new_root: root,
+        memo: memo,
     });
+    emit_escrow(index, escrow);
 }
 
 public fun withdraw<T>(
@@ -217,7 +291,10 @@ public fun withdraw<T>(
     current_root: u256,
     old_leaf_nullifier: u256,
     new_leaf: u256,
+    note_type: u64,
     proof: vector<u8>,
+    memo: vector<u8>,
+    escrow: vector<u8>,
     ctx: &mut TxContext,
 ): Coin<T> {
     let tn = get<T>();
@@ -238,6 +315,7 @@ public fun withdraw<T>(
     // public address
     verify_proof(
         shroud,
+        note_type,
         current_root,
         diff_hash,
         old_leaf_nullifier,
@@ -245,6 +323,7 @@ public fun withdraw<T>(
         proof,
         fr::from_address_bytes(ctx.sender()).repr(),
         0,
+        escrow,
     );
 
     // check if root valid
@@ -254,6 +333,9 @@ public fun withdraw<T>(
     assert!(!shroud.nullifiers.contains(old_leaf_nullifier), EOLD_NULLIFIER_EXISTS);
     // add old nullifier to nullifiers table
     shroud.nullifiers.add(old_leaf_nullifier, true);
+    emit(NullifierUsed {
+        nullifier: old_leaf_nullifier,
+    });
 
     // insert new leaf into tree
     let (index, root) = shroud.tree.insert(new_leaf);
@@ -267,13 +349,126 @@ public fun withdraw<T>(
         index: index,
         value: new_leaf,
         new_root: root,
+        memo: memo,
     });
+    emit_escrow(index, escrow);
+
+    withdrawn_coin
+}
+
+// public operation crediting and debiting several coin types with one proof,
+// must be consumed by `finish_public_operation` in the same transaction
+public struct PublicOperation {
+    shroud: ID,
+    coin_diff: CoinDiff,
+}
+
+public fun start_public_operation(shroud: &Shroud): PublicOperation {
+    PublicOperation {
+        shroud: object::id(shroud),
+        coin_diff: coin_diff::empty(shroud.allowed_token_length, shroud.allowed_tokens),
+    }
+}
+
+public fun deposit_coin<T>(
+    shroud: &mut Shroud,
+    operation: &mut PublicOperation,
+    coin: Coin<T>,
+    ctx: &mut TxContext,
+) {
+    assert!(operation.shroud == object::id(shroud), EWRONG_SHROUD);
+    let tn = get<T>();
+    assert!(shroud.allowed_tokens.contains(&tn), ETOKEN_NOT_ALLOWED);
+
+    let amount = coin.value();
+    let prev_coin: &mut Coin<T> = shroud.balances.borrow_mut(tn);
+    prev_coin.join(coin);
+    operation.coin_diff.add_coin(tn, amount);
+
+    emit(Deposited<T> {
+        account: ctx.sender(),
+        amount: amount,
+    });
+}
+
+public fun withdraw_coin<T>(
+    shroud: &mut Shroud,
+    operation: &mut PublicOperation,
+    amount: u64,
+    ctx: &mut TxContext,
+): Coin<T> {
+    assert!(operation.shroud == object::id(shroud), EWRONG_SHROUD);
+    let tn = get<T>();
+    assert!(shroud.allowed_tokens.contains(&tn), ETOKEN_NOT_ALLOWED);
+
+    let prev_coin: &mut Coin<T> = shroud.balances.borrow_mut(tn);
+    let withdrawn_coin = prev_coin.split(amount, ctx);
+    operation.coin_diff.sub_coin(tn, amount);
+
+    emit(Withdrawn<T> {
+        account: ctx.sender(),
+        amount: amount,
+    });
+
+    withdrawn_coin
+}
+
+public fun finish_public_operation(
+    shroud: &mut Shroud,
+    operation: PublicOperation,
+    current_root: u256,
+    old_leaf_nullifier: u256,
+    new_leaf: u256,
+    note_type: u64,
+    proof: vector<u8>,
+    memo: vector<u8>,
+    escrow: vector<u8>,
+    ctx: &mut TxContext,
+) {
+    let PublicOperation { shroud: shroud_id, coin_diff } = operation;
+    assert!(shroud_id == object::id(shroud), EWRONG_SHROUD);
+    let diff_hash = coin_diff.final_repr();
 
+    // verify proof
+    // 1. old leaf is in tree root (current_root)
+    // 2. old leaf nullifier is correct
+    // 3. new leaf is calculated correctly by applying every deposit and
+    //    withdrawal of the operation to the old leaf and final amounts >= 0
+    // public address
+    verify_proof(
+        shroud,
+        note_type,
+        current_root,
+        diff_hash,
+        old_leaf_nullifier,
+        new_leaf,
+        proof,
+        fr::from_address_bytes(ctx.sender()).repr(),
+        0,
+        escrow,
+    );
+
+    // check if root valid
+    assert!(shroud.tree.is_valid_root(current_root), EINVALID_ROOT);
+
+    // check if old nullifier exists, the first note of an account spends
+    // the nullifier of its empty leaf so an account has a single chain
+    assert!(!shroud.nullifiers.contains(old_leaf_nullifier), EOLD_NULLIFIER_EXISTS);
+    // add old nullifier to nullifiers table
+    shroud.nullifiers.add(old_leaf_nullifier, true);
     emit(NullifierUsed {
         nullifier: old_leaf_nullifier,
     });
 
-    withdrawn_coin
+    let (index, root) = shroud.tree.insert(new_leaf);
+
+    emit(LeafInserted {
+        index: index,
+        value: new_leaf,
+        new_root: root,
+        memo: memo,
+    });
+    emit_escrow(index, escrow);
 }
 
 public struct SwapBalance<phantom ORIGIN, phantom TARGET> {
@@ -288,7 +483,10 @@ public fun start_swap<ORIGIN, TARGET>(
     current_root: u256,
     old_leaf_nullifier: u256,
     new_leaf: u256,
+    note_type: u64,
     proof: vector<u8>,
+    memo: vector<u8>,
+    escrow: vector<u8>,
     ctx: &mut TxContext,
 ): (Coin<ORIGIN>, SwapBalance<ORIGIN, TARGET>) {
     let tn = get<ORIGIN>();
@@ -311,6 +509,7 @@ public fun start_swap<ORIGIN, TARGET>(
     // private address
     verify_proof(
         shroud,
+        note_type,
         current_root,
         diff_hash,
         old_leaf_nullifier,
@@ -318,6 +517,7 @@ public fun start_swap<ORIGIN, TARGET>(
         proof,
         0,
         0,
+        escrow,
     );
 
     // check if root valid
@@ -327,6 +527,9 @@ public fun start_swap<ORIGIN, TARGET>(
     assert!(!shroud.nullifiers.contains(old_leaf_nullifier), EOLD_NULLIFIER_EXISTS);
     // add old nullifier to nullifiers table
     shroud.nullifiers.add(old_leaf_nullifier, true);
+    emit(NullifierUsed {
+        nullifier: old_leaf_nullifier,
+    });
 
     let (index, root) = shroud.tree.insert(new_leaf);
 
@@ -334,11 +537,9 @@ public fun start_swap<ORIGIN, TARGET>(
         index: index,
         value: new_leaf,
         new_root: root,
+        memo: memo,
     });
-
-    emit(NullifierUsed {
-        nullifier: old_leaf_nullifier,
-    });
+    emit_escrow(index, escrow);
 
     (
         origin_coin,
@@ -371,24 +572,18 @@ public fun end_swap<ORIGIN, TARGET>(
     });
 }
 
-#[test]
-fun test_core() {
-    use sui::test_scenario::{Self, return_shared};
-    use std::unit_test::assert_eq;
-
-    let owner = @0xCAFE;
-
-    let mut scenario = test_scenario::begin(owner);
-    {
-        init(scenario.ctx());
-    };
+// shares a shroud proving with the test keys and allowing the five test coins
+#[test_only]
+fun setup(scenario: &mut Scenario) {
+    let owner = scenario.sender();
+    init(scenario.ctx());
 
     scenario.next_tx(owner);
     {
         let mut shroud = scenario.take_shared<Shroud>();
         let mut admin = scenario.take_from_sender<ShroudAdmin>();
         let vk_bytes =
-            x"5231b7d586a81feda76cb9ac99a1041c5df25e99593ca32a3fe346d6107fdb9298d4e4ddef2b891f8ced8c3eb5288fe195812970a7ee83c4d0dd6d6f12f61d1c38264e379bb33d1c64a84ef52f36e085b532adbbea9d753292ffc2f1c204d714d2fd12bdca34edb07c854124aedf01f6706684907214fc9d9d672b7774f1390c5d962e6bbb9c137effbd3f77b9be6b5432f1ace7783c02e95ad4e8490025ff1009a87e8bb8a2ce92eca888b47391339636ad9960ca91ec371d0009e4be57d2132b9e303ce48ab36737a69bf90499ecab6b965c77058dbaf0417a22c528218a980700000000000000ce5d0c9aef626936aba894a1e6c9464f98c84a9797e7abc6900d4d7228824a0dfb2c74523ddba1d29f809a161d5505b13d89c1a4894dc198f0fbb15daacbc68e44d73ba92a227d59fad1e6d41326ffeb29eacd3e17553225eba9099e167cf325ff82ebddee21ca1d7249b35471abb63541de2ab02525ccf1755ce2688b046a054baa62b82b8e0862b8378a8c644a2b2d2ed8b7db1a3804efcc97d1a728abd996dfe12aa479bc67e1046edf0c58adadc1d079837fee4437bb5cc69f4a8a1c371aef19a14367aa60077da131ecc7d5ea0ccc774fa2ed7c006c3e181dbafb04dd89";
+            x"c29606b03c4b0ddde7b0c7b59c621cd1c7c94b11fda71ec0d179df96757f8da88ab1f6ced27efe2d5a868b7cf439ad806ad4320275ab3473ac40d2de06b15819cf232e68582fc287c64965886195959b506f54b1de1647c79846b0d956880ea6751cb208931ab874abad6edb710d2effae52c7f46a53149bb33a56e65fadf21c634ec8fdc6fb88da7e6f578af28441056692fa6619df664048eff029f729990978785274e5868757fd0a053c66d864dc0e96a1f9c887b0e50577053f74e4b32cafe1ba1cb01062ef31760f181592f1c9b44e4361699109144fe951f02b79469e070000000000000064c30c10e5b403ebbad61ce2b04273b1e0254be157deebd50910d62666ef6316f7428848ca913e8c69198bb5d31cf1a9a4c620547a4330a667833139657ebfaeca3baf6b319d3488fa8ed7f12863e9d8e52b1a773a3d402b31cc8a237bd9cbadb2610c94847be6055d8bed56f8eb88c828efa45a2d2a95d9db835a9d4786559cdd6d7a5dc5f058a115ee40208a3beff92ac2d0d1bc73284b450a32bddc3b892d3dbfdbe20b6b92d314a397e93b849ff0ab6279eb9986335c68ca6a5b929ef48ccc40ceb4200f1a4adec61d0271729030b6d491a461175a8cd836ed5f336e312f";
         initialize_prover(&mut admin, &mut shroud, vk_bytes);
         allow_token<shroud::usdc::USDC>(&mut admin, &mut shroud, scenario.ctx());
         allow_token<shroud::usdt::USDT>(&mut admin, &mut shroud, scenario.ctx());
@@ -396,10 +591,23 @@ fun test_core() {
         allow_token<shroud::suins::SUINS>(&mut admin, &mut shroud, scenario.ctx());
         allow_token<shroud::wal::WAL>(&mut admin, &mut shroud, scenario.ctx());
 
-        let usdc_cap = coin::create_treasury_cap_for_testing<shroud::usdc::USDC>(scenario.ctx());
-
         scenario.return_to_sender(admin);
         return_shared(shroud);
+    };
+}
+
+#[test]
+fun test_core() {
+    use sui::test_scenario;
+
+    let owner = @0xCAFE;
+
+    let mut scenario = test_scenario::begin(owner);
+    setup(&mut scenario);
+
+    scenario.next_tx(owner);
+    {
+        let usdc_cap = coin::create_treasury_cap_for_testing<shroud::usdc::USDC>(scenario.ctx());
         transfer::public_share_object(usdc_cap);
     };
 
@@ -415,3 +623,100 @@ fun test_core() {
 
     scenario.end();
 }
+
+#[test]
+fun test_public_operation() {
+    use sui::test_scenario;
+    use shroud::usdc::USDC;
+    use shroud::usdt::USDT;
+
+    let owner = @0xCAFE;
+    let nullifier = 9755111232978022721349791194667976083711445599262704433827333297354801785751;
+
+    let mut scenario = test_scenario::begin(owner);
+    setup(&mut scenario);
+
+    scenario.next_tx(owner);
+    {
+        let mut shroud = scenario.take_shared<Shroud>();
+        // deposit 100 USDC and 50 USDT and withdraw 30 USDC under one proof
+        // of the first note of 0xCAFE, spending key 42, made with the proving
+        // key of the vk in `setup`: regenerate it whenever the vk changes
+        let mut operation = start_public_operation(&shroud);
+        let usdc = coin::mint_for_testing<USDC>(100, scenario.ctx());
+        deposit_coin(&mut shroud, &mut operation, usdc, scenario.ctx());
+        let usdt = coin::mint_for_testing<USDT>(50, scenario.ctx());
+        deposit_coin(&mut shroud, &mut operation, usdt, scenario.ctx());
+        let withdrawn = withdraw_coin<USDC>(&mut shroud, &mut operation, 30, scenario.ctx());
+        assert_eq!(withdrawn.value(), 30);
+
+        finish_public_operation(
+            &mut shroud,
+            operation,
+            10941962436777715901943463195175331263348098796018438960955633645115732864202,
+            nullifier,
+            15707010455025494486441010092474590720646079917685981036642691583689913551528,
+            SINGLE_KEY,
+            x"548636521cdbee38946072154e645dc4990b3ab97da69d1368ca857c0778b21d3dc9903aff1a6fe161975b0a7fabcb6abfb2633bf7184113f629f3f456c40617d0a9e6e22636d341582385d601db926d44b168b3d2100c490e6d010a45e74ea477f568065fefb3bf9c426c9fea73b204ca435fa96d375d46dadeb9fcfc9d0c88",
+            x"",
+            x"",
+            scenario.ctx(),
+        );
+
+        assert_eq!(shroud.tree.size(), 1);
+        assert_eq!(
+            shroud.tree.root(),
+            15139504277091373247533979538924628143477702891799002287315409089807868412163,
+        );
+        assert!(shroud.nullifiers.contains(nullifier));
+        let usdc: &Coin<USDC> = shroud.balances.borrow(get<USDC>());
+        assert_eq!(usdc.value(), 70);
+        let usdt: &Coin<USDT> = shroud.balances.borrow(get<USDT>());
+        assert_eq!(usdt.value(), 50);
+
+        destroy(withdrawn);
+        return_shared(shroud);
+    };
+
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = EWRONG_SHROUD)]
+fun test_deposit_into_another_shroud() {
+    use sui::test_scenario;
+
+    let owner = @0xCAFE;
+    let mut scenario = test_scenario::begin(owner);
+    setup(&mut scenario);
+
+    scenario.next_tx(owner);
+    let mut shroud = scenario.take_shared<Shroud>();
+    let other = new(scenario.ctx());
+    let mut operation = start_public_operation(&other);
+    let usdc = coin::mint_for_testing<shroud::usdc::USDC>(100, scenario.ctx());
+    deposit_coin(&mut shroud, &mut operation, usdc, scenario.ctx());
+
+    destroy(operation);
+    destroy(other);
+    return_shared(shroud);
+    scenario.end();
+}
+
+#[test, expected_failure(abort_code = EWRONG_SHROUD)]
+fun test_finish_in_another_shroud() {
+    use sui::test_scenario;
+
+    let owner = @0xCAFE;
+    let mut scenario = test_scenario::begin(owner);
+    setup(&mut scenario);
+
+    scenario.next_tx(owner);
+    let mut shroud = scenario.take_shared<Shroud>();
+    let other = new(scenario.ctx());
+    let operation = start_public_operation(&other);
+    finish_public_operation(&mut shroud, operation, 0, 0, 0, SINGLE_KEY, x"", x"", x"", scenario.ctx());
+
+    destroy(other);
+    return_shared(shroud);
+    scenario.end();
+}
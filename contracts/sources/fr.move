@@ -1,5 +1,7 @@
 module shroud::fr;
 
+use sui::poseidon::poseidon_bn254;
+
 public struct FR has copy, drop {
     value: u256,
 }
@@ -7,6 +9,9 @@ public struct FR has copy, drop {
 const FR_MODULUS: u256 =
     21888242871839275222246405745257275088548364400416034343698204186575808495617;
 const FR_MODULUS_HALF: u256 = FR_MODULUS - 1 / 2;
+// "addr", the first input of an encoded address
+const ADDRESS_DOMAIN: u256 = 0x61646472;
+const LIMB_MASK: u256 = (1 << 128) - 1;
 
 public fun zero(): FR {
     FR {
@@ -26,9 +31,17 @@ public fun from_repr(repr: u256): FR {
     }
 }
 
+// address_fr = H(ADDRESS_DOMAIN, hi, lo), both limbs fit in the field so
+// distinct addresses do not collide like they would modulo FR_MODULUS, and
+// no two-input hash of the tree or the circuits has three inputs
 public fun from_address_bytes(address: address): FR {
+    let value = address.to_u256();
+    let mut to_hash = vector::empty();
+    to_hash.push_back(ADDRESS_DOMAIN);
+    to_hash.push_back(value >> 128);
+    to_hash.push_back(value & LIMB_MASK);
     FR {
-        value: address.to_u256() % FR_MODULUS,
+        value: poseidon_bn254(&to_hash),
     }
 }
 
@@ -3,6 +3,17 @@ module shroud::merkle;
 use sui::poseidon::poseidon_bn254;
 use sui::table::{Self, Table};
 
+// Tree nodes are plain Poseidon, i.e. the zero capacity domain. Leaves,
+// nullifiers and history roots are hashed off chain with the nonzero
+// capacities below, `hash::Domain` of the Rust crate, and never collide with
+// a node. Only tests hash in these domains, to check the Rust hashes.
+#[test_only]
+const LEAF_DOMAIN: u256 = 1;
+#[test_only]
+const NULLIFIER_DOMAIN: u256 = 2;
+#[test_only]
+const HISTORY_DOMAIN: u256 = 3;
+
 const EINVALID_INDEX: u64 = 0x200001;
 const EINVALID_LEVEL: u64 = 0x200002;
 const EINVALID_VALID_SIZE: u64 = 0x200003;
@@ -102,3 +113,18 @@ public fun is_valid_root(tree: &MerkleTree, root: u256): bool {
 public fun size(tree: &MerkleTree): u64 {
     tree.leafs.length()
 }
+
+#[test_only]
+public fun leaf_domain(): u256 {
+    LEAF_DOMAIN
+}
+
+#[test_only]
+public fun nullifier_domain(): u256 {
+    NULLIFIER_DOMAIN
+}
+
+#[test_only]
+public fun history_domain(): u256 {
+    HISTORY_DOMAIN
+}
@@ -245,10 +245,21 @@ fun poseidon_rc(): (vector<FR>, vector<vector<FR>>) {
 }
 
 public fun poseidon_bn254(left: u256, right: u256): u256 {
+    hash_in(0, left, right)
+}
+
+// Poseidon with `domain` as the initial capacity, as the leaf, nullifier and
+// history hashes of `merkle` are computed off chain
+#[test_only]
+public fun poseidon_bn254_in(domain: u256, left: u256, right: u256): u256 {
+    hash_in(domain, left, right)
+}
+
+fun hash_in(domain: u256, left: u256, right: u256): u256 {
     let (rc, mds) = poseidon_rc();
 
     let mut state: vector<FR> = vector::empty();
-    state.push_back(fr::zero());
+    state.push_back(fr::from_repr(domain));
     state.push_back(fr::from_repr(left));
     state.push_back(fr::from_repr(right));
 
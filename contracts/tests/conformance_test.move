@@ -0,0 +1,137 @@
+// Generated from conformance.json by `cargo run --bin conformance conformance.json
+// ../contracts/tests/conformance_test.move` in circuits_rust, do not edit.
+#[test_only]
+module shroud::conformance_test;
+
+use shroud::coin_diff::{Self, CoinDiff};
+use shroud::core;
+use shroud::fr;
+use shroud::merkle;
+use std::type_name::get;
+use std::unit_test::{assert_eq, destroy};
+
+// one coin type for every asset, in the order they are allowed
+fun coin_diff(): CoinDiff {
+    coin_diff::empty(5, vector[get<u8>(), get<u16>(), get<u32>(), get<u64>(), get<u128>()])
+}
+
+#[test]
+fun diff_hashes_match_final_repr() {
+    let diff = coin_diff();
+    assert_eq!(diff.final_repr(), 0x0d0cd90304ce617006768b31cd6ab10e683f0bc59252f3b6ace0a62b422489e6);
+    let mut diff = coin_diff();
+    diff.add_coin(get<u8>(), 1000);
+    assert_eq!(diff.final_repr(), 0x2c7ab9d5cddb579d9816c2c993e1b124c0b79f7e6218762cac6e4d0fd003af76);
+    let mut diff = coin_diff();
+    diff.sub_coin(get<u8>(), 1000);
+    assert_eq!(diff.final_repr(), 0x29ef533dde4bb0ed2a4b35658ffac1abe9d136e3142eff56014053d8b2ec3d87);
+    let mut diff = coin_diff();
+    diff.sub_coin(get<u8>(), 500);
+    diff.add_coin(get<u32>(), 250);
+    assert_eq!(diff.final_repr(), 0x0c8f858afe0e76ae19e3beee59c706a048d9ea31a5b6c5f4f4ecdfa6fb35f4e7);
+    let mut diff = coin_diff();
+    diff.add_coin(get<u8>(), 9223372036854775807);
+    diff.sub_coin(get<u16>(), 9223372036854775808);
+    diff.add_coin(get<u32>(), 1);
+    diff.sub_coin(get<u64>(), 1);
+    assert_eq!(diff.final_repr(), 0x1f14f22d3cf06c52714ec5ed44ce55a6103fd272b98b2ceb03e1daf78a68862b);
+    let mut diff = coin_diff();
+    diff.add_coin(get<u8>(), 18446744073709551615);
+    diff.sub_coin(get<u16>(), 18446744073709551615);
+    assert_eq!(diff.final_repr(), 0x1b9088c18031f3510de4d75600b06d797c7be09181278d19d77ae9a020650aa4);
+}
+
+#[test]
+fun roots_match_merkle_insert() {
+    let mut ctx = tx_context::dummy();
+    let mut tree = merkle::new(20, 1, 0x0000000000000000000000000000000000000000000000000000000000000000, &mut ctx);
+    assert_eq!(tree.root(), 0x1830ee67b5fb554ad5f63d4388800e1cfe78e310697d46e43c9ce36134f72cca);
+    let (index, root) = tree.insert(0x08f3a2a2a474eaba600da35089c1abfb3afdfe0b112f0fb5aa73da21b41a1d6f);
+    assert_eq!(index, 0);
+    assert_eq!(root, 0x2e7d6dc3130a04f8770883662291aac5ab074f3be6d338dbc5a1d5bd982bf138);
+    let (index, root) = tree.insert(0x1f14a7ba09e14206f657c0495ed8804b6296ac4fb00ab5280f730c62a48bb7de);
+    assert_eq!(index, 1);
+    assert_eq!(root, 0x04c06920248a479610620fc7617404186a9516ff82a6a59187998b1d9117380a);
+    let (index, root) = tree.insert(0x12ee18d3fb09243b697e453e0531b4bf53c334910101d7828fc994509ccc22e5);
+    assert_eq!(index, 2);
+    assert_eq!(root, 0x1caf415913ebf602b657a43f4dc730137a8ff57a02193f1a401922c74b36a15e);
+    let (index, root) = tree.insert(0x0ee069e6aa796ef0e46cbd51d10468393d443a00f5affe72898d9ab62e335e16);
+    assert_eq!(index, 3);
+    assert_eq!(root, 0x06316b3bd1e3d4e4bb72f9139c32fc27e74031a1097e3217a6f7a5f52faf9639);
+    let (index, root) = tree.insert(0x00eb38bb8227e5e5262c114f2189898dcb007a8d3ec5930f5f5584f00f9b32bd);
+    assert_eq!(index, 4);
+    assert_eq!(root, 0x0d5580d15cad2303bb1489248711a4963c20f46b652e155f2bf4d490b08f2c90);
+    let (index, root) = tree.insert(0x2c0ef7d998395eb71b4ae3b58a557ca68129d749127a1ee115795198df0416f4);
+    assert_eq!(index, 5);
+    assert_eq!(root, 0x12f5c30e25780674c7098b063c2f3125dfecda1d7225dac58ac338e485bee18c);
+    let (index, root) = tree.insert(0x0befb349edba69ac0688f65e1ea5bd57f266f609c1d30147c191b20495ef7761);
+    assert_eq!(index, 6);
+    assert_eq!(root, 0x006dc64d011842e967f827d054888dab6d1c5d33829758a81e71b5f78ce2e182);
+    let (index, root) = tree.insert(0x2ca1574a77058c957df186089bec2fd96659de0f55f65097616aa785f18c8b3b);
+    assert_eq!(index, 7);
+    assert_eq!(root, 0x03a565d37068424f577d762d41cc889b4987d935ef54901bd601df8ac0fb5ff3);
+    destroy(tree);
+}
+
+#[test]
+fun addresses_match_from_address_bytes() {
+    assert_eq!(fr::from_address_bytes(@0x1111111111111111111111111111111111111111111111111111111111111111).repr(), 0x1ad0cdb560daebc6dd120c8d59161a91a52bb5656b49de8aa3142869cafcd97d);
+    assert_eq!(fr::from_address_bytes(@0x2222222222222222222222222222222222222222222222222222222222222222).repr(), 0x1ddf549ddefcc65591bbdc4f07fe47c2b2cde6fecbb4b235d611c1e9e03fab69);
+    assert_eq!(fr::from_address_bytes(@0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff).repr(), 0x09650b420d8dc24f52f906cc03b592ca3def0b6668aab7f9ee3a50c81d0b384e);
+}
+
+#[test]
+fun public_inputs_match_verify_proof() {
+    // first deposit
+    assert_eq!(
+        core::public_inputs_bytes(
+            0x03a565d37068424f577d762d41cc889b4987d935ef54901bd601df8ac0fb5ff3,
+            0x2c7ab9d5cddb579d9816c2c993e1b124c0b79f7e6218762cac6e4d0fd003af76,
+            0x0b56ab5c137c0154d49903a4d4ae4df0a69d9d50833c41e6ded93787a24cc043,
+            0x07f385bdf5404c91934c189bc311069ba297287a1886ae52f36d83afd41098fa,
+            0x1ad0cdb560daebc6dd120c8d59161a91a52bb5656b49de8aa3142869cafcd97d,
+            0x0000000000000000000000000000000000000000000000000000000000000000,
+            x"",
+        ),
+        x"f35ffbc08adf01d61b9054ef35d987499b88cc412d767d574f426870d365a50376af03d00f4d6eac2c7618627e9fb7c024b1e193c9c216989d57dbcdd5b97a2c43c04ca28737d9dee6413c83509d9da6f04daed4a40399d454017c135cab560bfa9810d4af836df352ae86187a2897a29b0611c39b184c93914c40f5bd85f3077dd9fcca692814a38ade496b65b52ba5911a16598d0c12ddc6ebda60b5cdd01a0000000000000000000000000000000000000000000000000000000000000000",
+    );
+    // private swap
+    assert_eq!(
+        core::public_inputs_bytes(
+            0x03a565d37068424f577d762d41cc889b4987d935ef54901bd601df8ac0fb5ff3,
+            0x0c8f858afe0e76ae19e3beee59c706a048d9ea31a5b6c5f4f4ecdfa6fb35f4e7,
+            0x071e3b2fd3cc58a3f6ddd1550e0cf0c81e5b7d83ded8cab9fb41e66793872833,
+            0x1f190bdc34f2f7846f71b14f28959066fdb84236a016edb323623cf0b98cdaf8,
+            0x0000000000000000000000000000000000000000000000000000000000000000,
+            0x0000000000000000000000000000000000000000000000000000000000000000,
+            x"",
+        ),
+        x"f35ffbc08adf01d61b9054ef35d987499b88cc412d767d574f426870d365a503e7f435fba6dfecf4f4c5b6a531ead948a006c759eebee319ae760efe8a858f0c3328879367e641fbb9cad8de837d5b1ec8f00c0e55d1ddf6a358ccd32f3b1e07f8da8cb9f03c6223b3ed16a03642b8fd669095284fb1716f84f7f234dc0b191f00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+    );
+    // withdraw with escrow
+    assert_eq!(
+        core::public_inputs_bytes(
+            0x03a565d37068424f577d762d41cc889b4987d935ef54901bd601df8ac0fb5ff3,
+            0x160977b0b30111b78149d255658b5353ea2598bf1271b295cba13beeb9dacb1d,
+            0x071e3b2fd3cc58a3f6ddd1550e0cf0c81e5b7d83ded8cab9fb41e66793872833,
+            0x285eb92daa9a88bd0187380a301ed4b8770484146f18aa69087fca8b6b32633d,
+            0x1ddf549ddefcc65591bbdc4f07fe47c2b2cde6fecbb4b235d611c1e9e03fab69,
+            0x0000000000000000000000000000000000000000000000000000000000000000,
+            x"b0d899b63b8ab0fe24e6fccce55f964a4efc2dd7ff8e43ada337d1045dc09a06fbf1cd581cdf679d4586fe2107de6faf74bfbc4b9d84cfea990882bef6c8ed169c7cfc2b66c14febbff6eb8e499c9cd9507277c1f5f06758706d55ddb34dc51a5834c195f5b9dcb5a048dd938c860c03da2cad9557a2e4dd59ecb8a1647bad2e712dbbb14f941dc4026f325f22d6e1a4edbbdae54585c47fb267911c03f0f01d5106de03be2566c3bc57e526977b126ca6465abf4cb99002f4dd590ee64cd506d4bf63d52007730d3070c6c31e1f4a9c9b904c66525699bb0a12387b089eae167cdb916cc72bef8282e939a80af6127876a606872bc34502a7ef1fa76e13bd02",
+        ),
+        x"f35ffbc08adf01d61b9054ef35d987499b88cc412d767d574f426870d365a5031dcbdab9ee3ba1cb95b27112bf9825ea53538b6555d24981b71101b3b07709163328879367e641fbb9cad8de837d5b1ec8f00c0e55d1ddf6a358ccd32f3b1e073d63326b8bca7f0869aa186f14840477b8d41e300a388701bd889aaa2db95e2869ab3fe0e9c111d635b2b4cbfee6cdb2c247fe074fdcbb9155c6fcde9d54df1d0000000000000000000000000000000000000000000000000000000000000000b0d899b63b8ab0fe24e6fccce55f964a4efc2dd7ff8e43ada337d1045dc09a06fbf1cd581cdf679d4586fe2107de6faf74bfbc4b9d84cfea990882bef6c8ed169c7cfc2b66c14febbff6eb8e499c9cd9507277c1f5f06758706d55ddb34dc51a5834c195f5b9dcb5a048dd938c860c03da2cad9557a2e4dd59ecb8a1647bad2e712dbbb14f941dc4026f325f22d6e1a4edbbdae54585c47fb267911c03f0f01d5106de03be2566c3bc57e526977b126ca6465abf4cb99002f4dd590ee64cd506d4bf63d52007730d3070c6c31e1f4a9c9b904c66525699bb0a12387b089eae167cdb916cc72bef8282e939a80af6127876a606872bc34502a7ef1fa76e13bd02",
+    );
+    // withdraw a full balance
+    assert_eq!(
+        core::public_inputs_bytes(
+            0x03a565d37068424f577d762d41cc889b4987d935ef54901bd601df8ac0fb5ff3,
+            0x24ec795017bce2ffd4636d6515717a2bd6d454cb7e1d2491ebea104627d2f907,
+            0x1ce7980cdc5122f3d5dbcc0862da80332f80b05e08064f2a69efa4e4ce593241,
+            0x1077423052ba5bd8476993c34b71930f1bfd33f59550955de213fc6f008cacb6,
+            0x09650b420d8dc24f52f906cc03b592ca3def0b6668aab7f9ee3a50c81d0b384e,
+            0x0000000000000000000000000000000000000000000000000000000000000000,
+            x"",
+        ),
+        x"f35ffbc08adf01d61b9054ef35d987499b88cc412d767d574f426870d365a50307f9d2274610eaeb91241d7ecb54d4d62b7a7115656d63d4ffe2bc175079ec24413259cee4a4ef692a4f06085eb0802f3380da6208ccdbd5f32251dc0c98e71cb6ac8c006ffc13e25d955095f533fd1b0f93714bc3936947d85bba52304277104e380b1dc8503aeef9b7aa68660bef3dca92b503cc06f9524fc28d0d420b65090000000000000000000000000000000000000000000000000000000000000000",
+    );
+}
@@ -0,0 +1,29 @@
+#[test_only]
+module shroud::fr_test;
+
+use shroud::fr;
+use std::unit_test::assert_eq;
+
+// expected values are `utils::address_to_fr` of the Rust crate, which
+// tests/address.rs checks against the same constants
+#[test]
+fun from_address_bytes_matches_address_to_fr() {
+    assert_eq!(
+        fr::from_address_bytes(@0x0).repr(),
+        7809808677678182919361798478645198704514003897184072159114288900413020862725,
+    );
+    assert_eq!(
+        fr::from_address_bytes(@0xb0b).repr(),
+        17650599565037227231417921435348288663855929599621633977351081005832815255886,
+    );
+    assert_eq!(
+        fr::from_address_bytes(@0xcafe).repr(),
+        3608769810656646089752900619526556407236928262508526470644518878056894752930,
+    );
+    assert_eq!(
+        fr::from_address_bytes(
+            @0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff,
+        ).repr(),
+        4249344890785685112034644153849129056088785602574882636279911741559812077646,
+    );
+}
@@ -1,7 +1,8 @@
 #[test_only]
 module shroud::poseidon_test;
 
-use shroud::poseidon::poseidon_bn254;
+use shroud::merkle;
+use shroud::poseidon::{poseidon_bn254, poseidon_bn254_in};
 
 #[test]
 fun correct_poseidon_hash() {
@@ -18,3 +19,21 @@ fun correct_poseidon_hash() {
     std::debug::print(&output_native);
     std::debug::print(&output);
 }
+
+// H(1, 2) in every domain of `merkle`, the values `hash_in` of the Rust crate
+// computes in tests/poseidon_kat.rs
+#[test]
+fun poseidon_hash_in_domains() {
+    assert!(
+        poseidon_bn254_in(0, 1, 2) == 7853200120776062878684798364095072458815029376092732009249414926327459813530,
+    );
+    assert!(
+        poseidon_bn254_in(merkle::leaf_domain(), 1, 2) == 15265521111443306125770393834298107805008305603868395386351450631655540391531,
+    );
+    assert!(
+        poseidon_bn254_in(merkle::nullifier_domain(), 1, 2) == 3587841099753357953233326007097742799211941019050730282074530241745813090888,
+    );
+    assert!(
+        poseidon_bn254_in(merkle::history_domain(), 1, 2) == 18983173721955286408381200682584471402668031072231079754401407247299446548040,
+    );
+}
@@ -4,35 +4,48 @@ import { decodeSuiPrivateKey } from "@mysten/sui/cryptography"
 import { getFaucetHost, requestSuiFromFaucetV2 } from "@mysten/sui/faucet"
 import { Ed25519Keypair } from "@mysten/sui/keypairs/ed25519"
 import { Transaction } from "@mysten/sui/transactions"
-import { fromHex, Hex } from "viem"
 import { z } from "zod"
 
 import { env } from "@/env.mjs"
 import { contracts } from "@/config/contract"
+import { addMoveCalls } from "@/lib/move-calls"
 
+const hex = z.string().regex(/^[0-9a-f]*$/)
+const u64 = z.string().regex(/^[0-9]+$/)
+
+// the `start_swap` call of a swap intent, the proxy pays for nothing else:
+// `core::start_swap(shroud, amount, minimum_received, current_root,
+// old_leaf_nullifier, new_leaf, note_type, proof, memo, escrow)`
 const schema = z.object({
-  coinIn: z.string(),
-  coinOut: z.string(),
-  amountOut: z.string(),
-  minimumReceived: z.string(),
-  currentRoot: z.string(),
-  nullifier: z.string(),
-  newLeaf: z.string(),
-  proof: z.string(),
+  calls: z.tuple([
+    z.object({
+      target: z.literal(`${contracts.packageId}::core::start_swap`),
+      type_arguments: z.tuple([z.string(), z.string()]),
+      arguments: z.tuple([
+        z.object({
+          kind: z.literal("object"),
+          id: z.literal(contracts.coreId),
+        }),
+        z.object({ kind: z.literal("u64"), value: u64 }),
+        z.object({ kind: z.literal("u64"), value: u64 }),
+        z.object({ kind: z.literal("u256"), value: hex }),
+        z.object({ kind: z.literal("u256"), value: hex }),
+        z.object({ kind: z.literal("u256"), value: hex }),
+        z.object({ kind: z.literal("u64"), value: u64 }),
+        z.object({ kind: z.literal("bytes"), value: hex }),
+        z.object({ kind: z.literal("bytes"), value: hex }),
+        z.object({ kind: z.literal("bytes"), value: hex }),
+      ]),
+    }),
+  ]),
 })
 
 export async function POST(request: NextRequest) {
   const body = await request.json()
-  const {
-    coinIn,
-    coinOut,
-    amountOut,
-    minimumReceived,
-    currentRoot,
-    nullifier,
-    newLeaf,
-    proof,
-  } = await schema.parseAsync(body)
+  const { calls } = await schema.parseAsync(body)
+  const [startSwap] = calls
+  const [coinOut, coinIn] = startSwap.type_arguments
+  const minimumReceived = startSwap.arguments[2].value
 
   const keypair = decodeSuiPrivateKey(env.PROXY_PRIVATE_KEY)
   const signer = Ed25519Keypair.fromSecretKey(keypair.secretKey)
@@ -49,19 +62,8 @@ export async function POST(request: NextRequest) {
 
   const tx = new Transaction()
 
-  const [coin, swapBalance] = tx.moveCall({
-    target: `${contracts.packageId}::core::start_swap`,
-    arguments: [
-      tx.object(contracts.coreId),
-      tx.pure.u64(amountOut.replace(/^-/, "")),
-      tx.pure.u64(minimumReceived),
-      tx.pure.u256(fromHex(currentRoot as Hex, "bigint")),
-      tx.pure.u256(fromHex(nullifier as Hex, "bigint")),
-      tx.pure.u256(fromHex(newLeaf as Hex, "bigint")),
-      tx.pure.vector("u8", fromHex(proof as Hex, "bytes")),
-    ],
-    typeArguments: [coinOut, coinIn],
-  })
+  const [swap] = await addMoveCalls(tx, calls, client, signer.toSuiAddress())
+  const [coin, swapBalance] = swap
 
   const [finalCoin] = tx.moveCall({
     target: `${contracts.packageId}::router::swap`,
@@ -16,4 +16,6 @@ export const contracts = {
     "0xaa555a4c865d260560627255552b855b67c32a35d13bee77e4b12fbf96973641",
   routerId:
     "0x9b57c18ddcb07cc7440c2941443ab6c4f444b1e7e4151062461224ecc07ad88f",
+  // Auditor public key the proving key was set up with, null without escrow
+  auditorKey: null as string | null,
 }
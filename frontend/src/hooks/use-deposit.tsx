@@ -8,10 +8,10 @@ import { useMutation, useQueryClient } from "@tanstack/react-query"
 import BigNumber from "bignumber.js"
 import { ExternalLink } from "lucide-react"
 import { toast } from "sonner"
-import { fromHex } from "viem"
 
 import { contracts } from "@/config/contract"
 import { CURRENCY } from "@/config/currency"
+import { addMoveCalls } from "@/lib/move-calls"
 import { network } from "@/components/provider"
 
 import { useInternalWallet } from "./use-internal-wallet"
@@ -32,6 +32,7 @@ export const useDeposit = () => {
     updateTreeIndex,
     updateLastActiveSeq,
     updateNullifier,
+    updateHistory,
     addHistory,
     getInternalAccount,
   } = useInternalWallet()
@@ -54,7 +55,7 @@ export const useDeposit = () => {
       const fullAmount = new BigNumber(amountStr)
         .shiftedBy(cur.decimals)
         .integerValue(BigNumber.ROUND_FLOOR)
-        .toString()
+        .toFixed()
 
       txState().startOperation({
         type: "deposit",
@@ -64,42 +65,15 @@ export const useDeposit = () => {
 
       const proof = await prove.mutateAsync({
         account: await getInternalAccount(currentAccount.address),
-        diffs: {
-          [currency]: amountStr,
+        intent: {
+          type: "deposit",
+          coin_type: cur.coinType,
+          amount: fullAmount,
         },
-        isPublic: true,
       })
 
-      const coins = await client.getCoins({
-        owner: currentAccount.address,
-        coinType: cur.coinType,
-      })
-
-      if (!coins.data.length) {
-        throw new Error("No coins")
-      }
-
       const tx = new Transaction()
-      if (coins.data.length > 1) {
-        tx.mergeCoins(
-          coins.data[0].coinObjectId,
-          coins.data.slice(1).map((c) => c.coinObjectId)
-        )
-      }
-      const [coin] = tx.splitCoins(coins.data[0].coinObjectId, [fullAmount])
-
-      tx.moveCall({
-        target: `${contracts.packageId}::core::deposit`,
-        typeArguments: [cur.coinType],
-        arguments: [
-          tx.object(contracts.coreId),
-          coin,
-          tx.pure.u256(fromHex(proof.merkleRoot, "bigint")),
-          tx.pure.u256(fromHex(proof.nullifier, "bigint")),
-          tx.pure.u256(fromHex(proof.afterLeaf, "bigint")),
-          tx.pure.vector("u8", fromHex(proof.proof, "bytes")),
-        ],
-      })
+      await addMoveCalls(tx, proof.calls, client, currentAccount.address)
 
       const txs = await sae.mutateAsync({
         transaction: tx,
@@ -125,6 +99,7 @@ export const useDeposit = () => {
       updateTreeIndex(currentAccount.address, treeIndex)
       updateLastActiveSeq(currentAccount.address, Date.now())
       updateNullifier(currentAccount.address, proof.afterNullifier)
+      updateHistory(currentAccount.address, proof.counter, proof.historyRoot)
       incDecBalance(currentAccount.address, currency, fullAmount, false)
       addHistory(currentAccount.address, {
         type: "deposit",
@@ -16,6 +16,8 @@ interface InternalAccount {
   treeIndex: number | null
   lastActiveSeq: number | null
   nullifier: string | null
+  counter: number
+  historyRoot: string
   balances: Record<keyof typeof CURRENCY, string>
   history: AccountHistory[]
 }
@@ -37,6 +39,7 @@ interface InternalWalletStore {
   updateTreeIndex: (address: string, treeIndex: number) => void
   updateLastActiveSeq: (address: string, lastActiveSeq: number) => void
   updateNullifier: (address: string, nullifier: string) => void
+  updateHistory: (address: string, counter: number, historyRoot: string) => void
   addHistory: (address: string, history: AccountHistory) => void
 }
 
@@ -55,6 +58,8 @@ export const useInternalWallet = create<InternalWalletStore>()(
               treeIndex: null,
               lastActiveSeq: null,
               nullifier: null,
+              counter: 0,
+              historyRoot: "0x00",
               balances: _.mapValues(CURRENCY, () => "0"),
               history: [],
             },
@@ -79,6 +84,10 @@ export const useInternalWallet = create<InternalWalletStore>()(
         if (account.treeIndex !== null) {
           acc.setIndex(account.treeIndex)
         }
+        acc.setHistory(
+          BigInt(account.counter),
+          account.historyRoot.replace(/^0x/, "")
+        )
         return acc.export()
       },
       updateBalance: async (
@@ -150,6 +159,19 @@ export const useInternalWallet = create<InternalWalletStore>()(
           ),
         }))
       },
+      updateHistory: async (
+        address: string,
+        counter: number,
+        historyRoot: string
+      ) => {
+        set((state) => ({
+          accounts: state.accounts.map((account) =>
+            account.address === address
+              ? { ...account, counter, historyRoot }
+              : account
+          ),
+        }))
+      },
       addHistory: (address: string, history: AccountHistory) => {
         set((state) => ({
           accounts: state.accounts.map((account) =>
@@ -1,84 +1,103 @@
 import { useSuiClient } from "@mysten/dapp-kit"
-import { useMutation } from "@tanstack/react-query"
-import BigNumber from "bignumber.js"
+import type { EventId, PaginatedEvents } from "@mysten/sui/client"
+import { useMutation, useQueryClient } from "@tanstack/react-query"
 import _ from "lodash"
-import { fromHex, Hex, toHex } from "viem"
+import { fromHex, Hex } from "viem"
 
 import { contracts } from "@/config/contract"
-import { CURRENCY, CURRENCY_LIST } from "@/config/currency"
+import { loadSyncedTree, saveSyncedTree } from "@/lib/tree-cache"
+import type { Intent } from "@/types/worker"
 
 import { txState, useTxState } from "./use-tx-state"
 import { useWorker } from "./use-worker"
 
 export const useProve = () => {
   const client = useSuiClient()
+  const queryClient = useQueryClient()
   const { prove } = useWorker()
 
-  const getAllLeafs = async () => {
-    const leafs: string[] = []
-    let cursor = null
+  // descriptor of `AssetRegistry`, the allowed tokens of the `Shroud` object
+  // in `allow_token` order with the metadata of their coins
+  const getPoolDescriptor = async () => {
+    const object = await client.getObject({
+      id: contracts.coreId,
+      options: {
+        showContent: true,
+      },
+    })
+    const fields = (object.data as any).content.fields
+    const allowedTokens = await Promise.all(
+      fields.allowed_tokens.map(async (t: any) => {
+        const coinType = `0x${t.fields.name}`
+        const metadata = await client.getCoinMetadata({ coinType })
+        if (!metadata) {
+          throw new Error(`No coin metadata of ${coinType}`)
+        }
+        return {
+          coin_type: coinType,
+          symbol: metadata.symbol,
+          decimals: metadata.decimals,
+        }
+      })
+    )
+    return JSON.stringify({
+      allowed_token_length: Number(fields.allowed_token_length),
+      allowed_tokens: allowedTokens,
+    })
+  }
+
+  // `core` events since `cursor`, the pages the worker syncs the tree with
+  const getEventPages = async (cursor: EventId | null) => {
+    const pages: PaginatedEvents[] = []
     while (true) {
-      const events = await client.queryEvents({
+      const page = await client.queryEvents({
         query: {
-          MoveEventType: `${contracts.packageId}::core::LeafInserted`,
+          MoveEventModule: { package: contracts.packageId, module: "core" },
         },
+        cursor,
         limit: 100,
-        order: "descending",
+        order: "ascending",
       })
-
-      for (const event of events.data) {
-        const parsed = event.parsedJson as {
-          index: string
-          value: string
-          new_root: string
-        }
-        leafs.push(
-          toHex(BigInt(parsed.value), {
-            size: 32,
-          }).replace(/^0x/, "")
-        )
-      }
-      cursor = events.nextCursor
-      if (!events.hasNextPage) {
+      pages.push(page)
+      cursor = page.nextCursor ?? cursor
+      if (!page.hasNextPage) {
         break
       }
     }
-    leafs.reverse()
-    return leafs
+    return pages
   }
 
   return useMutation({
     mutationFn: async ({
       account,
-      diffs,
-      isPublic,
+      intent,
     }: {
       account: Uint8Array
-      diffs: Partial<Record<keyof typeof CURRENCY, string>>
-      isPublic: boolean
+      intent: Intent
     }) => {
-      const leafs = await getAllLeafs()
-      txState().setMerkleTreeSize(leafs.length)
+      // tokens are only allowed before the first leaf, the pool never changes
+      const pool = await queryClient.fetchQuery({
+        queryKey: ["pool-descriptor"],
+        queryFn: getPoolDescriptor,
+        staleTime: Infinity,
+      })
+      const cached = await loadSyncedTree()
+      const pages = await getEventPages(cached?.cursor ?? null)
       const pk = await fetch("/api/pk").then((r) => r.json())
       txState().setProvingKeySize(pk.length / 2 - 1)
-      const diffsArray = CURRENCY_LIST.map((c) => {
-        const cur = CURRENCY[c]
-        return BigInt(
-          new BigNumber(diffs[c] ?? 0)
-            .shiftedBy(cur.decimals)
-            .integerValue(BigNumber.ROUND_FLOOR)
-            .toString()
-        )
-      })
-      console.log("Diff", diffsArray)
-      const proof = await prove({
+      const { proof, state, cursor, leafs } = await prove({
         account,
-        leafs,
+        state: cached?.state ?? null,
+        pages,
         pk_bytes: fromHex(pk as Hex, "bytes"),
-        diffs: new BigInt64Array(diffsArray),
-        is_public: isPublic,
-        aux: null,
+        pool,
+        intent,
+        auditor: contracts.auditorKey,
+        package_id: contracts.packageId,
+        shroud_id: contracts.coreId,
       })
+      await saveSyncedTree({ state, cursor })
+      txState().setMerkleTreeSize(leafs)
       txState().setProof(proof)
       return proof
     },
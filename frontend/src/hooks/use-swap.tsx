@@ -24,6 +24,7 @@ export const useSwap = () => {
     updateTreeIndex,
     updateLastActiveSeq,
     updateNullifier,
+    updateHistory,
     addHistory,
     getInternalAccount,
   } = useInternalWallet()
@@ -48,15 +49,15 @@ export const useSwap = () => {
         throw new Error("No current account")
       }
 
+      // coinOut leaves the note for at least minimumReceived of coinIn
       const fullAmountIn = new BigNumber(minimumReceivedStr)
         .shiftedBy(CURRENCY[coinIn].decimals)
         .integerValue(BigNumber.ROUND_FLOOR)
-        .toString()
-      const negAmountOut = new BigNumber(amountOutStr).negated().toString()
-      const fullAmountOut = new BigNumber(negAmountOut)
+        .toFixed()
+      const fullAmountOut = new BigNumber(amountOutStr)
         .shiftedBy(CURRENCY[coinOut].decimals)
         .integerValue(BigNumber.ROUND_FLOOR)
-        .toString()
+        .toFixed()
 
       txState().startOperation({
         type: "swap",
@@ -68,25 +69,18 @@ export const useSwap = () => {
 
       const proof = await prove.mutateAsync({
         account: await getInternalAccount(currentAccount.address),
-        diffs: {
-          [coinIn]: minimumReceived,
-          [coinOut]: negAmountOut,
+        intent: {
+          type: "swap",
+          from: CURRENCY[coinOut].coinType,
+          to: CURRENCY[coinIn].coinType,
+          amount: fullAmountOut,
+          min_out: fullAmountIn,
         },
-        isPublic: false,
       })
 
       const tx = await fetch("/api/proxy-swap", {
         method: "POST",
-        body: JSON.stringify({
-          coinIn: CURRENCY[coinIn].coinType,
-          coinOut: CURRENCY[coinOut].coinType,
-          amountOut: fullAmountOut,
-          minimumReceived: fullAmountIn,
-          currentRoot: proof.merkleRoot,
-          nullifier: proof.nullifier,
-          newLeaf: proof.afterLeaf,
-          proof: proof.proof,
-        }),
+        body: JSON.stringify({ calls: proof.calls }),
       })
 
       const { digest } = await tx.json()
@@ -111,7 +105,8 @@ export const useSwap = () => {
       updateTreeIndex(currentAccount.address, treeIndex)
       updateLastActiveSeq(currentAccount.address, Date.now())
       updateNullifier(currentAccount.address, proof.afterNullifier)
-      incDecBalance(currentAccount.address, coinOut, fullAmountOut, false)
+      updateHistory(currentAccount.address, proof.counter, proof.historyRoot)
+      incDecBalance(currentAccount.address, coinOut, `-${fullAmountOut}`, false)
       incDecBalance(currentAccount.address, coinIn, fullAmountIn, false)
       addHistory(currentAccount.address, {
         type: "swap",
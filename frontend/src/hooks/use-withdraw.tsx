@@ -8,10 +8,10 @@ import { useMutation, useQueryClient } from "@tanstack/react-query"
 import BigNumber from "bignumber.js"
 import { ExternalLink } from "lucide-react"
 import { toast } from "sonner"
-import { fromHex } from "viem"
 
 import { contracts } from "@/config/contract"
 import { CURRENCY } from "@/config/currency"
+import { addMoveCalls } from "@/lib/move-calls"
 import { network } from "@/components/provider"
 
 import { useInternalWallet } from "./use-internal-wallet"
@@ -32,6 +32,7 @@ export const useWithdraw = () => {
     updateTreeIndex,
     updateLastActiveSeq,
     updateNullifier,
+    updateHistory,
     addHistory,
     getInternalAccount,
   } = useInternalWallet()
@@ -50,12 +51,11 @@ export const useWithdraw = () => {
         throw new Error("No current account")
       }
 
-      const negAmount = new BigNumber(amountStr).negated().toString()
       const cur = CURRENCY[currency]
-      const fullAmount = new BigNumber(negAmount)
+      const fullAmount = new BigNumber(amountStr)
         .shiftedBy(cur.decimals)
         .integerValue(BigNumber.ROUND_FLOOR)
-        .toString()
+        .toFixed()
 
       txState().startOperation({
         type: "withdraw",
@@ -65,25 +65,21 @@ export const useWithdraw = () => {
 
       const proof = await prove.mutateAsync({
         account: await getInternalAccount(currentAccount.address),
-        diffs: {
-          [currency]: negAmount,
+        intent: {
+          type: "withdraw",
+          coin_type: cur.coinType,
+          amount: fullAmount,
         },
-        isPublic: true,
       })
 
       const tx = new Transaction()
-      const [coin] = tx.moveCall({
-        target: `${contracts.packageId}::core::withdraw`,
-        typeArguments: [cur.coinType],
-        arguments: [
-          tx.object(contracts.coreId),
-          tx.pure.u64(fullAmount.replace(/^-/, "")),
-          tx.pure.u256(fromHex(proof.merkleRoot, "bigint")),
-          tx.pure.u256(fromHex(proof.nullifier, "bigint")),
-          tx.pure.u256(fromHex(proof.afterLeaf, "bigint")),
-          tx.pure.vector("u8", fromHex(proof.proof, "bytes")),
-        ],
-      })
+      const [withdrawal] = await addMoveCalls(
+        tx,
+        proof.calls,
+        client,
+        currentAccount.address
+      )
+      const [coin] = withdrawal
       tx.transferObjects([coin], currentAccount.address)
 
       const txs = await sae.mutateAsync({
@@ -110,7 +106,8 @@ export const useWithdraw = () => {
       updateTreeIndex(currentAccount.address, treeIndex)
       updateLastActiveSeq(currentAccount.address, Date.now())
       updateNullifier(currentAccount.address, proof.afterNullifier)
-      incDecBalance(currentAccount.address, currency, fullAmount, false)
+      updateHistory(currentAccount.address, proof.counter, proof.historyRoot)
+      incDecBalance(currentAccount.address, currency, `-${fullAmount}`, false)
       addHistory(currentAccount.address, {
         type: "withdraw",
         coin: currency,
@@ -1,6 +1,6 @@
 import { useEffect, useRef } from "react"
 
-import { Proof, ProveParams } from "@/types/worker"
+import { ProveParams, ProveResult } from "@/types/worker"
 
 export const useWorker = () => {
   const workerRef = useRef<Worker>(null)
@@ -12,14 +12,14 @@ export const useWorker = () => {
     }
   }, [])
 
-  const prove = async (params: ProveParams): Promise<Proof> => {
+  const prove = async (params: ProveParams): Promise<ProveResult> => {
     const worker = workerRef.current
     if (!worker) {
       throw new Error("Worker not initialized")
     }
 
     return new Promise((resolve, reject) => {
-      const messageHandler = (event: MessageEvent<Proof>) => {
+      const messageHandler = (event: MessageEvent<ProveResult>) => {
         worker.removeEventListener("message", messageHandler)
         resolve(event.data)
       }
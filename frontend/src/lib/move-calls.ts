@@ -0,0 +1,79 @@
+import type { SuiClient } from "@mysten/sui/client"
+import type {
+  Transaction,
+  TransactionArgument,
+  TransactionResult,
+} from "@mysten/sui/transactions"
+import { fromHex } from "viem"
+
+import type { MoveArg, MoveCall } from "@/types/worker"
+
+// Coin of exactly `amount` of `coinType`, split from the coins of `owner`
+const splitCoin = async (
+  tx: Transaction,
+  client: SuiClient,
+  owner: string,
+  coinType: string,
+  amount: string
+) => {
+  const coins = await client.getCoins({ owner, coinType })
+  if (!coins.data.length) {
+    throw new Error("No coins")
+  }
+  if (coins.data.length > 1) {
+    tx.mergeCoins(
+      coins.data[0].coinObjectId,
+      coins.data.slice(1).map((c) => c.coinObjectId)
+    )
+  }
+  const [coin] = tx.splitCoins(coins.data[0].coinObjectId, [amount])
+  return coin
+}
+
+const toArgument = async (
+  tx: Transaction,
+  arg: MoveArg,
+  results: TransactionResult[],
+  client: SuiClient,
+  owner: string
+): Promise<TransactionArgument> => {
+  switch (arg.kind) {
+    case "object":
+      return tx.object(arg.id)
+    case "result":
+      return results[arg.index]
+    case "coin":
+      return splitCoin(tx, client, owner, arg.coin_type, arg.amount)
+    case "u64":
+      return tx.pure.u64(arg.value)
+    case "u256":
+      return tx.pure.u256(fromHex(`0x${arg.value}`, "bigint"))
+    case "bytes":
+      return tx.pure.vector("u8", fromHex(`0x${arg.value}`, "bytes"))
+  }
+}
+
+// Adds the calls `proveIntent` returns to `tx`, splitting coin arguments from
+// the coins of `owner`. Returns the result of every call, by index
+export const addMoveCalls = async (
+  tx: Transaction,
+  calls: MoveCall[],
+  client: SuiClient,
+  owner: string
+) => {
+  const results: TransactionResult[] = []
+  for (const call of calls) {
+    const args: TransactionArgument[] = []
+    for (const arg of call.arguments) {
+      args.push(await toArgument(tx, arg, results, client, owner))
+    }
+    results.push(
+      tx.moveCall({
+        target: call.target,
+        typeArguments: call.type_arguments,
+        arguments: args,
+      })
+    )
+  }
+  return results
+}
@@ -0,0 +1,41 @@
+import type { EventId } from "@mysten/sui/client"
+
+import { contracts } from "@/config/contract"
+
+// Synced `State` of the pool tree, kept in IndexedDB since it outgrows
+// localStorage once the tree has a few thousand leaves
+export type SyncedTree = {
+  state: Uint8Array
+  cursor: EventId | null
+}
+
+const DB_NAME = "shroud-tree"
+const STORE = "trees"
+const KEY = `${contracts.packageId}-${contracts.version}`
+
+const openDb = () =>
+  new Promise<IDBDatabase>((resolve, reject) => {
+    const request = indexedDB.open(DB_NAME, 1)
+    request.onupgradeneeded = () => request.result.createObjectStore(STORE)
+    request.onsuccess = () => resolve(request.result)
+    request.onerror = () => reject(request.error)
+  })
+
+export const loadSyncedTree = async (): Promise<SyncedTree | null> => {
+  const db = await openDb()
+  return new Promise((resolve, reject) => {
+    const request = db.transaction(STORE).objectStore(STORE).get(KEY)
+    request.onsuccess = () => resolve((request.result as SyncedTree) ?? null)
+    request.onerror = () => reject(request.error)
+  })
+}
+
+export const saveSyncedTree = async (tree: SyncedTree) => {
+  const db = await openDb()
+  return new Promise<void>((resolve, reject) => {
+    const tx = db.transaction(STORE, "readwrite")
+    tx.objectStore(STORE).put(tree, KEY)
+    tx.oncomplete = () => resolve()
+    tx.onerror = () => reject(tx.error)
+  })
+}
@@ -1,3 +1,4 @@
+import type { EventId, PaginatedEvents } from "@mysten/sui/client"
 import { Hex } from "viem"
 
 export type Proof = {
@@ -9,13 +10,56 @@ export type Proof = {
   diffHash: Hex
   proof: Hex
   publicInputs: Hex
+  memo: Hex
+  counter: number
+  historyRoot: Hex
+  escrow: Hex
+  // calls submitting the proof, see `addMoveCalls`
+  calls: MoveCall[]
 }
 
+// argument of a `MoveCall` as `proveIntent` returns it, hex without a prefix
+// and amounts as decimal strings
+export type MoveArg =
+  | { kind: "object"; id: string }
+  | { kind: "result"; index: number }
+  | { kind: "coin"; coin_type: string; amount: string }
+  | { kind: "u64"; value: string }
+  | { kind: "u256"; value: string }
+  | { kind: "bytes"; value: string }
+
+export type MoveCall = {
+  target: string
+  type_arguments: string[]
+  arguments: MoveArg[]
+}
+
+// amounts are decimal strings of base units
+export type Intent =
+  | { type: "deposit"; coin_type: string; amount: string }
+  | { type: "withdraw"; coin_type: string; amount: string }
+  | { type: "swap"; from: string; to: string; amount: string; min_out: string }
+  | { type: "refresh" }
+
 export type ProveParams = {
   account: Uint8Array
-  leafs: string[]
+  // synced tree from an earlier proof, null to sync from the first event
+  state: Uint8Array | null
+  // `core` events since the cursor of `state`, in ascending order
+  pages: PaginatedEvents[]
   pk_bytes: Uint8Array
-  diffs: BigInt64Array
-  is_public: boolean
-  aux?: string | null
+  pool: string
+  intent: Intent
+  auditor?: string | null
+  package_id: string
+  shroud_id: string
+}
+
+export type ProveResult = {
+  proof: Proof
+  // `state` with `pages` applied, to pass to the next proof
+  state: Uint8Array
+  cursor: EventId | null
+  // number of leaves of the synced tree
+  leafs: number
 }
@@ -1,20 +1,61 @@
-import type { ProveParams } from "@/types/worker"
+import type {
+  MoveCall,
+  Proof,
+  ProveParams,
+  ProveResult,
+} from "@/types/worker"
 import { getWasm } from "@/lib/utils"
 
+// serde_wasm_bindgen returns JSON objects as maps
+const fromMaps = (value: unknown): unknown => {
+  if (value instanceof Map) {
+    return Object.fromEntries(
+      Array.from(value, ([k, v]) => [k, fromMaps(v)])
+    )
+  }
+  return Array.isArray(value) ? value.map(fromMaps) : value
+}
+
 addEventListener("message", async (event: MessageEvent<ProveParams>) => {
   const data = event.data
   console.log("START PROVING")
   const wasm = await getWasm()
 
   const account = wasm.Account.import(data.account)
-  const state = wasm.State.new(account)
-  state.setLeafs(data.leafs)
+  let state: InstanceType<typeof wasm.State>
+  if (data.state) {
+    state = wasm.State.import(data.state)
+    state.setAccount(account)
+  } else {
+    state = wasm.State.new(account)
+  }
+
+  // rebuild the tree from the new events only, checking every root
+  const sync = new wasm.Synchronizer(data.package_id, state)
+  for (const page of data.pages) {
+    sync.applyPage(state, page)
+  }
+  sync.free()
+  // proving takes the state, keep the synced tree for the next proof first
+  const synced = state.export()
+  const cursor = state.getCursor() ?? null
+  const leafs = state.getLeafsLength()
+
+  const registry = wasm.AssetRegistry.fromJson(data.pool)
 
   const _proof = new Map(
-    wasm.prove(state, data.pk_bytes, data.diffs, data.is_public, data.aux)
+    wasm.proveIntent(
+      state,
+      data.pk_bytes,
+      registry,
+      data.intent,
+      data.auditor,
+      data.package_id,
+      data.shroud_id
+    )
   )
 
-  const proof = {
+  const proof: Proof = {
     afterLeaf: `0x${_proof.get("after_leaf")}`,
     afterNullifier: `0x${_proof.get("after_nullifier")}`,
     nullifier: `0x${_proof.get("nullifier")}`,
@@ -23,7 +64,18 @@ addEventListener("message", async (event: MessageEvent<ProveParams>) => {
     proof: `0x${_proof.get("proof")}`,
     address: `0x${_proof.get("address")}`,
     publicInputs: `0x${_proof.get("public_inputs")}`,
+    memo: `0x${_proof.get("memo")}`,
+    counter: Number(_proof.get("counter")),
+    historyRoot: `0x${_proof.get("history_root")}`,
+    escrow: `0x${_proof.get("escrow")}`,
+    calls: fromMaps(_proof.get("calls")) as MoveCall[],
   }
 
-  postMessage(proof)
+  const result: ProveResult = {
+    proof,
+    state: synced,
+    cursor,
+    leafs,
+  }
+  postMessage(result)
 })